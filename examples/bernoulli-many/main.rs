@@ -1,5 +1,5 @@
 use cmdstan::*;
-use std::{env, path::PathBuf};
+use std::env;
 
 fn main() {
     // Of course, one need not rely on an environment variable, but
@@ -11,7 +11,7 @@ fn main() {
     // this example utilizes the current working directory so that it
     // may be run from within the repository using `cargo run
     // --example bernoulli` (from crate root)
-    let mut path = PathBuf::from(env::current_dir().unwrap());
+    let mut path = env::current_dir().unwrap();
     path.push("examples");
     path.push("bernoulli");
 
@@ -24,40 +24,28 @@ fn main() {
         .compile::<[_; 0], &str>(&program, [])
         .expect("Something went wrong with compilation");
 
+    // Each label is inserted into the output file name, hence,
+    // the files will be `bernoulli-many/output_sample.csv`, etc.
     let tree = ArgTree::builder()
         .data(Data::builder().file(data_file))
+        .output(Output::builder().file(path.join("output.csv")))
         .build();
 
     // This supplies the defaults for each method
     let methods = [
-        SampleBuilder::new().build(),
-        OptimizeBuilder::new().build(),
-        VariationalBuilder::new().build(),
-        PathfinderBuilder::new().build(),
+        ("sample", SampleBuilder::new().build()),
+        ("optimize", OptimizeBuilder::new().build()),
+        ("variational", VariationalBuilder::new().build()),
+        ("pathfinder", PathfinderBuilder::new().build()),
     ];
-    let out_files = [
-        "sample.csv",
-        "optimize.csv",
-        "variational.csv",
-        "pathfinder.csv",
-    ];
-    let trees = methods
-        .into_iter()
-        .zip(out_files.into_iter())
-        .map(move |(method, file)| {
-            let mut tree = tree.clone();
-            tree.method = method;
-            tree.output = Output::builder().file(path.join(file)).build();
-            tree
-        });
 
-    for tree in trees {
-        match model.call(&tree) {
+    for (label, result) in model.run_grid(&tree, methods) {
+        match result {
             Ok(output) => {
-                println!("{:#?}", output.output());
+                println!("{label}: {:#?}", output.output());
             }
             Err(e) => {
-                println!("Something seems to have gone wrong...\n{:#?}", e);
+                println!("{label}: Something seems to have gone wrong...\n{:#?}", e);
             }
         }
     }
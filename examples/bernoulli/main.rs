@@ -1,6 +1,6 @@
 use cmdstan::stansummary::StanSummaryOptions;
 use cmdstan::*;
-use std::{env, path::PathBuf};
+use std::env;
 
 fn main() {
     // Of course, one need not rely on an environment variable, but
@@ -12,13 +12,11 @@ fn main() {
     // this example utilizes the current working directory so that it
     // may be run from within the repository using `cargo run
     // --example bernoulli` (from crate root)
-    let mut path = PathBuf::from(env::current_dir().unwrap());
+    let mut path = env::current_dir().unwrap();
     path.push("examples");
     path.push("bernoulli");
 
     let model_file = path.join("bernoulli.stan");
-    let data_file = path.join("bernoulli.data.json");
-    let output_file = path.join("output.csv");
 
     let program = StanProgram::try_from(model_file.as_ref()).expect("Stan program does not exist");
     let model = cmdstan
@@ -41,18 +39,20 @@ fn main() {
                 )
                 .num_chains(4),
         )
-        .data(Data::builder().file(data_file))
+        .data("bernoulli.data.json")
         .id(2)
         .init("1")
         .random(Random::builder().seed(12345))
         .output(
             Output::builder()
                 .sig_figs(4)
-                .file(output_file)
-                .profile_file(path.join("profile.csv")),
+                .file("output.csv")
+                .profile_file("profile.csv"),
         )
         .num_threads(48)
-        .build();
+        .build()
+        // Relative files are placed in the directory of the example.
+        .with_base(&path);
 
     // Automatically logs stdout/stderr
     let output = model.call(&tree).expect("Some problem with the executable");
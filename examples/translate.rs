@@ -1,5 +1,4 @@
-use cmdstan::argtree::ArgTree;
-use cmdstan::translate::Translate;
+use cmdstan::prelude::*;
 
 fn main() {
     let x = ArgTree::default();
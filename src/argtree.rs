@@ -1,7 +1,12 @@
 use crate::builder::Builder;
+use crate::draws::Adaptation;
+use crate::error::Error;
 use crate::method::*;
 use crate::translate::Translate;
 use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::{fmt, io};
 
 #[derive(Debug, PartialEq, Clone, Translate, Builder)]
 #[non_exhaustive]
@@ -18,7 +23,7 @@ pub struct ArgTree {
     pub data: Data,
     /// Initialization method: `"x"` initializes randomly between [-x,
     /// x], `"0"` initializes to `0`, anything else identifies a file of
-    /// values.
+    /// values; see [`Init`] for the typed equivalent.
     /// Valid values: All.
     /// Defaults to `"2"`.
     #[defaults_to = "2"]
@@ -32,12 +37,17 @@ pub struct ArgTree {
     /// Defaults to `1` or the value of the `STAN_NUM_THREADS` environment variable if set.
     #[defaults_to = 1]
     pub num_threads: i32,
+    /// Additional arguments, appended verbatim after all others, e.g. to
+    /// use an option of CmdStan which is not (yet) modeled by this type.
+    /// These are neither validated nor recognized by the parser.
+    /// Defaults to none.
+    pub extra_args: Vec<OsString>,
 }
 
 /// Match the behavior of CmdStan path handling, which
 /// includes substitution of a `"csv"` suffix if no `'.'`
 /// is present in the input.
-fn rsplit_file_at_dot<'a>(file: &'a OsStr) -> (&'a OsStr, &'a OsStr) {
+fn rsplit_file_at_dot(file: &OsStr) -> (&OsStr, &OsStr) {
     let bytes = file.as_encoded_bytes();
     let mut iter = bytes.rsplitn(2, |b| *b == b'.');
 
@@ -61,17 +71,50 @@ fn rsplit_file_at_dot<'a>(file: &'a OsStr) -> (&'a OsStr, &'a OsStr) {
     (prefix, suffix)
 }
 
+/// Rejoin `file` after splitting it using the same rules as CmdStan,
+/// which only differs from `file` if there is no `'.'`.
+fn resolve_file(file: &OsStr) -> OsString {
+    let (prefix, suffix) = rsplit_file_at_dot(file);
+    let mut s = prefix.to_os_string();
+    s.push(".");
+    s.push(suffix);
+    s
+}
+
+/// Insert `_{label}` between the prefix and suffix of `file`, using
+/// the same splitting rules as CmdStan.
+fn label_file(file: &OsStr, label: &str) -> OsString {
+    let (prefix, suffix) = rsplit_file_at_dot(file);
+    let mut s = prefix.to_os_string();
+    s.push(format!("_{label}."));
+    s.push(suffix);
+    s
+}
+
 /** File-handling utilities. */
 impl ArgTree {
-    fn files<F>(&self, f: F) -> Vec<OsString>
-    where
-        F: Fn(&ArgTree) -> &OsStr,
-    {
+    /// Return a copy of `self` in which the output, diagnostic (if
+    /// any) and profile files have `_{label}` inserted before the
+    /// suffix, e.g. `output.csv` becomes `output_{label}.csv`.
+    /// This is the means by which many calls derived from a single
+    /// tree are prevented from clobbering one another's files.
+    pub fn with_label(&self, label: &str) -> ArgTree {
+        let mut tree = self.clone();
+        tree.output.file = label_file(&self.output.file, label);
+        if !self.output.diagnostic_file.is_empty() {
+            tree.output.diagnostic_file = label_file(&self.output.diagnostic_file, label);
+        }
+        tree.output.profile_file = label_file(&self.output.profile_file, label);
+        tree
+    }
+
+    /// Return the file path(s) implied by `file`, with the chain id
+    /// inserted if multiple chains are run.
+    fn files(&self, file: &OsStr) -> Vec<OsString> {
         let mut files: Vec<OsString> = Vec::new();
-        let file = f(self);
         let (prefix, suffix) = rsplit_file_at_dot(file);
-        match &self.method {
-            Method::Sample { num_chains, .. } if *num_chains != 1 => {
+        match self.method.num_chains() {
+            num_chains if num_chains != 1 => {
                 let id = self.id;
                 (id..id + num_chains).for_each(|id| {
                     let mut s = prefix.to_os_string();
@@ -80,28 +123,67 @@ impl ArgTree {
                     files.push(s);
                 });
             }
-            _ => {
-                let mut s = prefix.to_os_string();
-                s.push(".");
-                s.push(suffix);
-                files.push(s);
-            }
+            _ => files.push(resolve_file(file)),
         }
         files
     }
 
+    /// Return the output file to which CmdStan will write a single
+    /// chain, i.e. `self.output.file` with CmdStan's normalization
+    /// applied: the file is split at the last `'.'` and rejoined, with
+    /// the suffix `csv` substituted only if there is no `'.'` at all.
+    /// For example,
+    ///
+    /// | `file`      | resolved        |
+    /// |-------------|-----------------|
+    /// | `output`    | `output.csv`    |
+    /// | `output.`   | `output.`       |
+    /// | `out.txt`   | `out.txt`       |
+    /// | `a.b.c`     | `a.b.c`         |
+    /// | `.csv`      | `.csv`          |
+    /// | `foo/bar/`  | `foo/bar/.csv`  |
+    /// | `foo.d/bar` | `foo.d/bar`     |
+    /// | (empty)     | `.csv`          |
+    ///
+    /// Note that the last row is only a prediction; CmdStan may reject
+    /// an empty file. When multiple chains are run, the chain id is
+    /// inserted prior to the last `'.'`, see [`ArgTree::output_files`].
+    pub fn resolved_output_file(&self) -> OsString {
+        resolve_file(&self.output.file)
+    }
+
     /// Return the output file path(s), as implied by the configuration of `self`.
     /// Typically, these will not be literal files on the filesystem.
+    /// These are the files planned prior to a call; for those which
+    /// exist after a call, see [`CmdStanOutput::output_files`](crate::CmdStanOutput::output_files).
     pub fn output_files(&self) -> Vec<OsString> {
-        self.files(|tree| &tree.output.file)
+        self.files(&self.output.file)
+    }
+    /// Return the output file path(s) of the chains with the given `ids`,
+    /// in the order of `ids`, as implied by the configuration of `self`.
+    /// The chains are numbered from `self.id`; ids of chains which
+    /// would not be run are ignored.
+    pub fn output_files_for_chains(&self, ids: &[i32]) -> Vec<OsString> {
+        let files = self.output_files();
+        ids.iter()
+            .filter_map(|id| {
+                let i = usize::try_from(id.checked_sub(self.id)?).ok()?;
+                files.get(i).cloned()
+            })
+            .collect()
     }
     /// Return the diagnostic file path(s), as implied by the configuration of `self`.
     /// Typically, these will not be literal files on the filesystem.
+    ///
+    /// Only `sample` writes one diagnostic file per chain. Every other method
+    /// which writes a diagnostic file (e.g. `variational`, `pathfinder`,
+    /// `optimize`) writes a single file, regardless of `num_paths` in the
+    /// case of `pathfinder`, hence, the chain id is never inserted.
     pub fn diagnostic_files(&self) -> Vec<OsString> {
         if self.output.diagnostic_file.is_empty() {
             Vec::new()
         } else {
-            self.files(|tree| &tree.output.diagnostic_file)
+            self.files(&self.output.diagnostic_file)
         }
     }
     /// Return the profile file path(s), as implied by the configuration of `self`.
@@ -109,6 +191,107 @@ impl ArgTree {
     pub fn profile_files(&self) -> Vec<OsString> {
         vec![self.output.profile_file.clone()]
     }
+    /// Return the metric file path(s) which CmdStan looks for, as
+    /// implied by the configuration of `self`. This is empty unless
+    /// the method is `sample` using HMC with a non-empty `metric_file`.
+    /// When multiple chains are run, these are the per-chain files
+    /// (e.g. `metric_1.json`, `metric_2.json` for `metric.json`) which,
+    /// if all exist, CmdStan uses in preference to `metric_file`.
+    pub fn metric_files(&self) -> Vec<OsString> {
+        match &self.method {
+            Method::Sample {
+                algorithm: SampleAlgorithm::Hmc { metric_file, .. },
+                num_chains,
+                ..
+            } if !metric_file.is_empty() => {
+                if *num_chains == 1 {
+                    vec![metric_file.clone()]
+                } else {
+                    self.files(metric_file)
+                }
+            }
+            _ => Vec::new(),
+        }
+    }
+    /// Write one inverse metric per chain to the files given by
+    /// [`ArgTree::metric_files`], e.g. to warm-start each chain from the
+    /// metric adapted by the respective chain of a previous call.
+    /// An error of kind `InvalidInput` is returned if the number of
+    /// metrics differs from the number of files.
+    pub fn write_metric_files(&self, metrics: &[InvMetric]) -> io::Result<()> {
+        let files = self.metric_files();
+        if files.len() != metrics.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "expected {} metric(s), one per metric file, got {}",
+                    files.len(),
+                    metrics.len()
+                ),
+            ));
+        }
+        for (file, metric) in files.iter().zip(metrics) {
+            metric.write(file)?;
+        }
+        Ok(())
+    }
+    /// Return one tree per element of `adaptations`, each of which runs
+    /// a single chain warm-started from the respective step size and
+    /// inverse metric, e.g. as adapted by the chains of a previous call
+    /// (see [`CmdStanOutput::adaptations`](crate::CmdStanOutput::adaptations)).
+    /// CmdStan applies a single `stepsize` to every chain of a process,
+    /// hence, chains with distinct step sizes must be run as distinct
+    /// processes; the trees may be called concurrently, e.g. by
+    /// [`CmdStanModel::call_in_dir`](crate::CmdStanModel::call_in_dir)
+    /// from many threads.
+    ///
+    /// The `i`-th tree has id `self.id + i`, output, diagnostic and profile
+    /// files labelled with that id (see [`ArgTree::with_label`]), and
+    /// the metric file which `self` implies for that chain (see
+    /// [`ArgTree::metric_files`]), to which the inverse metric is written.
+    /// The metric of `self` should agree with that of `adaptations`.
+    /// An error of kind `InvalidInput` is returned unless the method is
+    /// `sample` using HMC with a non-empty `metric_file`.
+    pub fn warm_start_chains(&self, adaptations: &[Adaptation]) -> io::Result<Vec<ArgTree>> {
+        let metric_file = match &self.method {
+            Method::Sample {
+                algorithm: SampleAlgorithm::Hmc { metric_file, .. },
+                ..
+            } if !metric_file.is_empty() => metric_file.clone(),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "warm-start requires `sample` using HMC with a non-empty `metric_file`",
+                ))
+            }
+        };
+        adaptations
+            .iter()
+            .zip(self.id..)
+            .map(|(adaptation, id)| {
+                let label = id.to_string();
+                let mut tree = self.with_label(&label);
+                tree.id = id;
+                if let Method::Sample {
+                    algorithm:
+                        SampleAlgorithm::Hmc {
+                            metric_file: file,
+                            stepsize,
+                            ..
+                        },
+                    num_chains,
+                    ..
+                } = &mut tree.method
+                {
+                    *file = label_file(&metric_file, &label);
+                    adaptation.inv_metric.write(&*file)?;
+                    *stepsize = adaptation.stepsize;
+                    *num_chains = 1;
+                }
+                Ok(tree)
+            })
+            .collect()
+    }
     /// Return the single-path pathfinder file path(s), if
     /// appropriate, as implied by the configuration of `self`.
     /// Typically, these will not be literal files on the filesystem.
@@ -154,7 +337,510 @@ impl ArgTree {
     }
 }
 
+impl ArgTree {
+    /// If `num_threads == -1`, substitute the number of threads
+    /// available to this process, as reported by
+    /// [`std::thread::available_parallelism`], such that the recorded
+    /// arguments reflect the thread count actually used. Otherwise, or
+    /// if the number cannot be determined, this is a no-op.
+    ///
+    /// CmdStan interprets `-1` as "use all cores", as reported by the
+    /// hardware; in contrast, `available_parallelism` respects CPU affinity
+    /// and container quotas (where these can be detected), hence, the
+    /// resolved count may be smaller than that which CmdStan would use.
+    /// This is opt-in: `-1` is passed through as-is unless this method is called.
+    pub fn resolve_threads(&mut self) {
+        if self.num_threads == -1 {
+            if let Ok(n) = std::thread::available_parallelism() {
+                self.num_threads = i32::try_from(n.get()).unwrap_or(i32::MAX);
+            }
+        }
+    }
+
+    /// Return `self` with the relative files of [`ArgTree::data`] and
+    /// [`ArgTree::output`] joined onto `base`; see [`Data::with_base`]
+    /// and [`Output::with_base`]. Files of the method (e.g. `metric_file`)
+    /// and `init` (which need not be a file) are unchanged.
+    pub fn with_base<P: AsRef<Path>>(mut self, base: P) -> Self {
+        let base = base.as_ref();
+        self.data = self.data.with_base(base);
+        self.output = self.output.with_base(base);
+        self
+    }
+
+    /// Return advisories concerning the configuration of threads, given the
+    /// number of threads available to this process, as reported by
+    /// [`std::thread::available_parallelism`] (if it can be determined).
+    /// These are not errors: CmdStan runs regardless, but likely more
+    /// slowly than intended. See [`ArgTree::thread_warnings_for`].
+    pub fn thread_warnings(&self) -> Vec<ThreadWarning> {
+        let available = std::thread::available_parallelism().map_or(0, |n| n.get());
+        self.thread_warnings_for(available)
+    }
+
+    /// Return advisories concerning the configuration of threads, given
+    /// `available` threads, or `0` if unknown. The chains of a single
+    /// call share a pool of `num_threads` threads, hence:
+    /// - a pool larger than the threads available oversubscribes the cores.
+    /// - a pool smaller than `num_chains` cannot run every chain at once.
+    /// - a pool larger than `num_chains` benefits only models which use
+    ///   within-chain parallelism (e.g. `reduce_sum`), and only evenly
+    ///   if each chain receives the same number of threads.
+    ///
+    /// A `num_threads` of `-1` uses every core, hence, is never advised against.
+    pub fn thread_warnings_for(&self, available: usize) -> Vec<ThreadWarning> {
+        let mut warnings = Vec::new();
+        let num_threads = self.num_threads;
+        let num_chains = self.method.num_chains();
+        // Invalid values are left to CmdStan to reject.
+        if num_threads < 1 || num_chains < 1 {
+            return warnings;
+        }
+        if available > 0 && num_threads as usize > available {
+            warnings.push(ThreadWarning::Oversubscribed {
+                num_threads,
+                available,
+            });
+        }
+        if num_threads < num_chains {
+            warnings.push(ThreadWarning::FewerThreadsThanChains {
+                num_threads,
+                num_chains,
+            });
+        } else if num_threads % num_chains != 0 {
+            warnings.push(ThreadWarning::UnevenThreadsPerChain {
+                num_threads,
+                num_chains,
+            });
+        }
+        warnings
+    }
+
+    /// Append the runtime `opencl` arguments to [`ArgTree::extra_args`],
+    /// selecting the OpenCL `platform` and `device`. The model must have
+    /// been compiled with OpenCL support (see
+    /// [`CompileOptions`](crate::compile::CompileOptions)), else the call
+    /// returns an error.
+    pub fn with_opencl(mut self, platform: u32, device: u32) -> Self {
+        self.extra_args.extend([
+            OsString::from("opencl"),
+            OsString::from(format!("platform={platform}")),
+            OsString::from(format!("device={device}")),
+        ]);
+        self
+    }
+
+    /// Return `true` if the `opencl` argument is among [`ArgTree::extra_args`].
+    pub fn uses_opencl(&self) -> bool {
+        self.extra_args.iter().any(|x| x == "opencl")
+    }
+
+    /// Normalize the spelling of values which CmdStan interprets
+    /// identically, such that trees which differ only in such spelling
+    /// compare equal after canonicalization, e.g. a tree parsed from
+    /// a command line and that parsed from the resulting CSV header.
+    ///
+    /// Every field of a tree holds a value, as the builder and the parser
+    /// supply the default of any which is unspecified; hence, trees which
+    /// differ only in whether defaults were given explicitly are already
+    /// equal, as are floating point values, which are parsed rather than
+    /// compared as text. The remaining normalizations are:
+    /// - the output file is resolved as by [`ArgTree::resolved_output_file`],
+    ///   e.g. `output` becomes `output.csv`.
+    /// - a numeric `init` is written as the number it denotes, e.g. `2.0`
+    ///   becomes `2`; otherwise, `init` identifies a file, and is unchanged.
+    ///
+    /// Values which CmdStan resolves at the time of the call, i.e. a `seed`
+    /// of `-1` and a `num_threads` of `-1`, cannot be canonicalized, as
+    /// the resolved values are unknown beforehand; see [`ArgTree::resolve_threads`].
+    pub fn canonicalize(&mut self) {
+        self.output.file = self.resolved_output_file();
+        if let Ok(x) = self.init.to_str().unwrap_or_default().parse::<f64>() {
+            if x.is_finite() {
+                self.init = x.to_string().into();
+            }
+        }
+    }
+
+    /// Return `true` if `self` and `other` are equal after
+    /// [`ArgTree::canonicalize`].
+    pub fn eq_canonical(&self, other: &ArgTree) -> bool {
+        let mut lhs = self.clone();
+        let mut rhs = other.clone();
+        lhs.canonicalize();
+        rhs.canonicalize();
+        lhs == rhs
+    }
+
+    /// Classify `init`; see [`Init`]. An error of kind `Validation`
+    /// is returned if `init` is a number which is not a valid radius.
+    pub fn init_kind(&self) -> Result<Init, Error> {
+        Init::try_from(self.init.as_os_str())
+    }
+
+    /// Return `true` if `self` and `other` are equal, except that
+    /// floating point values `x` and `y` are considered equal if
+    /// `|x - y| <= epsilon * max(1, |x|, |y|)`, i.e. the tolerance is
+    /// absolute for small magnitudes and relative for large magnitudes.
+    ///
+    /// The comparison is of the arguments (see [`Translate::to_args`]),
+    /// hence, is structural: the trees must have the same method, etc.
+    /// An argument is compared with tolerance only if both values parse
+    /// as numbers, and not both as integers; all others, including paths,
+    /// must be identical.
+    pub fn approx_eq(&self, other: &ArgTree, epsilon: f64) -> bool {
+        let lhs = self.to_args();
+        let rhs = other.to_args();
+        lhs.len() == rhs.len()
+            && lhs
+                .iter()
+                .zip(rhs.iter())
+                .all(|(x, y)| x == y || approx_eq_arg(x, y, epsilon))
+    }
+}
+
+/** Echo of the configuration. */
+impl ArgTree {
+    /// Translate `self` to a tree in the format which CmdStan echoes
+    /// to the console (and, with each line prefixed by `# `, to the header
+    /// of a Stan CSV file), less the `(Default)` markers, which depend
+    /// upon the command line rather than the configuration.
+    ///
+    /// This differs from [`Translate::to_tree`] only in the spelling of
+    /// floating point values: CmdStan writes 17 significant digits, as
+    /// by `printf("%.17g")`, e.g. `0.050000000000000003` for `0.05`,
+    /// whereas `to_tree` writes the shortest spelling which round-trips.
+    /// The divergence is intentional, as the shortest spelling is that
+    /// which a user would write. Values which CmdStan echoes as given,
+    /// i.e. `init`, files and other paths, are never respelled.
+    pub fn to_cmdstan_echo(&self) -> OsString {
+        let tree = self.to_tree();
+        let mut s = OsString::with_capacity(tree.len() + 64);
+        for (i, line) in tree.as_encoded_bytes().split(|b| *b == b'\n').enumerate() {
+            if i != 0 {
+                s.push("\n");
+            }
+            match std::str::from_utf8(line).ok().and_then(respell_echo_line) {
+                Some(line) => s.push(line),
+                // SAFETY:
+                // - each fragment only contains content that originated
+                //   from `OsStr::as_encoded_bytes`.
+                // - split with ASCII newline, which is a non-empty UTF-8
+                //   substring.
+                // Thus, the invariants are maintained.
+                None => s.push(unsafe { OsStr::from_encoded_bytes_unchecked(line) }),
+            }
+        }
+        s
+    }
+}
+
+/** Flat export of the configuration. */
+impl ArgTree {
+    /// Flatten `self` to `(path, value)` pairs, in the order of
+    /// [`Translate::to_tree`], where `path` is the names of the enclosing
+    /// arguments joined by `.`, as for [`ArgTreeWithProvenance`], e.g.
+    /// `("method.sample.adapt.delta", "0.8")` or `("data.file", "")`.
+    ///
+    /// The pairs may be written to any `section.field = value` format
+    /// (INI, TOML, logs) without a serialization framework. Values which
+    /// are not valid UTF-8 (i.e. paths) are converted lossily.
+    pub fn to_key_value(&self) -> Vec<(String, String)> {
+        let tree = self.to_tree();
+        let tree = tree.to_string_lossy();
+        // A stack of the enclosing arguments, with the indentation of each.
+        let mut path: Vec<(usize, &str)> = Vec::new();
+        let mut pairs = Vec::new();
+        for line in tree.lines() {
+            let body = line.trim_start();
+            let indent = line.len() - body.len();
+            while path.last().is_some_and(|(n, _)| *n >= indent) {
+                path.pop();
+            }
+            let (name, value) = match body.split_once(" =") {
+                Some((name, value)) => (name, Some(value.strip_prefix(' ').unwrap_or(value))),
+                None => (body.trim_end(), None),
+            };
+            if name.is_empty() {
+                continue;
+            }
+            path.push((indent, name));
+            if let Some(value) = value {
+                let key: Vec<&str> = path.iter().map(|(_, name)| *name).collect();
+                pairs.push((key.join("."), value.to_string()));
+            }
+        }
+        pairs
+    }
+
+    /// Return the fields at which `self` and `other` differ, by
+    /// comparison of [`ArgTree::to_key_value`]: first those of `self`,
+    /// in order, then those present only in `other`. Values are compared
+    /// as spelled, hence, e.g. `init = 2` and `init = 2.0` differ;
+    /// see [`ArgTree::eq_canonical`].
+    pub fn diff(&self, other: &ArgTree) -> Vec<FieldDiff> {
+        let lhs = self.to_key_value();
+        let rhs = other.to_key_value();
+        let find = |pairs: &[(String, String)], path: &str| {
+            pairs
+                .iter()
+                .find(|(x, _)| x == path)
+                .map(|(_, value)| value.clone())
+        };
+        let mut diffs: Vec<FieldDiff> = lhs
+            .iter()
+            .filter_map(|(path, left)| {
+                let right = find(&rhs, path);
+                (right.as_ref() != Some(left)).then(|| FieldDiff {
+                    path: path.clone(),
+                    left: Some(left.clone()),
+                    right,
+                })
+            })
+            .collect();
+        diffs.extend(
+            rhs.into_iter()
+                .filter(|(path, _)| find(&lhs, path).is_none())
+                .map(|(path, right)| FieldDiff {
+                    path,
+                    left: None,
+                    right: Some(right),
+                }),
+        );
+        diffs
+    }
+}
+
+/// A field at which two trees differ; see [`ArgTree::diff`]. A value
+/// is `None` if the field is absent from the tree, e.g. as the trees
+/// have different methods.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FieldDiff {
+    /// The path to the field, as for [`ArgTree::to_key_value`].
+    pub path: String,
+    pub left: Option<String>,
+    pub right: Option<String>,
+}
+
+impl fmt::Display for FieldDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = |x: &Option<String>| x.clone().unwrap_or_else(|| "(absent)".to_string());
+        write!(
+            f,
+            "{}: {} -> {}",
+            self.path,
+            value(&self.left),
+            value(&self.right)
+        )
+    }
+}
+
+/// Respell the value of a `key = value` line of a tree as CmdStan
+/// would echo it, or return `None` if the line is echoed as-is.
+fn respell_echo_line(line: &str) -> Option<String> {
+    let (key, value) = line.split_once(" = ")?;
+    let name = key.trim_start();
+    if name == "init" || name == "file" || name.ends_with("_file") || name.ends_with("_params") {
+        return None;
+    }
+    // `Display` never writes an exponent, hence, only values with a
+    // fractional part may be spelled differently.
+    if !value.contains('.') {
+        return None;
+    }
+    let x = value.parse::<f64>().ok()?;
+    Some(format!("{key} = {}", format_g17(x)))
+}
+
+/// Format `x` as by `printf("%.17g", x)`.
+fn format_g17(x: f64) -> String {
+    fn trim_zeros(s: &str) -> &str {
+        if s.contains('.') {
+            s.trim_end_matches('0').trim_end_matches('.')
+        } else {
+            s
+        }
+    }
+    if !x.is_finite() {
+        return x.to_string();
+    }
+    // The exponent after rounding to 17 significant digits.
+    let e = format!("{x:.16e}");
+    let (mantissa, exp) = e.split_once('e').unwrap();
+    let exp = exp.parse::<i32>().unwrap();
+    if (-4..17).contains(&exp) {
+        let s = format!("{x:.*}", (16 - exp) as usize);
+        trim_zeros(&s).to_string()
+    } else {
+        let sign = if exp < 0 { '-' } else { '+' };
+        format!("{}e{sign}{:02}", trim_zeros(mantissa), exp.abs())
+    }
+}
+
+/// An advisory concerning the configuration of threads, as returned
+/// by [`ArgTree::thread_warnings`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ThreadWarning {
+    /// `num_threads` exceeds the number of threads available.
+    Oversubscribed { num_threads: i32, available: usize },
+    /// `num_threads` is less than `num_chains`, hence, some chains
+    /// wait for others to finish.
+    FewerThreadsThanChains { num_threads: i32, num_chains: i32 },
+    /// `num_threads` is not a multiple of `num_chains`, hence, the
+    /// chains receive unequal numbers of threads.
+    UnevenThreadsPerChain { num_threads: i32, num_chains: i32 },
+}
+
+impl fmt::Display for ThreadWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Oversubscribed {
+                num_threads,
+                available,
+            } => write!(
+                f,
+                "num_threads={num_threads} exceeds the {available} thread(s) available"
+            ),
+            Self::FewerThreadsThanChains {
+                num_threads,
+                num_chains,
+            } => write!(
+                f,
+                "num_threads={num_threads} is less than num_chains={num_chains}; chains will not all run at once"
+            ),
+            Self::UnevenThreadsPerChain {
+                num_threads,
+                num_chains,
+            } => write!(
+                f,
+                "num_threads={num_threads} is not a multiple of num_chains={num_chains}; chains will receive unequal threads"
+            ),
+        }
+    }
+}
+
+/// Allocator of distinct chain ids, which may be shared across threads.
+///
+/// Calls derived from a single tree share its `id` and output files,
+/// hence, if made concurrently, they would clobber one another's files
+/// and, with equal seeds, draw identical random numbers. Each call should
+/// instead be made with the tree produced by [`ChainIdAllocator::assign`].
+#[derive(Debug)]
+pub struct ChainIdAllocator {
+    next: AtomicI32,
+}
+
+impl ChainIdAllocator {
+    /// Return an allocator whose first id is `first`.
+    pub const fn new(first: i32) -> Self {
+        Self {
+            next: AtomicI32::new(first),
+        }
+    }
+
+    /// Allocate `n` consecutive ids, returning the first. Ids are never
+    /// handed out twice, regardless of the thread from which this is called.
+    pub fn allocate(&self, n: i32) -> i32 {
+        self.next.fetch_add(n, Ordering::Relaxed)
+    }
+
+    /// Return a copy of `tree` in which `id` is the first of the
+    /// [`Method::num_chains`] ids newly allocated for the chains of the
+    /// call, and the output, diagnostic and profile files are labelled
+    /// with that id (see [`ArgTree::with_label`]), e.g. `output_3.csv`
+    /// for a single chain, or `output_3_3.csv`, `output_3_4.csv` for two.
+    /// The latter is necessary as CmdStan inserts the chain id only when
+    /// multiple chains are run, and never into the profile file.
+    pub fn assign(&self, tree: &ArgTree) -> ArgTree {
+        let id = self.allocate(tree.method.num_chains());
+        let mut tree = tree.with_label(&id.to_string());
+        tree.id = id;
+        tree
+    }
+}
+
+impl Default for ChainIdAllocator {
+    /// An allocator whose first id is `1`, the default id of CmdStan.
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+/// Compare arguments of the form `key=value` with tolerance on the values,
+/// as described for [`ArgTree::approx_eq`].
+fn approx_eq_arg(x: &OsStr, y: &OsStr, epsilon: f64) -> bool {
+    let (Some(x), Some(y)) = (x.to_str(), y.to_str()) else {
+        return false;
+    };
+    match (x.split_once('='), y.split_once('=')) {
+        (Some((k1, v1)), Some((k2, v2))) if k1 == k2 => {
+            if v1.parse::<i64>().is_ok() && v2.parse::<i64>().is_ok() {
+                return false;
+            }
+            match (v1.parse::<f64>(), v2.parse::<f64>()) {
+                (Ok(a), Ok(b)) => {
+                    let scale = 1.0_f64.max(a.abs()).max(b.abs());
+                    (a - b).abs() <= epsilon * scale
+                }
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+/// An argument tree read from the header of a Stan CSV file, along with
+/// the provenance of each value: CmdStan marks each value which was not
+/// given on the command line with `(Default)`. Produced by
+/// [`ArgTree::from_reader_with_provenance`].
+///
+/// Values are identified by their path in the tree, i.e. the names of
+/// the enclosing arguments joined by `.`, as printed by CmdStan, e.g.
+/// `method`, `method.sample.num_samples`, `method.sample.adapt.engaged`,
+/// `data.file` or `id`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ArgTreeWithProvenance {
+    pub(crate) tree: ArgTree,
+    pub(crate) values: Vec<(String, bool)>,
+}
+
+impl ArgTreeWithProvenance {
+    /// Return a reference to the argument tree.
+    pub fn tree(&self) -> &ArgTree {
+        &self.tree
+    }
+    /// Return the argument tree, discarding the provenance.
+    pub fn into_tree(self) -> ArgTree {
+        self.tree
+    }
+    /// Return whether the value at `path` was a default, or `None`
+    /// if the header does not contain the path.
+    pub fn is_default(&self, path: &str) -> Option<bool> {
+        self.values
+            .iter()
+            .find(|(x, _)| x == path)
+            .map(|(_, is_default)| *is_default)
+    }
+    /// Return the paths of the values which were set explicitly,
+    /// in the order in which they appear in the header.
+    pub fn explicit(&self) -> Vec<&str> {
+        self.values
+            .iter()
+            .filter(|(_, is_default)| !is_default)
+            .map(|(path, _)| path.as_str())
+            .collect()
+    }
+}
+
 /// Input data options
+///
+/// A model with an empty (or no) `data` block requires no data file,
+/// for which the empty `file` (the default) is appropriate. As for every
+/// other argument, it is nonetheless emitted, as `data file=`, which
+/// CmdStan treats identically to the absence of the argument, as the
+/// empty string is its default; thus, the arguments reproduce the tree
+/// exactly.
 #[derive(Debug, PartialEq, Clone, Translate, Builder)]
 #[non_exhaustive]
 #[declare = "data"]
@@ -175,10 +861,158 @@ pub struct Random {
     /// Valid values: non-negative integer < `4294967296` or `-1` to
     /// generate seed from system time.
     /// Defaults to `-1`.
-    #[defaults_to = "crate::consts::NEG1_I64"]
+    #[defaults_to = "-1"]
+    #[no_setter]
     pub seed: i64,
 }
 
+impl Random {
+    /// Check that `seed` is within the range accepted by CmdStan;
+    /// see [`Seed`].
+    pub fn validate(&self) -> Result<(), Error> {
+        Seed::try_from(self.seed).map(|_| ())
+    }
+}
+
+impl RandomBuilder {
+    /// Configure `seed` with the given value, which is within the range
+    /// accepted by CmdStan by construction, e.g. `12345` (a `u32`) or
+    /// [`Seed::from_system_time`].
+    pub fn seed<T: Into<Seed>>(mut self, seed: T) -> Self {
+        self.seed = Some(seed.into().into());
+        self
+    }
+
+    /// Build the `Random` instance, returning an error if the seed
+    /// is out of range (see [`Random::validate`]).
+    pub fn try_build(self) -> Result<Random, Error> {
+        let x = self.build();
+        x.validate()?;
+        Ok(x)
+    }
+}
+
+/// Random number generator seed, which is either `-1` (generate the
+/// seed from the system time), or a non-negative integer < `4294967296`.
+/// Defaults to [`Seed::from_system_time`].
+///
+/// An integer outside of this range is rejected by `TryFrom<i64>`;
+/// CmdStan would otherwise accept it, but not as the seed intended.
+/// [`RandomBuilder::seed`] takes a `Seed`, hence, the builder cannot
+/// be given an out of range seed; a `Seed` converts to the `i64` of
+/// [`Random::seed`].
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Seed(i64);
+
+impl Seed {
+    /// The seed `-1`, i.e. generate the seed from the system time.
+    pub const fn from_system_time() -> Self {
+        Self(-1)
+    }
+    /// The given, fixed seed.
+    pub const fn fixed(seed: u32) -> Self {
+        Self(seed as i64)
+    }
+    /// Return the seed as it appears in the argument tree.
+    pub const fn get(&self) -> i64 {
+        self.0
+    }
+}
+
+impl Default for Seed {
+    fn default() -> Self {
+        Self::from_system_time()
+    }
+}
+
+impl From<u32> for Seed {
+    fn from(seed: u32) -> Self {
+        Self::fixed(seed)
+    }
+}
+
+impl TryFrom<i64> for Seed {
+    type Error = Error;
+    fn try_from(seed: i64) -> Result<Self, Error> {
+        match seed {
+            -1 => Ok(Self::from_system_time()),
+            _ => u32::try_from(seed).map(Self::fixed).map_err(|_| {
+                Error::validation(format!("seed must be -1 or in [0, 4294967296), got {seed}"))
+            }),
+        }
+    }
+}
+
+impl From<Seed> for i64 {
+    fn from(seed: Seed) -> Self {
+        seed.0
+    }
+}
+
+impl fmt::Display for Seed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Classification of the `init` argument of an [`ArgTree`], as
+/// interpreted by CmdStan: a number is the radius `x` of the interval
+/// `[-x, x]` from which unconstrained initial values are drawn, and
+/// anything else identifies a file of values.
+///
+/// A radius of zero is the special case of initialization to zero;
+/// it is always classified as [`Init::Zero`], whether spelled `0` or
+/// `0.0`, hence, `Init::Radius(x)` has `x > 0`. A negative, infinite
+/// or NaN radius is rejected by `TryFrom`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Init {
+    /// Initialize all unconstrained parameters to zero, i.e. `init=0`.
+    Zero,
+    /// Draw from `[-x, x]` on the unconstrained scale, e.g. `init=2`.
+    Radius(f64),
+    /// Read initial values from the file.
+    File(OsString),
+}
+
+impl TryFrom<f64> for Init {
+    type Error = Error;
+    fn try_from(x: f64) -> Result<Self, Error> {
+        if x == 0.0 {
+            Ok(Self::Zero)
+        } else if x > 0.0 && x.is_finite() {
+            Ok(Self::Radius(x))
+        } else {
+            Err(Error::validation(format!(
+                "init radius must be finite and >= 0, got {x}"
+            )))
+        }
+    }
+}
+
+impl TryFrom<&OsStr> for Init {
+    type Error = Error;
+    /// Classify `init` as CmdStan would; a value which parses as a
+    /// number must be a valid radius.
+    fn try_from(init: &OsStr) -> Result<Self, Error> {
+        match init.to_str().map(|s| s.trim().parse::<f64>()) {
+            Some(Ok(x)) => Self::try_from(x),
+            _ => Ok(Self::File(init.to_os_string())),
+        }
+    }
+}
+
+impl From<Init> for OsString {
+    /// Spell `init` as a command line value, e.g. `Init::Radius(2.0)`
+    /// as `2`.
+    fn from(init: Init) -> Self {
+        match init {
+            Init::Zero => "0".into(),
+            Init::Radius(x) => x.to_string().into(),
+            Init::File(file) => file,
+        }
+    }
+}
+
 /// File output options
 #[derive(Debug, PartialEq, Clone, Translate, Builder)]
 #[non_exhaustive]
@@ -197,20 +1031,145 @@ pub struct Output {
     /// Number of interations between screen updates.
     /// Valid values: `0 <= refresh`.
     /// Defaults to `100`.
-    #[defaults_to = 100]
+    #[defaults_to = "crate::consts::REFRESH"]
+    #[omit_if = "omits_refresh"]
     pub refresh: i32,
     /// The number of significant figures used for the output CSV
     /// files.
     /// Valid values: `0 <= sig_figs <= 18` or `-1` to use the
     /// default number of significant figures.
     /// Defaults to` -1`.
-    #[defaults_to = "crate::consts::NEG1_I32"]
+    #[defaults_to = "crate::consts::SIG_FIGS"]
+    #[omit_if = "omits_sig_figs"]
     pub sig_figs: i32,
     /// File to store profiling information.
     /// Valid values: Valid path and write access to the folder.
     /// Defaults to `"profile.csv"`.
     #[defaults_to = "crate::consts::PROFILE_FILE"]
     pub profile_file: OsString,
+    /// The arguments to omit from the command; see
+    /// [`OutputBuilder::maybe_refresh`].
+    #[internal]
+    #[no_setter]
+    pub(crate) omitted: Omitted,
+}
+
+/// The arguments of [`Output`] which are omitted from the command, such
+/// that CmdStan applies its own default. An argument is omitted only
+/// while its field holds the default, which is that of CmdStan, hence,
+/// the omission is immaterial to equality.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Omitted {
+    pub(crate) refresh: bool,
+    pub(crate) sig_figs: bool,
+}
+impl PartialEq for Omitted {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+/// Join `file` onto `base`, unless `file` is empty (i.e. unset) or absolute.
+fn rebase_file(file: &mut OsString, base: &Path) {
+    if !file.is_empty() {
+        *file = base.join(&*file).into_os_string();
+    }
+}
+
+/// Implement `From<$t>` for [`Data`], treating the value as the file.
+macro_rules! data_from_file {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Data {
+                fn from(file: $t) -> Self {
+                    Self { file: file.into() }
+                }
+            }
+        )*
+    };
+}
+data_from_file!(&str, String, &OsStr, OsString, &Path, PathBuf);
+
+impl Data {
+    /// Return `self` with a relative `file` joined onto `base`, e.g.
+    /// the directory of an experiment, such that the file is independent
+    /// of the working directory of the call. An absolute or empty `file`
+    /// is unchanged.
+    pub fn with_base<P: AsRef<Path>>(mut self, base: P) -> Self {
+        rebase_file(&mut self.file, base.as_ref());
+        self
+    }
+}
+
+impl Output {
+    /// Return `self` with each relative file (output, diagnostic and
+    /// profile) joined onto `base`, as for [`Data::with_base`].
+    pub fn with_base<P: AsRef<Path>>(mut self, base: P) -> Self {
+        let base = base.as_ref();
+        rebase_file(&mut self.file, base);
+        rebase_file(&mut self.diagnostic_file, base);
+        rebase_file(&mut self.profile_file, base);
+        self
+    }
+
+    /// Check that the values are within the ranges accepted by CmdStan.
+    /// File paths are not checked, as these may not exist until the
+    /// time of the call.
+    pub fn validate(&self) -> Result<(), Error> {
+        Error::require(self.refresh >= 0, "refresh", ">= 0", self.refresh)?;
+        Error::require(
+            self.sig_figs == -1 || (0..=18).contains(&self.sig_figs),
+            "sig_figs",
+            "-1 (default) or in 0..=18",
+            self.sig_figs,
+        )?;
+        Ok(())
+    }
+
+    fn omits_refresh(&self) -> bool {
+        self.omitted.refresh && self.refresh == crate::consts::REFRESH
+    }
+
+    fn omits_sig_figs(&self) -> bool {
+        self.omitted.sig_figs && self.sig_figs == crate::consts::SIG_FIGS
+    }
+}
+
+impl OutputBuilder {
+    /// Configure `refresh` to `0`, which disables the progress
+    /// updates written to the console by CmdStan. Consequently,
+    /// [`CmdStanModel::call_with_progress`](crate::CmdStanModel::call_with_progress)
+    /// will not report progress for a tree built with this option.
+    pub fn quiet(self) -> Self {
+        self.refresh(0)
+    }
+
+    /// Configure `refresh` if `Some`, else revert any previous configuration
+    /// to the default, which is that of CmdStan, and omit the argument
+    /// from the command, such that CmdStan applies its default. The
+    /// argument is omitted only while the field holds the default.
+    pub fn maybe_refresh(mut self, refresh: Option<i32>) -> Self {
+        self.refresh = refresh;
+        self.omitted.get_or_insert_with(Omitted::default).refresh = refresh.is_none();
+        self
+    }
+
+    /// Configure `sig_figs` if `Some`, else revert any previous configuration
+    /// to the default, `-1`, and omit the argument, as for
+    /// [`OutputBuilder::maybe_refresh`].
+    pub fn maybe_sig_figs(mut self, sig_figs: Option<i32>) -> Self {
+        self.sig_figs = sig_figs;
+        self.omitted.get_or_insert_with(Omitted::default).sig_figs = sig_figs.is_none();
+        self
+    }
+
+    /// Build the `Output` instance, returning an error if any value
+    /// would be rejected by CmdStan (see [`Output::validate`]).
+    pub fn try_build(self) -> Result<Output, Error> {
+        let x = self.build();
+        x.validate()?;
+        Ok(x)
+    }
 }
 
 #[cfg(test)]
@@ -237,6 +1196,7 @@ mod tests {
                 refresh: 1,
                 sig_figs: 18,
                 profile_file: "foo.txt".into(),
+                omitted: Default::default(),
             };
             let num_threads = 48;
             let x = ArgTree::builder()
@@ -258,6 +1218,7 @@ mod tests {
                     random,
                     output,
                     num_threads,
+                    extra_args: Vec::new(),
                 }
             );
         }
@@ -275,6 +1236,7 @@ mod tests {
                 refresh: 100,
                 sig_figs: -1,
                 profile_file: "profile.csv".into(),
+                omitted: Default::default(),
             };
             let num_threads = 1;
             assert_eq!(
@@ -287,6 +1249,7 @@ mod tests {
                     random,
                     output,
                     num_threads,
+                    extra_args: Vec::new(),
                 }
             );
         }
@@ -317,6 +1280,7 @@ mod tests {
                 refresh: 1,
                 sig_figs: 18,
                 profile_file: "foo.txt".into(),
+                omitted: Default::default(),
             };
             let num_threads = 48;
             let x = ArgTree {
@@ -327,6 +1291,7 @@ mod tests {
                 random,
                 output,
                 num_threads,
+                extra_args: Vec::new(),
             };
             assert_eq!(x.to_stmt(), "method=sample num_samples=10000 num_warmup=1000 save_warmup=0 thin=1 adapt engaged=1 gamma=0.05 delta=0.8 kappa=0.75 t0=10 init_buffer=75 term_buffer=50 window=25 algorithm=hmc engine=nuts max_depth=100 metric=diag_e metric_file= stepsize=1 stepsize_jitter=0 num_chains=10 id=2 data file=bernoulli.json init=5 random seed=12345 output file=hello.csv diagnostic_file=world.txt refresh=1 sig_figs=18 profile_file=foo.txt num_threads=48");
 
@@ -351,6 +1316,7 @@ mod tests {
                 refresh: 1,
                 sig_figs: 18,
                 profile_file: "foo.txt".into(),
+                omitted: Default::default(),
             };
             let num_threads = 48;
             let x = ArgTree {
@@ -361,10 +1327,554 @@ mod tests {
                 random,
                 output,
                 num_threads,
+                extra_args: Vec::new(),
             };
             assert_eq!(x.to_stmt(), "method=sample num_samples=10000 num_warmup=1000 save_warmup=0 thin=1 adapt engaged=1 gamma=0.05 delta=0.8 kappa=0.75 t0=10 init_buffer=75 term_buffer=50 window=25 algorithm=hmc engine=static int_time=2.5 metric=diag_e metric_file= stepsize=1 stepsize_jitter=0 num_chains=10 id=2 data file=bernoulli.json init=5 random seed=12345 output file=hello.csv diagnostic_file=world.txt refresh=1 sig_figs=18 profile_file=foo.txt num_threads=48");
         }
 
+        #[test]
+        fn canonicalize() {
+            let cli = "sample data file=bernoulli.data.json init=2.0 output file=output";
+            let x = cli.parse::<ArgTree>().unwrap();
+            let header = "method = sample (Default)
+  sample
+    num_samples = 1000 (Default)
+    num_warmup = 1000 (Default)
+    save_warmup = 0 (Default)
+    thin = 1 (Default)
+    adapt
+      engaged = 1 (Default)
+      gamma = 0.050000000000000003 (Default)
+      delta = 0.80000000000000004 (Default)
+      kappa = 0.75 (Default)
+      t0 = 10 (Default)
+      init_buffer = 75 (Default)
+      term_buffer = 50 (Default)
+      window = 25 (Default)
+    algorithm = hmc (Default)
+      hmc
+        engine = nuts (Default)
+          nuts
+            max_depth = 10 (Default)
+        metric = diag_e (Default)
+        metric_file =  (Default)
+        stepsize = 1 (Default)
+        stepsize_jitter = 0 (Default)
+    num_chains = 1 (Default)
+id = 1 (Default)
+data
+  file = bernoulli.data.json
+init = 2
+random
+  seed = -1 (Default)
+output
+  file = output.csv
+  diagnostic_file =  (Default)
+  refresh = 100 (Default)
+  sig_figs = -1 (Default)
+  profile_file = profile.csv (Default)
+num_threads = 1 (Default)";
+            let y = ArgTree::from_reader(header.as_bytes()).unwrap().unwrap();
+            assert_ne!(x, y);
+            assert!(x.eq_canonical(&y));
+
+            let mut z = x.clone();
+            z.canonicalize();
+            assert_eq!(z, y);
+            assert_eq!(z.init, "2");
+            z.canonicalize();
+            assert_eq!(z, y);
+
+            let mut x = ArgTree::builder().init("inits.json").build();
+            x.canonicalize();
+            assert_eq!(x.init, "inits.json");
+            assert!(!x.eq_canonical(&ArgTree::default()));
+        }
+
+        #[test]
+        fn approx_eq() {
+            // As echoed by CmdStan in the output CSV.
+            let s = "method=sample num_samples=1000 num_warmup=1000 save_warmup=0 thin=1 adapt engaged=1 gamma=0.050000000000000003 delta=0.80000000000000004 kappa=0.75 t0=10 init_buffer=75 term_buffer=50 window=25 algorithm=hmc engine=nuts max_depth=10 metric=diag_e metric_file= stepsize=1 stepsize_jitter=0 num_chains=1 id=1 data file=bernoulli.data.json init=2 random seed=589886520 output file=output.csv diagnostic_file= refresh=100 sig_figs=-1 profile_file=profile.csv num_threads=1";
+            let lhs = s.parse::<ArgTree>().unwrap();
+            let rhs = ArgTree::builder()
+                .data(Data::builder().file("bernoulli.data.json"))
+                .random(Random::builder().seed(589886520))
+                .num_threads(1)
+                .build();
+            assert!(lhs.approx_eq(&rhs, 0.0));
+
+            let f = |adapt: SampleAdaptBuilder| {
+                ArgTree::builder()
+                    .method(SampleBuilder::new().adapt(adapt))
+                    .num_threads(1)
+                    .build()
+            };
+            let x = f(SampleAdapt::builder().delta(0.8));
+            let y = f(SampleAdapt::builder().delta(0.8 + 1e-10));
+            assert_ne!(x, y);
+            assert!(x.approx_eq(&y, 1e-9));
+            assert!(!x.approx_eq(&y, 1e-12));
+
+            // Relative for large magnitudes.
+            let x = f(SampleAdapt::builder().t0(1e6));
+            let y = f(SampleAdapt::builder().t0(1e6 + 1e-4));
+            assert!(x.approx_eq(&y, 1e-9));
+            assert!(!x.approx_eq(&y, 1e-12));
+
+            // Integers and non-numeric values are compared exactly.
+            let x = f(SampleAdapt::builder().window(25u32));
+            let y = f(SampleAdapt::builder().window(26u32));
+            assert!(!x.approx_eq(&y, 1.0));
+            let y = ArgTree::builder()
+                .method(OptimizeBuilder::new())
+                .num_threads(1)
+                .build();
+            assert!(!x.approx_eq(&y, 1.0));
+            let y = ArgTree {
+                init: "3".into(),
+                ..x.clone()
+            };
+            assert!(!x.approx_eq(&y, 1.0));
+            assert!(x.approx_eq(&x, 0.0));
+        }
+
+        #[test]
+        fn thread_warnings() {
+            let f = |num_chains: i32, num_threads: i32, available: usize| {
+                ArgTree::builder()
+                    .method(SampleBuilder::new().num_chains(num_chains))
+                    .num_threads(num_threads)
+                    .build()
+                    .thread_warnings_for(available)
+            };
+            assert!(f(4, 4, 8).is_empty());
+            assert!(f(4, 8, 8).is_empty());
+            assert!(f(4, -1, 2).is_empty());
+            assert!(f(1, 1, 0).is_empty());
+            assert!(f(0, 1, 0).is_empty());
+            assert_eq!(
+                f(4, 48, 8),
+                [ThreadWarning::Oversubscribed {
+                    num_threads: 48,
+                    available: 8
+                }]
+            );
+            assert_eq!(
+                f(4, 2, 8),
+                [ThreadWarning::FewerThreadsThanChains {
+                    num_threads: 2,
+                    num_chains: 4
+                }]
+            );
+            let x = f(4, 6, 4);
+            assert_eq!(
+                x,
+                [
+                    ThreadWarning::Oversubscribed {
+                        num_threads: 6,
+                        available: 4
+                    },
+                    ThreadWarning::UnevenThreadsPerChain {
+                        num_threads: 6,
+                        num_chains: 4
+                    }
+                ]
+            );
+            assert_eq!(
+                x[1].to_string(),
+                "num_threads=6 is not a multiple of num_chains=4; chains will receive unequal threads"
+            );
+            let x = ArgTree::builder().num_threads(1).build();
+            assert!(x.thread_warnings().is_empty());
+        }
+
+        #[test]
+        fn chain_id_allocator() {
+            let ids = ChainIdAllocator::default();
+            assert_eq!(ids.allocate(2), 1);
+            assert_eq!(ids.allocate(1), 3);
+
+            let ids = ChainIdAllocator::new(10);
+            let single = ArgTree::default();
+            let multi = ArgTree::builder()
+                .method(SampleBuilder::new().num_chains(3))
+                .build();
+            let trees: Vec<ArgTree> = std::thread::scope(|scope| {
+                let handles: Vec<_> = (0..16)
+                    .map(|i| {
+                        let ids = &ids;
+                        let tree = if i % 2 == 0 { &single } else { &multi };
+                        scope.spawn(move || ids.assign(tree))
+                    })
+                    .collect();
+                handles.into_iter().map(|h| h.join().unwrap()).collect()
+            });
+            let mut chain_ids: Vec<i32> = trees
+                .iter()
+                .flat_map(|t| t.id..t.id + t.method.num_chains())
+                .collect();
+            chain_ids.sort();
+            assert_eq!(chain_ids, (10..10 + 8 * 4).collect::<Vec<_>>());
+            let mut files: Vec<OsString> = trees
+                .iter()
+                .flat_map(|t| {
+                    let mut files = t.output_files();
+                    files.extend(t.profile_files());
+                    files
+                })
+                .collect();
+            let n = files.len();
+            files.sort();
+            files.dedup();
+            assert_eq!(files.len(), n);
+
+            let x = ChainIdAllocator::new(3).assign(&single);
+            assert_eq!(x.id, 3);
+            assert_eq!(x.output_files(), vec!["output_3.csv"]);
+            let x = ChainIdAllocator::new(3).assign(&multi);
+            assert_eq!(
+                x.output_files(),
+                vec!["output_3_3.csv", "output_3_4.csv", "output_3_5.csv"]
+            );
+        }
+
+        #[test]
+        fn to_key_value() {
+            let tree = ArgTree::builder()
+                .data(Data::builder().file("bernoulli.data.json"))
+                .build();
+            let pairs = tree.to_key_value();
+            let get = |key: &str| {
+                pairs
+                    .iter()
+                    .find(|(k, _)| k == key)
+                    .map(|(_, v)| v.as_str())
+            };
+            assert_eq!(pairs[0], ("method".to_string(), "sample".to_string()));
+            assert_eq!(get("method.sample.num_samples"), Some("1000"));
+            assert_eq!(get("method.sample.adapt.delta"), Some("0.8"));
+            assert_eq!(
+                get("method.sample.algorithm.hmc.engine.nuts.max_depth"),
+                Some("10")
+            );
+            assert_eq!(get("method.sample.algorithm.hmc.metric_file"), Some(""));
+            assert_eq!(get("id"), Some("1"));
+            assert_eq!(get("data.file"), Some("bernoulli.data.json"));
+            assert_eq!(get("output.file"), Some("output.csv"));
+            assert_eq!(get("method.sample"), None);
+            assert_eq!(
+                pairs.len(),
+                tree.to_tree().to_string_lossy().matches(" =").count()
+            );
+        }
+
+        #[test]
+        fn field_paths() {
+            let paths = ArgTree::field_paths();
+            let trees = [
+                ArgTree::default(),
+                ArgTree::builder()
+                    .data(Data::builder().file("bernoulli.data.json"))
+                    .build(),
+                ArgTree::builder().method(OptimizeBuilder::new()).build(),
+                ArgTree::builder().method(VariationalBuilder::new()).build(),
+                ArgTree::builder().method(PathfinderBuilder::new()).build(),
+            ];
+            for tree in trees {
+                for (path, _) in tree.to_key_value() {
+                    assert!(paths.contains(&path), "{path}");
+                }
+            }
+            assert_eq!(paths[0], "method");
+            assert!(paths.contains(&"method.sample.num_samples".to_string()));
+            assert!(paths.contains(&"method.optimize.iter".to_string()));
+            assert!(paths.contains(&"random.seed".to_string()));
+            assert!(!paths.contains(&"extra_args".to_string()));
+            assert_eq!(Data::field_paths(), ["data.file"]);
+        }
+
+        #[test]
+        fn diff() {
+            let lhs = ArgTree::default();
+            assert!(lhs.diff(&lhs).is_empty());
+
+            let rhs = ArgTree::builder()
+                .method(SampleBuilder::new().num_samples(500))
+                .random(Random::builder().seed(7))
+                .build();
+            let diffs = lhs.diff(&rhs);
+            assert_eq!(
+                diffs,
+                [
+                    FieldDiff {
+                        path: "method.sample.num_samples".to_string(),
+                        left: Some("1000".to_string()),
+                        right: Some("500".to_string()),
+                    },
+                    FieldDiff {
+                        path: "random.seed".to_string(),
+                        left: Some("-1".to_string()),
+                        right: Some("7".to_string()),
+                    },
+                ]
+            );
+            assert_eq!(
+                diffs[0].to_string(),
+                "method.sample.num_samples: 1000 -> 500"
+            );
+
+            let rhs = ArgTree::builder().method(OptimizeBuilder::new()).build();
+            let diffs = lhs.diff(&rhs);
+            assert_eq!(diffs[0].path, "method");
+            assert_eq!(diffs[0].right.as_deref(), Some("optimize"));
+            assert!(diffs
+                .iter()
+                .any(|x| x.path == "method.sample.num_samples" && x.right.is_none()));
+            let x = diffs
+                .iter()
+                .find(|x| x.path == "method.optimize.iter")
+                .unwrap();
+            assert_eq!(x.left, None);
+            assert_eq!(x.to_string(), "method.optimize.iter: (absent) -> 2000");
+        }
+
+        #[test]
+        fn static_int_time_echo() {
+            let tree = ArgTree::builder()
+                .method(SampleBuilder::new().hmc(|b| b.engine(StaticBuilder::new())))
+                .build();
+            let echo = tree.to_cmdstan_echo();
+            let echo = echo.to_str().unwrap();
+            assert!(echo.contains("\n            int_time = 6.2831853071795862\n"));
+            let header: String = echo.lines().map(|l| format!("# {l}\n")).collect();
+            let parsed = ArgTree::from_reader(header.as_bytes()).unwrap().unwrap();
+            assert_eq!(parsed, tree);
+        }
+
+        #[test]
+        fn to_cmdstan_echo() {
+            // As echoed by CmdStan 2.33 to the console.
+            let echo = "method = sample (Default)
+  sample
+    num_samples = 1000 (Default)
+    num_warmup = 1000 (Default)
+    save_warmup = 0 (Default)
+    thin = 1 (Default)
+    adapt
+      engaged = 1 (Default)
+      gamma = 0.050000000000000003 (Default)
+      delta = 0.80000000000000004 (Default)
+      kappa = 0.75 (Default)
+      t0 = 10 (Default)
+      init_buffer = 75 (Default)
+      term_buffer = 50 (Default)
+      window = 25 (Default)
+    algorithm = hmc (Default)
+      hmc
+        engine = nuts (Default)
+          nuts
+            max_depth = 10 (Default)
+        metric = diag_e (Default)
+        metric_file =  (Default)
+        stepsize = 1 (Default)
+        stepsize_jitter = 0 (Default)
+    num_chains = 1 (Default)
+id = 1 (Default)
+data
+  file = bernoulli.data.json
+init = 2 (Default)
+random
+  seed = 589886520 (Default)
+output
+  file = output.csv (Default)
+  diagnostic_file =  (Default)
+  refresh = 100 (Default)
+  sig_figs = -1 (Default)
+  profile_file = profile.csv (Default)
+num_threads = 1 (Default)";
+            let x = ArgTree::from_reader(echo.as_bytes()).unwrap().unwrap();
+            assert_eq!(x.to_cmdstan_echo(), echo.replace(" (Default)", "").as_str());
+            assert_ne!(x.to_tree(), x.to_cmdstan_echo());
+
+            let x = ArgTree::builder()
+                .method(OptimizeBuilder::new().algorithm(LbfgsBuilder::new().tol_obj(1e-12)))
+                .init("0.1")
+                .build();
+            let echo = x.to_cmdstan_echo();
+            let echo = echo.to_str().unwrap();
+            assert!(echo.contains("tol_obj = 9.9999999999999998e-13\n"));
+            assert!(echo.contains("init_alpha = 0.001\n"));
+            assert!(echo.contains("init = 0.1\n"));
+        }
+
+        #[test]
+        fn format_g17() {
+            use super::super::format_g17;
+            assert_eq!(format_g17(0.05), "0.050000000000000003");
+            assert_eq!(format_g17(2.5), "2.5");
+            assert_eq!(format_g17(100.0), "100");
+            assert_eq!(format_g17(0.0), "0");
+            assert_eq!(format_g17(1e-5), "1.0000000000000001e-05");
+            assert_eq!(format_g17(1e20), "1e+20");
+            assert_eq!(format_g17(-0.75), "-0.75");
+        }
+
+        #[test]
+        fn extra_args() {
+            let x = ArgTree::builder().build();
+            assert!(x.extra_args.is_empty());
+            assert!(x.to_stmt().to_str().unwrap().ends_with("num_threads=1"));
+            assert!(x.to_tree().to_str().unwrap().ends_with("num_threads = 1"));
+
+            let x = ArgTree::builder()
+                .method(OptimizeBuilder::new())
+                .extra_args(["opencl", "device=0"])
+                .build();
+            assert_eq!(x.extra_args, vec!["opencl", "device=0"]);
+            assert!(x.uses_opencl());
+            assert!(!ArgTree::default().uses_opencl());
+            let y = ArgTree::default().with_opencl(1, 0);
+            assert_eq!(y.extra_args, vec!["opencl", "platform=1", "device=0"]);
+            let args = x.to_args();
+            assert_eq!(x.arg_count(), args.len());
+            assert_eq!(
+                &args[args.len() - 3..],
+                ["num_threads=1", "opencl", "device=0"]
+            );
+            assert!(x
+                .to_stmt()
+                .to_str()
+                .unwrap()
+                .ends_with("num_threads=1 opencl device=0"));
+            assert!(x
+                .to_tree()
+                .to_str()
+                .unwrap()
+                .ends_with("num_threads = 1\nopencl\ndevice=0"));
+        }
+
+        #[test]
+        fn resolved_output_file() {
+            let f = |file: &str| {
+                let x = ArgTree::builder()
+                    .output(Output::builder().file(file))
+                    .build();
+                let resolved = x.resolved_output_file();
+                assert_eq!(x.output_files(), vec![resolved.clone()]);
+                resolved
+            };
+            assert_eq!(f("output"), "output.csv");
+            assert_eq!(f("output."), "output.");
+            assert_eq!(f("output.csv"), "output.csv");
+            assert_eq!(f("out.txt"), "out.txt");
+            assert_eq!(f("a.b.c"), "a.b.c");
+            assert_eq!(f(".csv"), ".csv");
+            assert_eq!(f("."), ".");
+            assert_eq!(f("..."), "...");
+            assert_eq!(f(",,"), ",,.csv");
+            assert_eq!(f("foo/bar/"), "foo/bar/.csv");
+            assert_eq!(f("foo/.bar"), "foo/.bar");
+            assert_eq!(f("foo.d/bar"), "foo.d/bar");
+            assert_eq!(f("/abs/path/output"), "/abs/path/output.csv");
+            assert_eq!(f(""), ".csv");
+
+            // Independent of the number of chains.
+            let x = ArgTree::builder()
+                .method(SampleBuilder::new().num_chains(3))
+                .output(Output::builder().file("post"))
+                .build();
+            assert_eq!(x.resolved_output_file(), "post.csv");
+        }
+
+        #[test]
+        fn metric_files() {
+            let hmc = HmcBuilder::new().metric_file("metric.json");
+            let x = ArgTree::builder()
+                .method(SampleBuilder::new().algorithm(hmc.clone()).num_chains(3))
+                .id(2)
+                .build();
+            assert_eq!(
+                x.metric_files(),
+                vec!["metric_2.json", "metric_3.json", "metric_4.json"]
+            );
+            let x = ArgTree::builder()
+                .method(SampleBuilder::new().algorithm(hmc))
+                .build();
+            assert_eq!(x.metric_files(), vec!["metric.json"]);
+
+            assert!(ArgTree::default().metric_files().is_empty());
+            let x = ArgTree::builder()
+                .method(SampleBuilder::new().algorithm(SampleAlgorithm::FixedParam))
+                .build();
+            assert!(x.metric_files().is_empty());
+        }
+
+        #[test]
+        fn write_metric_files() {
+            let dir = crate::test_support::temp_dir();
+            let hmc = HmcBuilder::new().metric_file(dir.join("metric.json"));
+            let x = ArgTree::builder()
+                .method(SampleBuilder::new().algorithm(hmc).num_chains(2))
+                .build();
+            let metrics = [InvMetric::Diag(vec![1.0]), InvMetric::Diag(vec![2.0])];
+            let e = x.write_metric_files(&metrics[..1]).unwrap_err();
+            assert_eq!(e.kind(), io::ErrorKind::InvalidInput);
+            x.write_metric_files(&metrics).unwrap();
+            let s = std::fs::read_to_string(dir.join("metric_2.json")).unwrap();
+            assert_eq!(s, r#"{"inv_metric":[2]}"#);
+        }
+
+        #[test]
+        fn warm_start_chains() {
+            let dir = crate::test_support::temp_dir();
+            let hmc = HmcBuilder::new().metric_file(dir.join("metric.json"));
+            let x = ArgTree::builder()
+                .method(SampleBuilder::new().algorithm(hmc).num_chains(4))
+                .id(2)
+                .build();
+            let adaptations = [
+                Adaptation {
+                    stepsize: 0.5,
+                    inv_metric: InvMetric::Diag(vec![1.0]),
+                },
+                Adaptation {
+                    stepsize: 0.25,
+                    inv_metric: InvMetric::Diag(vec![2.0]),
+                },
+            ];
+            let trees = x.warm_start_chains(&adaptations).unwrap();
+            assert_eq!(trees.len(), 2);
+            for (tree, (id, stepsize)) in trees.iter().zip([(2, 0.5), (3, 0.25)]) {
+                assert_eq!(tree.id, id);
+                assert_eq!(tree.method.num_chains(), 1);
+                assert_eq!(
+                    tree.output_files(),
+                    vec![OsString::from(format!("output_{id}.csv"))]
+                );
+                let metric_file = dir.join(format!("metric_{id}.json"));
+                assert_eq!(
+                    tree.metric_files(),
+                    vec![metric_file.clone().into_os_string()]
+                );
+                let Method::Sample {
+                    algorithm: SampleAlgorithm::Hmc { stepsize: x, .. },
+                    ..
+                } = &tree.method
+                else {
+                    unreachable!()
+                };
+                assert_eq!(*x, stepsize);
+                assert!(metric_file.is_file());
+            }
+            let s = std::fs::read_to_string(dir.join("metric_3.json")).unwrap();
+            assert_eq!(s, r#"{"inv_metric":[2]}"#);
+
+            let e = ArgTree::default()
+                .warm_start_chains(&adaptations)
+                .unwrap_err();
+            assert_eq!(e.kind(), io::ErrorKind::InvalidInput);
+        }
+
         #[test]
         fn files() {
             let b = ArgTree::builder()
@@ -405,6 +1915,10 @@ mod tests {
                 .output(Output::builder().file("a.b.c").diagnostic_file("a...,"))
                 .build();
             assert_eq!(x.output_files(), vec!["a.b_2.c", "a.b_3.c", "a.b_4.c"]);
+            assert_eq!(
+                x.output_files_for_chains(&[4, 1, 2, 5, i32::MIN]),
+                vec!["a.b_4.c", "a.b_2.c"]
+            );
             assert_eq!(x.diagnostic_files(), vec!["a.._2.,", "a.._3.,", "a.._4.,"]);
 
             let x = b
@@ -496,6 +2010,169 @@ mod tests {
                 vec!["foo/bar/baz_2.", "foo/bar/baz_3.", "foo/bar/baz_4."]
             );
         }
+
+        #[test]
+        fn diagnostic_files() {
+            let output = Output::builder().file("out").diagnostic_file("diag.csv");
+            let x = ArgTree::builder()
+                .method(VariationalBuilder::new())
+                .output(output.clone())
+                .build();
+            assert_eq!(x.diagnostic_files(), vec!["diag.csv"]);
+            assert_eq!(x.output_files(), vec!["out.csv"]);
+
+            let x = ArgTree::builder()
+                .method(PathfinderBuilder::new().num_paths(4))
+                .output(output.clone())
+                .id(3)
+                .build();
+            assert_eq!(x.diagnostic_files(), vec!["diag.csv"]);
+            assert_eq!(x.output_files(), vec!["out.csv"]);
+
+            let x = ArgTree::builder()
+                .method(PathfinderBuilder::new().num_paths(1))
+                .output(Output::builder().diagnostic_file("diag"))
+                .build();
+            assert_eq!(x.diagnostic_files(), vec!["diag.csv"]);
+
+            let x = ArgTree::builder()
+                .method(PathfinderBuilder::new())
+                .output(Output::builder().file("out"))
+                .build();
+            assert!(x.diagnostic_files().is_empty());
+        }
+
+        #[test]
+        fn reset() {
+            let mut b = ArgTree::builder()
+                .id(7)
+                .init("0.5")
+                .output(Output::builder().file("out.csv"));
+            b.reset();
+            assert_eq!(b, ArgTreeBuilder::new());
+            assert_eq!(b.clone().build(), ArgTree::default());
+
+            // A builder may be reused across iterations.
+            let mut b = Output::builder();
+            for i in 0..3 {
+                b.reset();
+                let x = b.clone().refresh(i).build();
+                assert_eq!(x, Output::builder().refresh(i).build());
+            }
+        }
+
+        #[test]
+        fn nested_builders() {
+            // Every nested builder may be passed as-is, i.e. `.build()` is optional.
+            let x = ArgTree::builder()
+                .method(
+                    SampleBuilder::new()
+                        .adapt(SampleAdapt::builder().delta(0.9))
+                        .algorithm(
+                            HmcBuilder::new()
+                                .engine(NutsBuilder::new().max_depth(100))
+                                .metric(Metric::DenseE),
+                        ),
+                )
+                .data(Data::builder().file("bernoulli.json"))
+                .random(Random::builder().seed(12345))
+                .output(Output::builder().sig_figs(4))
+                .build();
+            let y = ArgTree::builder()
+                .method(
+                    SampleBuilder::new()
+                        .adapt(SampleAdapt::builder().delta(0.9).build())
+                        .algorithm(
+                            HmcBuilder::new()
+                                .engine(NutsBuilder::new().max_depth(100).build())
+                                .metric(Metric::DenseE)
+                                .build(),
+                        )
+                        .build(),
+                )
+                .data(Data::builder().file("bernoulli.json").build())
+                .random(Random::builder().seed(12345).build())
+                .output(Output::builder().sig_figs(4).build())
+                .build();
+            assert_eq!(x, y);
+
+            let x = ArgTree::builder()
+                .method(OptimizeBuilder::new().algorithm(LbfgsBuilder::new().history_size(3)))
+                .build();
+            let y = ArgTree::builder()
+                .method(
+                    OptimizeBuilder::new()
+                        .algorithm(LbfgsBuilder::new().history_size(3).build())
+                        .build(),
+                )
+                .build();
+            assert_eq!(x, y);
+
+            let x = ArgTree::builder()
+                .method(VariationalBuilder::new().adapt(VariationalAdapt::builder().iter(10)))
+                .build();
+            let y = ArgTree::builder()
+                .method(
+                    VariationalBuilder::new()
+                        .adapt(VariationalAdapt::builder().iter(10).build())
+                        .build(),
+                )
+                .build();
+            assert_eq!(x, y);
+
+            let x = ArgTree::builder()
+                .method(DiagnoseBuilder::new().test(GradientBuilder::new().epsilon(0.1)))
+                .build();
+            let y = ArgTree::builder()
+                .method(
+                    DiagnoseBuilder::new()
+                        .test(GradientBuilder::new().epsilon(0.1).build())
+                        .build(),
+                )
+                .build();
+            assert_eq!(x, y);
+        }
+
+        #[test]
+        fn resolve_threads() {
+            let mut x = ArgTree::builder().num_threads(-1).build();
+            x.resolve_threads();
+            let n = std::thread::available_parallelism().unwrap().get();
+            assert_eq!(x.num_threads, n as i32);
+
+            for n in [1, 2, 48] {
+                let mut x = ArgTree::builder().num_threads(n).build();
+                x.resolve_threads();
+                assert_eq!(x.num_threads, n);
+            }
+        }
+
+        #[test]
+        fn with_label() {
+            let x = ArgTree::default().with_label("nuts");
+            assert_eq!(x.output.file, "output_nuts.csv");
+            assert_eq!(x.output.diagnostic_file, "");
+            assert_eq!(x.output.profile_file, "profile_nuts.csv");
+            assert_eq!(x.method, Method::default());
+
+            let x = ArgTree::builder()
+                .method(SampleBuilder::new().num_chains(2))
+                .output(
+                    Output::builder()
+                        .file("foo/post")
+                        .diagnostic_file("checks.txt")
+                        .profile_file("a.b.c"),
+                )
+                .build()
+                .with_label("1");
+            assert_eq!(x.output.file, "foo/post_1.csv");
+            assert_eq!(x.output.diagnostic_file, "checks_1.txt");
+            assert_eq!(x.output.profile_file, "a.b_1.c");
+            assert_eq!(
+                x.output_files(),
+                vec!["foo/post_1_1.csv", "foo/post_1_2.csv"]
+            );
+        }
     }
 
     #[cfg(test)]
@@ -512,10 +2189,56 @@ mod tests {
         fn to_args() {
             let mut x = Data::default();
             assert_eq!(x.to_args(), vec!["data", "file="]);
+            assert_eq!(x.to_stmt().to_str().unwrap().parse::<Data>().unwrap(), x);
 
             x.file.push("bernoulli.data.json");
             assert_eq!(x.to_args(), vec!["data", "file=bernoulli.data.json"]);
         }
+
+        #[test]
+        fn from_file() {
+            let x = Data::builder().file("bernoulli.data.json").build();
+            assert_eq!(Data::from("bernoulli.data.json"), x);
+            assert_eq!(Data::from(String::from("bernoulli.data.json")), x);
+            assert_eq!(Data::from(OsStr::new("bernoulli.data.json")), x);
+            assert_eq!(Data::from(Path::new("bernoulli.data.json")), x);
+            assert_eq!(Data::from(PathBuf::from("bernoulli.data.json")), x);
+            let tree = ArgTree::builder().data("bernoulli.data.json").build();
+            assert_eq!(tree.data, x);
+        }
+
+        #[test]
+        fn with_base() {
+            let base = Path::new("/experiments/bernoulli");
+            let x = Data::builder().file("bernoulli.data.json").build();
+            assert_eq!(
+                x.with_base(base).file,
+                base.join("bernoulli.data.json").into_os_string()
+            );
+            let x = Data::builder().file("/data/bernoulli.data.json").build();
+            assert_eq!(x.with_base(base).file, "/data/bernoulli.data.json");
+            assert_eq!(Data::default().with_base(base).file, "");
+
+            let x = Output::builder()
+                .profile_file("/tmp/profile.csv")
+                .build()
+                .with_base(base);
+            assert_eq!(x.file, base.join("output.csv").into_os_string());
+            assert_eq!(x.diagnostic_file, "");
+            assert_eq!(x.profile_file, "/tmp/profile.csv");
+
+            let x = ArgTree::builder()
+                .data(Data::builder().file("bernoulli.data.json"))
+                .init("0.5")
+                .build()
+                .with_base(base);
+            assert_eq!(
+                x.data.file,
+                base.join("bernoulli.data.json").into_os_string()
+            );
+            assert_eq!(x.output.file, base.join("output.csv").into_os_string());
+            assert_eq!(x.init, "0.5");
+        }
     }
 
     #[cfg(test)]
@@ -533,11 +2256,70 @@ mod tests {
             let x = Random::default();
             assert_eq!(x.to_args(), vec!["random", "seed=-1"]);
         }
+
+        #[test]
+        fn seed() {
+            assert_eq!(Seed::default(), Seed::from_system_time());
+            assert_eq!(Seed::try_from(-1_i64).unwrap().get(), -1);
+            assert_eq!(Seed::try_from(0_i64).unwrap(), Seed::fixed(0));
+            assert_eq!(
+                Seed::try_from(4294967295_i64).unwrap(),
+                Seed::fixed(u32::MAX)
+            );
+            for x in [-5_i64, -2, 4294967296, i64::MAX, i64::MIN] {
+                let e = Seed::try_from(x).unwrap_err();
+                assert_eq!(e.kind(), crate::error::ErrorKind::Validation);
+            }
+
+            let x = Random::builder().seed(Seed::fixed(12345)).build();
+            assert_eq!(x.seed, 12345);
+            let x = Random::builder().seed(Seed::from_system_time()).build();
+            assert_eq!(x.to_args(), vec!["random", "seed=-1"]);
+
+            for seed in [0, 4294967295] {
+                let x = Random::builder().seed(seed).try_build().unwrap();
+                assert_eq!(x.seed, seed as i64);
+            }
+            // Only a value assigned to the field can be out of range.
+            for seed in [-5, 4294967296] {
+                let e = Random { seed }.validate().unwrap_err();
+                assert_eq!(e.kind(), crate::error::ErrorKind::Validation);
+            }
+        }
+
+        #[test]
+        fn init() {
+            assert_eq!(Init::try_from(0.0).unwrap(), Init::Zero);
+            assert_eq!(Init::try_from(-0.0).unwrap(), Init::Zero);
+            assert_eq!(Init::try_from(0.5).unwrap(), Init::Radius(0.5));
+            for x in [-1.0, f64::NAN, f64::INFINITY] {
+                let e = Init::try_from(x).unwrap_err();
+                assert_eq!(e.kind(), crate::error::ErrorKind::Validation);
+            }
+
+            let f = |s: &str| Init::try_from(OsStr::new(s));
+            assert_eq!(f("0").unwrap(), Init::Zero);
+            assert_eq!(f("0.0").unwrap(), Init::Zero);
+            assert_eq!(f("2").unwrap(), Init::Radius(2.0));
+            assert_eq!(f("inits.json").unwrap(), Init::File("inits.json".into()));
+            assert!(f("-2").is_err());
+            assert!(f("nan").is_err());
+
+            assert_eq!(OsString::from(Init::Radius(2.0)), "2");
+            assert_eq!(OsString::from(Init::Radius(0.5)), "0.5");
+            assert_eq!(OsString::from(Init::Zero), "0");
+            let x = ArgTree::builder().init(Init::Radius(2.0)).build();
+            assert_eq!(x.init, "2");
+            assert_eq!(x.init_kind().unwrap(), Init::Radius(2.0));
+            let x = ArgTree::builder().init("-1").build();
+            assert!(x.init_kind().is_err());
+        }
     }
 
     #[cfg(test)]
     mod output {
         use super::*;
+        use crate::error::ErrorKind;
 
         #[test]
         fn builder() {
@@ -556,6 +2338,7 @@ mod tests {
                     refresh: 1,
                     sig_figs: 18,
                     profile_file: "foo.txt".into(),
+                    omitted: Default::default(),
                 }
             );
         }
@@ -571,10 +2354,100 @@ mod tests {
                     refresh: 100,
                     sig_figs: -1,
                     profile_file: "profile.csv".into(),
+                    omitted: Default::default(),
                 }
             );
         }
 
+        #[test]
+        fn quiet() {
+            let x = Output::builder().quiet().build();
+            assert_eq!(x.refresh, 0);
+            let x = Output::builder().refresh(5).quiet().build();
+            assert_eq!(x.refresh, 0);
+        }
+
+        #[test]
+        fn maybe() {
+            let x = Output::builder()
+                .maybe_refresh(Some(5))
+                .maybe_sig_figs(Some(6))
+                .build();
+            assert_eq!((x.refresh, x.sig_figs), (5, 6));
+            let x = Output::builder()
+                .refresh(5)
+                .sig_figs(6)
+                .maybe_refresh(None)
+                .maybe_sig_figs(None)
+                .build();
+            assert_eq!(x, Output::default());
+            assert_eq!(x.arg_count(), 4);
+            assert_eq!(
+                x.to_args(),
+                vec![
+                    "output",
+                    "file=output.csv",
+                    "diagnostic_file=",
+                    "profile_file=profile.csv"
+                ]
+            );
+            // The statement and tree are unaffected.
+            assert_eq!(x.to_stmt(), Output::default().to_stmt());
+            assert_eq!(x.to_tree(), Output::default().to_tree());
+
+            // The argument is omitted only while the field holds the default.
+            let mut y = x.clone();
+            y.refresh = 5;
+            assert_eq!(y.arg_count(), 5);
+            assert!(y.to_args().contains(&"refresh=5".into()));
+            let y = Output::builder().maybe_refresh(None).refresh(5).build();
+            assert!(y.to_args().contains(&"refresh=5".into()));
+            let y = Output::builder()
+                .maybe_refresh(None)
+                .maybe_refresh(Some(100))
+                .build();
+            assert!(y.to_args().contains(&"refresh=100".into()));
+        }
+
+        #[test]
+        fn maybe_round_trip() {
+            let tree = ArgTree::builder()
+                .output(Output::builder().maybe_refresh(None).maybe_sig_figs(None))
+                .build();
+            let args = tree.to_args();
+            assert!(!args
+                .iter()
+                .any(|x| x.to_string_lossy().starts_with("refresh=")));
+            assert!(!args
+                .iter()
+                .any(|x| x.to_string_lossy().starts_with("sig_figs=")));
+
+            let x = tree.to_stmt().to_str().unwrap().parse::<ArgTree>().unwrap();
+            assert_eq!(x, tree);
+            assert!(x.eq_canonical(&tree));
+            assert!(x.diff(&tree).is_empty());
+            // As are the arguments, in which CmdStan's defaults are implied.
+            let args: Vec<_> = args.iter().map(|x| x.to_str().unwrap()).collect();
+            let x = args.join(" ").parse::<ArgTree>().unwrap();
+            assert_eq!(x, tree);
+            assert!(x.diff(&tree).is_empty());
+        }
+
+        #[test]
+        fn try_build() {
+            for n in [-1, 0, 1, 17, 18] {
+                let x = Output::builder().sig_figs(n).try_build().unwrap();
+                assert_eq!(x.sig_figs, n);
+            }
+            for n in [-2, 19, i32::MIN, i32::MAX] {
+                let e = Output::builder().sig_figs(n).try_build().unwrap_err();
+                assert_eq!(e.kind(), ErrorKind::Validation);
+            }
+            assert!(Output::builder().refresh(0).try_build().is_ok());
+            assert!(Output::builder().refresh(-1).try_build().is_err());
+            assert_eq!(Output::builder().try_build().unwrap(), Output::default());
+        }
+
         #[test]
         fn to_args() {
             let mut x = Output::default();
@@ -1,18 +1,27 @@
-use crate::argtree::ArgTree;
+use crate::argtree::{ArgTree, ChainIdAllocator, Data};
+use crate::compile::StancWarning;
 use crate::consts::*;
+use crate::draws::{Adaptation, DrawsIter, DrawsTable, ExportFormat, LogProbs};
 use crate::error::*;
-use crate::stansummary::StanSummaryOptions;
+use crate::method::{Engine, LogProbBuilder, Method, SampleAlgorithm};
+use crate::model_info::ModelStructure;
+use crate::progress::{Progress, ProgressConfig};
+use crate::stansummary::{StanSummaryOptions, Summary};
+use crate::temp::ManagedTempFiles;
 use crate::translate::Translate;
 use std::{
+    collections::HashMap,
     convert::TryFrom,
     env,
     ffi::{OsStr, OsString},
+    fmt,
     fs::{self, File},
     hash::Hash,
-    io::{self, Read},
+    io::{self, BufRead, BufReader, Read, Write},
     path::{Path, PathBuf},
     process::{self, Command, Stdio},
-    sync::{Arc, RwLock},
+    sync::{Arc, OnceLock, RwLock},
+    time::Instant,
 };
 
 /// Try to determine if the file exists by attempting to open it in read-only mode.
@@ -36,12 +45,25 @@ fn try_help<S: AsRef<OsStr>>(path: S, help: &'static str) -> Result<process::Out
     Command::new(path).arg(help).output()
 }
 
+/// Return `true` if `path` was modified more recently than `than`.
+/// If either modification time cannot be determined, then the answer
+/// is conservatively `false`.
+fn is_newer(path: &Path, than: &Path) -> bool {
+    let mtime = |p: &Path| fs::metadata(p).and_then(|m| m.modified());
+    match (mtime(path), mtime(than)) {
+        (Ok(a), Ok(b)) => a > b,
+        _ => false,
+    }
+}
+
 /// Holds an absolute path to a Stan program. Invariants established
 /// at the time of construction cannot be guaranteed to be true at all times,
 /// as it is always possible to modify or delete the underlying file.
 #[derive(Debug, Clone, Hash, Eq, Ord, PartialEq, PartialOrd)]
 pub struct StanProgram {
     path: PathBuf,
+    /// The directory which holds the program, if given inline.
+    temp: Option<ManagedTempFiles>,
 }
 
 impl TryFrom<&Path> for StanProgram {
@@ -74,7 +96,64 @@ impl TryFrom<&Path> for StanProgram {
         // their relative path shenanigans accordingly.
         let path = fs::canonicalize(path).map_err(op)?;
         try_open(&path).map_err(op)?;
-        Ok(Self { path })
+        Ok(Self { path, temp: None })
+    }
+}
+
+/// 64-bit FNV-1a hash of `bytes`. Unlike the hasher of the standard
+/// library, the result is specified, hence, stable across versions
+/// of Rust and across platforms.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+    bytes.iter().fold(OFFSET_BASIS, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(PRIME)
+    })
+}
+
+impl StanProgram {
+    /// Create an instance from the given `source`, which is written to
+    /// `{name}.stan` in a [`ManagedTempFiles`]. The file (and the
+    /// executable, once compiled) is deleted when the program, and any
+    /// model compiled from it, is dropped.
+    ///
+    /// `name` must be a plain file name, without path separators,
+    /// else an error of kind [`ErrorKind::Validation`] is returned.
+    pub fn from_source(name: &str, source: &str) -> Result<Self, Error> {
+        if !crate::temp::is_file_name(name) {
+            return Err(Error::validation(format!(
+                "program name must not contain path separators, got {name:?}"
+            )));
+        }
+        let op = |e: io::Error| Error::new(ErrorKind::ModelFile, e.into());
+        let temp = ManagedTempFiles::new().map_err(op)?;
+        let path = temp.write(&format!("{name}.stan"), source).map_err(op)?;
+        let program = Self::try_from(path.as_ref())?;
+        Ok(Self {
+            temp: Some(temp),
+            ..program
+        })
+    }
+
+    /// Return the absolute path to the Stan program.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Read the source of the Stan program.
+    pub fn source(&self) -> io::Result<String> {
+        fs::read_to_string(&self.path)
+    }
+
+    /// Return a stable hash of the contents of the Stan program.
+    ///
+    /// The hash is content-based, not path-based: moving or copying
+    /// the file does not change the hash, whereas any change to its
+    /// bytes (including whitespace and comments) does. Together with
+    /// the statement of an [`ArgTree`] (see [`Translate::to_stmt`]),
+    /// this suffices to key a cache of runs.
+    pub fn content_hash(&self) -> io::Result<u64> {
+        fs::read(&self.path).map(|bytes| fnv1a(&bytes))
     }
 }
 
@@ -85,12 +164,26 @@ impl TryFrom<&Path> for StanProgram {
 /// repetition. Perhaps perhaps more importantly, it enables the methods and
 /// associated functions of the `CmdStan` type to be written with clarity,
 /// since any operation must acquire this resource.
-#[derive(Debug, Clone, Hash, Eq, Ord, PartialEq, PartialOrd)]
+#[derive(Debug, Clone)]
 struct CmdStanInner {
     root: PathBuf,
     stanc: PathBuf,
     stansummary: PathBuf,
     diagnose: PathBuf,
+    /// Models produced by `CmdStan::compile`, keyed by the (canonicalized)
+    /// path of the program.
+    models: HashMap<PathBuf, CompiledModel>,
+}
+
+/// A model produced by `CmdStan::compile`, along with the content hash
+/// of the program and the arguments with which it was compiled, either
+/// of which, if changed, invalidates the model.
+#[derive(Debug, Clone)]
+struct CompiledModel {
+    hash: u64,
+    args: Vec<OsString>,
+    model: CmdStanModel,
+    warnings: Vec<StancWarning>,
 }
 
 macro_rules! impl_try_ensure {
@@ -154,15 +247,42 @@ impl CmdStanInner {
         diagnose
     );
 
+    /// Ensure that all of the utilities are built, in the order
+    /// required to establish the invariants.
+    fn try_ensure_utilities(&self) -> Result<(), Error> {
+        self.try_ensure_stanc()?;
+        self.try_ensure_stansummary()?;
+        self.try_ensure_diagnose()
+    }
+
+    /// Parse `CMDSTAN_VERSION` from the makefile in the root directory.
+    fn version(&self) -> Option<(u32, u32, u32)> {
+        let makefile = fs::read_to_string(self.root.join("makefile")).ok()?;
+        let line = makefile
+            .lines()
+            .find(|line| line.trim_start().starts_with("CMDSTAN_VERSION"))?;
+        let (_, version) = line.split_once('=')?;
+        let mut iter = version.trim().splitn(3, '.').map(|x| {
+            // Tolerate suffixes such as `-rc1` on the last component.
+            let end = x.find(|c: char| !c.is_ascii_digit()).unwrap_or(x.len());
+            x[..end].parse::<u32>().ok()
+        });
+        Some((iter.next()??, iter.next()??, iter.next()??))
+    }
+
     fn make<S: AsRef<OsStr>>(&self, arg: S) -> io::Result<process::Output> {
         Command::new(MAKE).current_dir(&self.root).arg(arg).output()
     }
 }
 
-impl TryFrom<&Path> for CmdStanInner {
-    type Error = Error;
-    fn try_from(path: &Path) -> Result<Self, Self::Error> {
-        let install_err = |e: io::Error| Self::Error::new(ErrorKind::Install, e.into());
+/** Checks which, taken together, establish the invariants of `CmdStan`.
+Each is separate, such that all may be run by [`CmdStan::check`].
+*/
+impl CmdStanInner {
+    /// Construct with the canonicalized `path` as root, without any checks
+    /// other than that the directory exists and is accessible.
+    fn new(path: &Path) -> Result<Self, Error> {
+        let install_err = |e: io::Error| Error::new(ErrorKind::Install, e.into());
         // A key invariant is that `CmdStan` can work from anywhere,
         // thus, we need an absolute path for the proposed root.
         // All subsequent invariants will be established on the basis
@@ -173,15 +293,6 @@ impl TryFrom<&Path> for CmdStanInner {
         // and is accessible is to attempt to read it.
         fs::read_dir(&root).map_err(install_err)?;
 
-        // Superficial check for make
-        let output = Command::new(MAKE)
-            .current_dir(&root)
-            .output()
-            .map_err(|e| Self::Error::new(ErrorKind::Make, e.into()))?;
-        Self::Error::appears_ok(ErrorKind::Make, output)?;
-
-        // Since things appear to work on the surface, initialize
-        // and use the stock methods to verify.
         let mut stanc = root.clone();
         stanc.push("bin");
         stanc.push(STANC);
@@ -194,16 +305,137 @@ impl TryFrom<&Path> for CmdStanInner {
         diagnose.pop();
         diagnose.push(DIAGNOSE);
 
-        let inner = Self {
+        Ok(Self {
             root,
             stanc,
             stansummary,
             diagnose,
-        };
+            models: HashMap::new(),
+        })
+    }
+
+    /// Superficial check for make
+    fn check_make(&self) -> Result<(), Error> {
+        let output = Command::new(MAKE)
+            .current_dir(&self.root)
+            .output()
+            .map_err(|e| Error::new(ErrorKind::Make, e.into()))?;
+        Error::appears_ok(ErrorKind::Make, output)
+    }
+
+    fn bernoulli_exec(&self) -> PathBuf {
+        let mut exec = self.root.clone();
+        exec.push("examples");
+        exec.push("bernoulli");
+        exec.push("bernoulli");
+        exec.set_extension(OS_EXE_EXT);
+        exec
+    }
+
+    fn check_bernoulli_compile(&self) -> Result<(), Error> {
+        let output = self
+            .make(MAKE_BERNOULLI)
+            .map_err(|e| Error::new(ErrorKind::Bernoulli, e.into()))?;
+        Error::ensure_success(ErrorKind::Bernoulli, output)?;
+        try_open(self.bernoulli_exec()).map_err(|e| Error::new(ErrorKind::Bernoulli, e.into()))
+    }
+
+    fn check_bernoulli_sample(&self) -> Result<(), Error> {
+        let output = Command::new(self.bernoulli_exec())
+            .current_dir(&self.root)
+            .arg("sample")
+            .arg("data")
+            .arg("file=examples/bernoulli/bernoulli.data.json")
+            .output()
+            .map_err(|e| Error::new(ErrorKind::Bernoulli, e.into()))?;
+        Error::appears_ok(ErrorKind::Bernoulli, output)
+    }
+
+    fn check_bernoulli_stansummary(&self) -> Result<(), Error> {
+        let output = Command::new(&self.stansummary)
+            .current_dir(&self.root)
+            .arg("output.csv")
+            .output()
+            .map_err(|e| Error::new(ErrorKind::StanSummary, e.into()))?;
+
+        let output = Error::ensure_success(ErrorKind::StanSummary, output)?;
 
-        inner.try_ensure_stanc()?;
-        inner.try_ensure_stansummary()?;
-        inner.try_ensure_diagnose()?;
+        let stdout = String::from_utf8_lossy(&output.stdout[..]);
+        if let Some(line) = stdout.lines().find(|l| l.starts_with("theta")) {
+            let mut iter = line.split_whitespace();
+            let f = |x: &str| x.parse::<f64>().ok();
+            let mean = iter.nth(1).and_then(f);
+            let stddev = iter.nth(1).and_then(f);
+            match (mean, stddev) {
+                (Some(mean), Some(stddev)) if mean - stddev < 0.2 && 0.2 < mean + stddev => Ok(()),
+                _ => Err(Error::new(ErrorKind::Bernoulli, output.into())),
+            }
+        } else {
+            Err(Error::new(ErrorKind::Bernoulli, output.into()))
+        }
+    }
+
+    fn check_bernoulli_diagnose(&self) -> Result<(), Error> {
+        let output = Command::new(&self.diagnose)
+            .current_dir(&self.root)
+            .arg("output.csv")
+            .output()
+            .map_err(|e| Error::new(ErrorKind::Diagnose, e.into()))?;
+        let output = Error::ensure_success(ErrorKind::Diagnose, output)?;
+        let stdout = String::from_utf8_lossy(&output.stdout[..]);
+        if stdout.contains("Processing complete, no problems detected") {
+            Ok(())
+        } else {
+            Err(Error::new(ErrorKind::Bernoulli, output.into()))
+        }
+    }
+}
+
+type Check = fn(&CmdStanInner) -> Result<(), Error>;
+
+/// The checks run by [`CmdStan::check`], in order, each with the
+/// checks on which it depends.
+const CHECKS: [(&str, Check, &[&str]); 8] = [
+    ("make", CmdStanInner::check_make, &[]),
+    ("stanc", CmdStanInner::try_ensure_stanc, &["make"]),
+    (
+        "stansummary",
+        CmdStanInner::try_ensure_stansummary,
+        &["make"],
+    ),
+    ("diagnose", CmdStanInner::try_ensure_diagnose, &["make"]),
+    (
+        "bernoulli compile",
+        CmdStanInner::check_bernoulli_compile,
+        &["stanc"],
+    ),
+    (
+        "bernoulli sample",
+        CmdStanInner::check_bernoulli_sample,
+        &["bernoulli compile"],
+    ),
+    (
+        "bernoulli stansummary",
+        CmdStanInner::check_bernoulli_stansummary,
+        &["stansummary", "bernoulli sample"],
+    ),
+    (
+        "bernoulli diagnose",
+        CmdStanInner::check_bernoulli_diagnose,
+        &["diagnose", "bernoulli sample"],
+    ),
+];
+
+impl TryFrom<&Path> for CmdStanInner {
+    type Error = Error;
+    fn try_from(path: &Path) -> Result<Self, Self::Error> {
+        let inner = Self::new(path)?;
+
+        inner.check_make()?;
+
+        // Since things appear to work on the surface, use the stock
+        // methods to verify.
+        inner.try_ensure_utilities()?;
 
         Ok(inner)
     }
@@ -265,126 +497,472 @@ impl TryFrom<&Path> for CmdStan {
 
         // Rather than verify individual files, a simple way to
         // verify CmdStan works is to build and run the bernoulli example
-        let output = inner
-            .make(MAKE_BERNOULLI)
-            .map_err(|e| Self::Error::new(ErrorKind::Bernoulli, e.into()))?;
-        if !output.status.success() {
-            return Err(Self::Error::new(ErrorKind::Bernoulli, output.into()));
-        }
+        inner.check_bernoulli_compile()?;
+        inner.check_bernoulli_sample()?;
+        inner.check_bernoulli_stansummary()?;
+        inner.check_bernoulli_diagnose()?;
 
-        let mut exec = inner.root.clone();
-        exec.push("examples");
-        exec.push("bernoulli");
-        exec.push("bernoulli");
-        exec.set_extension(OS_EXE_EXT);
-        try_open(&exec).map_err(|e| Self::Error::new(ErrorKind::Bernoulli, e.into()))?;
+        Ok(Self {
+            inner: Arc::new(RwLock::new(inner)),
+        })
+    }
+}
 
-        let output = Command::new(&exec)
-            .current_dir(&inner.root)
-            .arg("sample")
-            .arg("data")
-            .arg("file=examples/bernoulli/bernoulli.data.json")
-            .output()
-            .map_err(|e| Self::Error::new(ErrorKind::Bernoulli, e.into()))?;
-        Self::Error::appears_ok(ErrorKind::Bernoulli, output)?;
+/// Selection of the checks run by [`CmdStan::try_from_with_checks`].
+/// Each field corresponds to the check of the same name reported by
+/// [`CmdStan::check`]; [`InstallChecks::all`] (the default) is the
+/// selection of [`CmdStan::try_from`].
+///
+/// Dependencies between checks are not implied: e.g. selecting
+/// `bernoulli_sample` without `bernoulli_compile` succeeds only if
+/// the example was previously compiled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InstallChecks {
+    pub make: bool,
+    pub stanc: bool,
+    pub stansummary: bool,
+    pub diagnose: bool,
+    pub bernoulli_compile: bool,
+    pub bernoulli_sample: bool,
+    pub bernoulli_stansummary: bool,
+    pub bernoulli_diagnose: bool,
+}
 
-        let output = Command::new(&inner.stansummary)
-            .current_dir(&inner.root)
-            .arg("output.csv")
-            .output()
-            .map_err(|e| Self::Error::new(ErrorKind::StanSummary, e.into()))?;
+impl InstallChecks {
+    /// Select every check.
+    pub const fn all() -> Self {
+        Self {
+            make: true,
+            stanc: true,
+            stansummary: true,
+            diagnose: true,
+            bernoulli_compile: true,
+            bernoulli_sample: true,
+            bernoulli_stansummary: true,
+            bernoulli_diagnose: true,
+        }
+    }
 
-        if !output.status.success() {
-            return Err(Self::Error::new(ErrorKind::StanSummary, output.into()));
+    /// Select no check, such that only the existence of the directory
+    /// is verified.
+    pub const fn none() -> Self {
+        Self {
+            make: false,
+            stanc: false,
+            stansummary: false,
+            diagnose: false,
+            bernoulli_compile: false,
+            bernoulli_sample: false,
+            bernoulli_stansummary: false,
+            bernoulli_diagnose: false,
         }
+    }
 
-        let stdout = String::from_utf8_lossy(&output.stdout[..]);
-        if let Some(line) = stdout.lines().find(|l| l.starts_with("theta")) {
-            let mut iter = line.split_whitespace();
-            let f = |x: &str| x.parse::<f64>().ok();
-            let mean = iter.nth(1).and_then(f);
-            let stddev = iter.nth(1).and_then(f);
-            match (mean, stddev) {
-                (Some(mean), Some(stddev)) if mean - stddev < 0.2 && 0.2 < mean + stddev => (),
-                _ => return Err(Self::Error::new(ErrorKind::Bernoulli, output.into())),
-            }
-        } else {
-            return Err(Self::Error::new(ErrorKind::Bernoulli, output.into()));
+    /// Select every check other than those which involve `diagnose`,
+    /// for installations in which it is absent or broken.
+    pub const fn without_diagnose() -> Self {
+        Self {
+            diagnose: false,
+            bernoulli_diagnose: false,
+            ..Self::all()
         }
+    }
 
-        let output = Command::new(&inner.diagnose)
-            .current_dir(&inner.root)
-            .arg("output.csv")
-            .output()
-            .map_err(|e| Self::Error::new(ErrorKind::Diagnose, e.into()))?;
-        if !output.status.success() {
-            return Err(Self::Error::new(ErrorKind::Diagnose, output.into()));
+    /// Return `true` if the check of the given `name` (as in [`CHECKS`])
+    /// is selected.
+    fn includes(&self, name: &str) -> bool {
+        match name {
+            "make" => self.make,
+            "stanc" => self.stanc,
+            "stansummary" => self.stansummary,
+            "diagnose" => self.diagnose,
+            "bernoulli compile" => self.bernoulli_compile,
+            "bernoulli sample" => self.bernoulli_sample,
+            "bernoulli stansummary" => self.bernoulli_stansummary,
+            "bernoulli diagnose" => self.bernoulli_diagnose,
+            _ => false,
         }
-        let stdout = String::from_utf8_lossy(&output.stdout[..]);
-        if !stdout.contains("Processing complete, no problems detected") {
-            return Err(Self::Error::new(ErrorKind::Bernoulli, output.into()));
+    }
+}
+
+impl Default for InstallChecks {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+/// Environment variables which may hold the path to a CmdStan
+/// installation, in order of precedence.
+const CMDSTAN_ENV_VARS: [&str; 2] = ["CMDSTAN", "CMDSTAN_HOME"];
+
+impl CmdStan {
+    /// Try to create an instance from the path given by the environment
+    /// variable `CMDSTAN`, else `CMDSTAN_HOME`, as by [`CmdStan::try_from`].
+    /// Each variable which is set is tried in turn; if none is set, or
+    /// construction fails for each, an error of kind `Install` is returned
+    /// which lists the variables checked and the reason each failed.
+    pub fn from_env() -> Result<Self, Error> {
+        Self::from_vars(|var| env::var_os(var))
+    }
+
+    fn from_vars<F>(get: F) -> Result<Self, Error>
+    where
+        F: Fn(&str) -> Option<OsString>,
+    {
+        let mut reasons = Vec::with_capacity(CMDSTAN_ENV_VARS.len());
+        for var in CMDSTAN_ENV_VARS {
+            match get(var) {
+                Some(path) => match Self::try_from(Path::new(&path)) {
+                    Ok(cmdstan) => return Ok(cmdstan),
+                    Err(e) => reasons.push(format!("{var}={}: {e:#}", path.to_string_lossy())),
+                },
+                None => reasons.push(format!("{var}: not set")),
+            }
         }
+        let msg = format!(
+            "no CmdStan installation found via environment ({})",
+            reasons.join("; ")
+        );
+        Err(Error::new(
+            ErrorKind::Install,
+            io::Error::new(io::ErrorKind::NotFound, msg).into(),
+        ))
+    }
 
+    /// As [`CmdStan::try_from`], but run only the checks selected by
+    /// `checks`, in the same order, stopping at the first failure.
+    ///
+    /// Invariants which are not checked are not established; e.g.
+    /// without the `diagnose` check, [`CmdStan::diagnose`] may fail.
+    /// Note that [`CmdStan::clean`] re-builds all of the utilities,
+    /// regardless of the checks with which the instance was constructed.
+    pub fn try_from_with_checks(path: &Path, checks: InstallChecks) -> Result<Self, Error> {
+        let inner = CmdStanInner::new(path)?;
+        for (name, check, _) in CHECKS {
+            if checks.includes(name) {
+                check(&inner)?;
+            }
+        }
         Ok(Self {
             inner: Arc::new(RwLock::new(inner)),
         })
     }
+
+    /// Run all of the checks performed by [`CmdStan::try_from`], without
+    /// stopping at the first failure, and report the outcome of each.
+    /// A check is skipped if any check on which it depends did not pass,
+    /// e.g. the Bernoulli example is not run if it failed to compile.
+    /// This is intended for diagnosis of a broken installation.
+    pub fn check(path: &Path) -> InstallReport {
+        let mut checks = Vec::with_capacity(CHECKS.len() + 1);
+        let inner = match CmdStanInner::new(path) {
+            Ok(inner) => {
+                checks.push(InstallCheck::new("directory", CheckStatus::Passed));
+                Some(inner)
+            }
+            Err(e) => {
+                checks.push(InstallCheck::new("directory", CheckStatus::Failed(e)));
+                None
+            }
+        };
+        for (name, check, requires) in CHECKS {
+            let passed = |dep: &&str| {
+                checks
+                    .iter()
+                    .any(|x: &InstallCheck| x.name == *dep && x.passed())
+            };
+            let status = match &inner {
+                Some(inner) if requires.iter().all(passed) => match check(inner) {
+                    Ok(()) => CheckStatus::Passed,
+                    Err(e) => CheckStatus::Failed(e),
+                },
+                _ => CheckStatus::Skipped,
+            };
+            checks.push(InstallCheck::new(name, status));
+        }
+        InstallReport { checks }
+    }
+}
+
+/// Outcome of a single check of an installation.
+#[derive(Debug)]
+pub enum CheckStatus {
+    Passed,
+    Failed(Error),
+    /// Not run, as a check on which it depends did not pass.
+    Skipped,
+}
+
+/// A named check of an installation, with its outcome.
+#[derive(Debug)]
+pub struct InstallCheck {
+    name: &'static str,
+    status: CheckStatus,
+}
+
+impl InstallCheck {
+    fn new(name: &'static str, status: CheckStatus) -> Self {
+        Self { name, status }
+    }
+    /// Return the name of the check, e.g. `"stanc"` or `"bernoulli sample"`.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+    /// Return the outcome of the check.
+    pub fn status(&self) -> &CheckStatus {
+        &self.status
+    }
+    /// Return `true` if the check passed.
+    pub fn passed(&self) -> bool {
+        matches!(self.status, CheckStatus::Passed)
+    }
+}
+
+/// The outcome of each check of an installation, as produced by
+/// [`CmdStan::check`]. The `Display` implementation renders one
+/// line per check.
+#[derive(Debug)]
+pub struct InstallReport {
+    checks: Vec<InstallCheck>,
+}
+
+impl InstallReport {
+    /// Return the checks, in the order in which they were run.
+    pub fn checks(&self) -> &[InstallCheck] {
+        &self.checks
+    }
+    /// Return `true` if every check passed, in which case
+    /// [`CmdStan::try_from`] would be expected to succeed.
+    pub fn is_ok(&self) -> bool {
+        self.checks.iter().all(InstallCheck::passed)
+    }
+    /// Return the checks which failed.
+    pub fn failures(&self) -> Vec<&InstallCheck> {
+        self.checks
+            .iter()
+            .filter(|x| matches!(x.status, CheckStatus::Failed(_)))
+            .collect()
+    }
+}
+
+impl fmt::Display for InstallReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            match &check.status {
+                CheckStatus::Passed => writeln!(f, "{}: ok", check.name)?,
+                CheckStatus::Failed(e) => writeln!(f, "{}: FAILED: {:#}", check.name, e)?,
+                CheckStatus::Skipped => writeln!(f, "{}: skipped", check.name)?,
+            }
+        }
+        Ok(())
+    }
 }
 
 /** Operations which acquire write access
 
 - `compile` : has the potential to modify all files in the root directory of `self`.
 - `stanc` : may write to a `StanProgram`'s (generated) C++ program file; such a write
-would race with other such `stanc` calls.
+  would race with other such `stanc` calls.
+- `clean` : deletes all build products in the root directory of `self`.
 */
 impl CmdStan {
+    /// Run `make clean-all` in the root directory, then re-build the
+    /// `stanc`, `stansummary` and `diagnose` utilities, thereby restoring
+    /// the invariants established at construction.
+    ///
+    /// This should be preferred to passing `"clean-all"` to
+    /// [`CmdStan::compile`]. Note that any previously compiled models
+    /// will need to be re-compiled, as the precompiled headers and
+    /// libraries will have been deleted.
+    pub fn clean(&self) -> Result<(), Error> {
+        let mut guard = self.inner.write().unwrap();
+        guard.models.clear();
+        let output = guard
+            .make("clean-all")
+            .map_err(|e| Error::new(ErrorKind::Make, e.into()))?;
+        Error::ensure_success(ErrorKind::Make, output)?;
+        guard.try_ensure_utilities()
+    }
+
+    /// Compile `program`, passing `args` to make, and return the model.
+    /// Typed options may be given by means of
+    /// [`CompileOptions::command_fragment`](crate::compile::CompileOptions::command_fragment).
+    ///
+    /// Models are cached per program: if `program` was previously compiled
+    /// by `self` (or a clone) with the same `args`, its contents are
+    /// unchanged since (as determined by [`StanProgram::content_hash`]),
+    /// and the executable still exists, then the model is returned without
+    /// invoking make or re-testing the executable. Compilation with
+    /// `"clean-all"` among `args`, or [`CmdStan::clean`], empties the cache.
+    /// Programs given inline ([`StanProgram::from_source`]) are not cached.
+    /// Otherwise, make is always invoked; see [`CmdStan::compile_if_stale`]
+    /// to skip make when the executable is up to date.
     pub fn compile<I, S>(&self, program: &StanProgram, args: I) -> Result<CmdStanModel, Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.compile_with_warnings(program, args)
+            .map(|(model, _)| model)
+    }
+
+    /// As [`CmdStan::compile`], but also return the warnings which stanc
+    /// emitted, e.g. with `STANCFLAGS=--warn-pedantic` among `args`.
+    /// A cached model is returned with the warnings of the compilation
+    /// which produced it.
+    pub fn compile_with_warnings<I, S>(
+        &self,
+        program: &StanProgram,
+        args: I,
+    ) -> Result<(CmdStanModel, Vec<StancWarning>), Error>
     where
         I: IntoIterator<Item = S>,
         S: AsRef<OsStr>,
     {
         let exec = program.path.with_extension(OS_EXE_EXT);
+        let args: Vec<OsString> = args
+            .into_iter()
+            .map(|s| s.as_ref().to_os_string())
+            .collect();
+        // If the program cannot be read, then make will fail,
+        // hence, there is no need to handle the error here.
+        let hash = program.content_hash().ok();
 
         // Compilation has the potential to touch all of the files in
         // the CmdStan directory.
-        let guard = self.inner.write().unwrap();
+        let mut guard = self.inner.write().unwrap();
+
+        if let (Some(hash), Some(x)) = (hash, guard.models.get(&program.path)) {
+            if x.hash == hash && x.args == args && x.model.exec.is_file() {
+                return Ok((x.model.clone(), x.warnings.clone()));
+            }
+        }
 
         // We need to detect whether the diagnose and stansummary utilities
         // will be deleted. If combined with invalid unicode, it will be difficult
         // to detect whether `clean-all` is actually passed to make --
         // we would hope that make fails.
-        let mut state = false;
-        let args = args.into_iter().inspect(|os| {
-            state |= os
-                .as_ref()
-                .to_str()
-                .is_some_and(|s| s.trim() == "clean-all")
-        });
+        let state = args
+            .iter()
+            .any(|os| os.to_str().is_some_and(|s| s.trim() == "clean-all"));
 
         // This is lazy, but, not unreasonable given the myriad ways in which
         // compilation can fail.
         let output = Command::new(MAKE)
             .current_dir(&guard.root)
-            .args(args)
+            .args(&args)
             .arg(&exec)
             .output()
             .map_err(|e| Error::new(ErrorKind::Compilation, e.into()))?;
 
-        if !output.status.success() {
-            return Err(Error::new(ErrorKind::Compilation, output.into()));
+        let output = Error::ensure_success(ErrorKind::Compilation, output)?;
+        let mut warnings = StancWarning::parse_all(&String::from_utf8_lossy(&output.stderr));
+        for warning in warnings.iter_mut() {
+            warning.restore_file(&program.path);
         }
 
         // If `clean-all` occurred, then we need to re-build the utilities
-        // in order to maintain the invariants.
+        // in order to maintain the invariants. Every other model
+        // will need to be re-compiled.
         if state {
-            guard.try_ensure_stanc()?;
-            guard.try_ensure_stansummary()?;
-            guard.try_ensure_diagnose()?;
+            guard.models.clear();
+            guard.try_ensure_utilities()?;
         }
 
         // Then, we subject the binary to the same tests as are required
         // to construct directly from a path.
-        CmdStanModel::try_from(exec.as_ref())
+        let model = CmdStanModel {
+            temp: program.temp.clone(),
+            ..CmdStanModel::try_from(exec.as_ref())?
+        };
+        match hash {
+            // Compilation with `clean-all` should not be skipped on repetition.
+            // A program given inline is unique to its temporary directory,
+            // and the cache must not keep the directory alive.
+            Some(hash) if !state && program.temp.is_none() => {
+                let x = CompiledModel {
+                    hash,
+                    args,
+                    model: model.clone(),
+                    warnings: warnings.clone(),
+                };
+                guard.models.insert(program.path.clone(), x);
+            }
+            _ => {
+                guard.models.remove(&program.path);
+            }
+        }
+        Ok((model, warnings))
+    }
+
+    /// As [`CmdStan::compile`] without arguments, but make is not invoked
+    /// if the executable is newer (by modification time) than the program
+    /// and each file which it `#include`s (see [`CmdStan::model_info`]);
+    /// the executable is then only subjected to the tests of
+    /// [`CmdStanModel::try_from`]. This is cheaper than a rebuild by make
+    /// which turns out to be a no-op, as make must evaluate the CmdStan
+    /// makefiles to reach that conclusion.
+    ///
+    /// The executable is taken as is: it is not checked against the
+    /// arguments with which it was built (e.g. `STAN_THREADS=true`),
+    /// nor against a rebuild or upgrade of CmdStan. In such cases, use
+    /// [`CmdStan::compile`]. If make is invoked, then any cached model
+    /// of the program is discarded beforehand.
+    pub fn compile_if_stale(&self, program: &StanProgram) -> Result<CmdStanModel, Error> {
+        let exec = program.path.with_extension(OS_EXE_EXT);
+        if is_newer(&exec, &program.path) && self.includes_older(program, &exec) {
+            Ok(CmdStanModel {
+                temp: program.temp.clone(),
+                ..CmdStanModel::try_from(exec.as_ref())?
+            })
+        } else {
+            // The cache does not account for included files.
+            self.inner.write().unwrap().models.remove(&program.path);
+            self.compile::<[_; 0], &str>(program, [])
+        }
+    }
+
+    /// Return `true` if every file which `program` includes is older than
+    /// `exec`. Relative paths are resolved against the directory of the
+    /// program. If the includes cannot be determined, then the answer is
+    /// conservatively `false`.
+    fn includes_older(&self, program: &StanProgram, exec: &Path) -> bool {
+        let dir = program.path.parent().unwrap_or(Path::new(""));
+        self.model_info(program).is_ok_and(|info| {
+            info.included_files
+                .iter()
+                .all(|file| is_newer(exec, &dir.join(file)))
+        })
+    }
+
+    /// Return the structure of `program` (its variables, by block), as
+    /// reported by `stanc --info`, without compiling it.
+    pub fn model_info(&self, program: &StanProgram) -> Result<ModelStructure, Error> {
+        let output = self.stanc(program, ["--info"])?;
+        let output = Error::ensure_success(ErrorKind::StanC, output)?;
+        String::from_utf8_lossy(&output.stdout)
+            .parse::<ModelStructure>()
+            .map_err(|e| {
+                Error::new(
+                    ErrorKind::StanC,
+                    io::Error::new(io::ErrorKind::InvalidData, e).into(),
+                )
+            })
+    }
+
+    /// Return the source of `program` as formatted by `stanc --auto-format`,
+    /// without compiling it. The program must be free of syntax errors.
+    /// For the canonical form (e.g. with deprecated syntax updated), call
+    /// [`CmdStan::stanc`] with `--print-canonical`.
+    pub fn format_model(&self, program: &StanProgram) -> Result<String, Error> {
+        let output = self.stanc(program, ["--auto-format"])?;
+        let output = Error::ensure_success(ErrorKind::StanC, output)?;
+        String::from_utf8(output.stdout).map_err(|e| {
+            Error::new(
+                ErrorKind::StanC,
+                io::Error::new(io::ErrorKind::InvalidData, e).into(),
+            )
+        })
     }
 
     pub fn stanc<I, S>(&self, program: &StanProgram, args: I) -> Result<process::Output, Error>
@@ -402,40 +980,293 @@ impl CmdStan {
     }
 }
 
+/// Asynchronous counterparts, available with the `tokio` feature.
+#[cfg(feature = "tokio")]
+impl CmdStan {
+    /// Asynchronous counterpart of [`CmdStan::compile`].
+    ///
+    /// Compilation must hold the write lock for its entire duration,
+    /// and the lock is synchronous; holding it across an `.await`
+    /// would block the executor and render the future `!Send`. Hence,
+    /// compilation runs on the blocking thread pool of the runtime,
+    /// and the semantics are exactly those of the synchronous version.
+    pub async fn compile_async<I, S>(
+        &self,
+        program: &StanProgram,
+        args: I,
+    ) -> Result<CmdStanModel, Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let cmdstan = self.clone();
+        let program = program.clone();
+        let args: Vec<OsString> = args
+            .into_iter()
+            .map(|s| s.as_ref().to_os_string())
+            .collect();
+        tokio::task::spawn_blocking(move || cmdstan.compile(&program, args))
+            .await
+            .map_err(|e| Error::new(ErrorKind::Compilation, io::Error::other(e).into()))?
+    }
+}
+
 /** Operations which acquire read access
 
 - `diagnose` : does not modify any files in the root directory of `self`
 - `stansummary` : does not modify any files in the root directory of `self`
+- `examples` : does not modify any files in the root directory of `self`
 */
 impl CmdStan {
+    /// Return the Stan programs in the `examples` directory of the
+    /// installation (e.g. `examples/bernoulli/bernoulli.stan`), searched
+    /// recursively, sorted by path. If the directory is absent, no
+    /// programs are returned.
+    pub fn examples(&self) -> Result<Vec<StanProgram>, Error> {
+        fn visit(dir: &Path, paths: &mut Vec<PathBuf>) -> io::Result<()> {
+            for entry in fs::read_dir(dir)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    visit(&path, paths)?;
+                } else if path.extension().is_some_and(|ext| ext == "stan") {
+                    paths.push(path);
+                }
+            }
+            Ok(())
+        }
+        let guard = self.inner.read().unwrap();
+        let dir = guard.root.join("examples");
+        let mut paths = Vec::new();
+        if dir.is_dir() {
+            visit(&dir, &mut paths).map_err(|e| Error::new(ErrorKind::ModelFile, e.into()))?;
+        }
+        paths.sort();
+        paths
+            .iter()
+            .map(|path| StanProgram::try_from(path.as_ref()))
+            .collect()
+    }
+
+    /// Return the version of CmdStan as `(major, minor, patch)`, as declared
+    /// by `CMDSTAN_VERSION` in the makefile of the root directory,
+    /// or `None` if it cannot be determined.
+    pub fn version(&self) -> Option<(u32, u32, u32)> {
+        let guard = self.inner.read().unwrap();
+        guard.version()
+    }
+
+    /// Run `diagnose` on the output files of `output` which exist.
+    /// If none exist, e.g. as the call failed, an error of kind
+    /// `Diagnose` is returned without running `diagnose`.
     pub fn diagnose(&self, output: &CmdStanOutput) -> Result<process::Output, Error> {
+        self.diagnose_files(Self::present_files(output, ErrorKind::Diagnose)?)
+    }
+
+    /// As [`CmdStan::diagnose`], but restricted to the chains with the
+    /// given `ids` (see [`CmdStanOutput::output_files_for_chains`]).
+    /// If none of the chains has an output file, an error of kind
+    /// `Diagnose` is returned, as for [`CmdStan::diagnose`].
+    pub fn diagnose_chains(
+        &self,
+        output: &CmdStanOutput,
+        ids: &[i32],
+    ) -> Result<process::Output, Error> {
+        self.diagnose_files(Self::chain_files(output, ids, ErrorKind::Diagnose)?)
+    }
+
+    fn diagnose_files(&self, files: Vec<PathBuf>) -> Result<process::Output, Error> {
         let guard = self.inner.read().unwrap();
         Command::new(&guard.diagnose)
-            .args(output.output_files())
+            .args(files)
             .output()
             .map_err(|e| Error::new(ErrorKind::Diagnose, e.into()))
     }
+
+    /// Return the output files of `output` which exist, or an error
+    /// of the given `kind` if there are none, as the tools would
+    /// otherwise be run without files and fail cryptically.
+    fn present_files(output: &CmdStanOutput, kind: ErrorKind) -> Result<Vec<PathBuf>, Error> {
+        Self::some_files(
+            output.output_files(),
+            kind,
+            "no output files present; did the run succeed?".to_string(),
+        )
+    }
+
+    /// As `present_files`, but for the chains with the given `ids`.
+    fn chain_files(
+        output: &CmdStanOutput,
+        ids: &[i32],
+        kind: ErrorKind,
+    ) -> Result<Vec<PathBuf>, Error> {
+        Self::some_files(
+            output.output_files_for_chains(ids),
+            kind,
+            format!("no output files present for chains {ids:?}"),
+        )
+    }
+
+    fn some_files(
+        files: Vec<PathBuf>,
+        kind: ErrorKind,
+        msg: String,
+    ) -> Result<Vec<PathBuf>, Error> {
+        if files.is_empty() {
+            Err(Error::new(
+                kind,
+                io::Error::new(io::ErrorKind::NotFound, msg).into(),
+            ))
+        } else {
+            Ok(files)
+        }
+    }
+
+    /// Run `stansummary` on the output files of `output` which exist.
+    /// Errors are as for [`CmdStan::diagnose`], but of kind `StanSummary`.
     pub fn stansummary<T>(&self, output: &CmdStanOutput, opts: T) -> Result<process::Output, Error>
     where
         T: Into<Option<StanSummaryOptions>>,
     {
-        let guard = self.inner.read().unwrap();
-        let mut cmd = Command::new(&guard.stansummary);
-        cmd.args(output.output_files());
-        if let Some(opts) = opts.into() {
-            cmd.args(opts.command_fragment());
-        }
-        cmd.output()
-            .map_err(|e| Error::new(ErrorKind::StanSummary, e.into()))
+        let files = Self::present_files(output, ErrorKind::StanSummary)?;
+        self.stansummary_files(files, opts.into())
     }
-}
 
-/// Holds an absolute path to a compiled executable. Invariants established
-/// at the time of construction cannot be guaranteed to be true at all times,
+    /// As [`CmdStan::stansummary`], but restricted to the chains with the
+    /// given `ids`, e.g. to exclude a chain which failed to converge.
+    /// Errors are as for [`CmdStan::diagnose_chains`].
+    pub fn stansummary_chains<T>(
+        &self,
+        output: &CmdStanOutput,
+        ids: &[i32],
+        opts: T,
+    ) -> Result<process::Output, Error>
+    where
+        T: Into<Option<StanSummaryOptions>>,
+    {
+        let files = Self::chain_files(output, ids, ErrorKind::StanSummary)?;
+        self.stansummary_files(files, opts.into())
+    }
+
+    fn stansummary_files(
+        &self,
+        files: Vec<PathBuf>,
+        opts: Option<StanSummaryOptions>,
+    ) -> Result<process::Output, Error> {
+        let guard = self.inner.read().unwrap();
+        let mut cmd = Command::new(&guard.stansummary);
+        cmd.args(files);
+        if let Some(opts) = opts {
+            // Older versions reject unknown flags; if the version cannot
+            // be determined, the flags are passed and CmdStan decides.
+            if !opts.include_param.is_empty() {
+                if let Some(version @ (major, minor, patch)) = guard.version() {
+                    if version < (2, 33, 0) {
+                        return Err(Error::validation(format!(
+                            "--include_param requires CmdStan >= 2.33.0, found {major}.{minor}.{patch}"
+                        )));
+                    }
+                }
+            }
+            cmd.args(opts.command_fragment());
+        }
+        cmd.output()
+            .map_err(|e| Error::new(ErrorKind::StanSummary, e.into()))
+    }
+
+    /// Compile `program` (see [`CmdStan::compile`]), call the model with
+    /// `data` and `method`, and read the draws. The output and log files
+    /// are written to a [`ManagedTempFiles`] held by the [`Fit`], hence,
+    /// are deleted when the `Fit` is dropped; for control over the
+    /// arguments and the placement of files, use [`CmdStanModel::call`].
+    pub fn fit<D, M>(&self, program: &StanProgram, data: D, method: M) -> Result<Fit, Error>
+    where
+        D: Into<Data>,
+        M: Into<Method>,
+    {
+        let model = self.compile::<[_; 0], &str>(program, [])?;
+        let temp = ManagedTempFiles::new().map_err(CmdStanModel::error_op)?;
+        let tree = ArgTree::builder().data(data).method(method).build();
+        let output = model.call_in_dir(&tree, temp.dir())?;
+        let draws = output.draws().map_err(CmdStanModel::error_op)?;
+        Ok(Fit {
+            cmdstan: self.clone(),
+            model,
+            output,
+            draws,
+            summary: OnceLock::new(),
+            _temp: temp,
+        })
+    }
+
+    /// Run `stansummary` as for [`CmdStan::stansummary`], and read the
+    /// statistics which it writes to `csv_filename`. If `csv_filename`
+    /// is not given, the statistics are written to a [`ManagedTempFiles`],
+    /// which is removed once they have been read.
+    /// The percentile columns are checked against those requested (see
+    /// [`StanSummaryOptions::check_percentile_columns`]).
+    pub fn stansummary_parsed<T>(&self, output: &CmdStanOutput, opts: T) -> Result<Summary, Error>
+    where
+        T: Into<Option<StanSummaryOptions>>,
+    {
+        let mut opts = opts
+            .into()
+            .unwrap_or_else(|| StanSummaryOptions::builder().build());
+        // Held until the statistics have been read.
+        let mut _temp = None;
+        let csv = match opts.csv_filename {
+            Some(ref csv) => csv.clone(),
+            None => {
+                let temp = ManagedTempFiles::new()
+                    .map_err(|e| Error::new(ErrorKind::StanSummary, e.into()))?;
+                let csv = temp.dir().join("stansummary.csv").into();
+                _temp = Some(temp);
+                opts.csv_filename.insert(csv).clone()
+            }
+        };
+        let process_output = self.stansummary(output, opts.clone())?;
+        Error::ensure_success(ErrorKind::StanSummary, process_output)?;
+        let summary = Summary::from_csv(csv)?;
+        opts.check_percentile_columns(summary.statistics())?;
+        Ok(summary)
+    }
+}
+
+/// Holds an absolute path to a compiled executable. Invariants established
+/// at the time of construction cannot be guaranteed to be true at all times,
 /// as it is always possible to modify or delete the underlying file.
 #[derive(Debug, Clone, Hash, Eq, Ord, PartialEq, PartialOrd)]
 pub struct CmdStanModel {
     exec: PathBuf,
+    /// The directory which holds the executable, if compiled from
+    /// a program given inline.
+    temp: Option<ManagedTempFiles>,
+    build_info: BuildInfoCache,
+}
+
+/// The build information of an executable, computed at most once and
+/// shared by all clones of a [`CmdStanModel`]. The cache is not part
+/// of the identity of a model, hence, is ignored by comparison and hashing.
+#[derive(Debug, Clone, Default)]
+struct BuildInfoCache(Arc<OnceLock<HashMap<String, String>>>);
+
+impl PartialEq for BuildInfoCache {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+impl Eq for BuildInfoCache {}
+impl PartialOrd for BuildInfoCache {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for BuildInfoCache {
+    fn cmp(&self, _: &Self) -> std::cmp::Ordering {
+        std::cmp::Ordering::Equal
+    }
+}
+impl Hash for BuildInfoCache {
+    fn hash<H: std::hash::Hasher>(&self, _: &mut H) {}
 }
 
 impl TryFrom<&Path> for CmdStanModel {
@@ -451,7 +1282,11 @@ impl TryFrom<&Path> for CmdStanModel {
         let output = try_help(&exec, HELP).map_err(Self::error_op)?;
         Self::Error::appears_ok(ErrorKind::Executable, output)?;
 
-        Ok(Self { exec })
+        Ok(Self {
+            exec,
+            temp: None,
+            build_info: BuildInfoCache::default(),
+        })
     }
 }
 // Worthwhile? not certain.
@@ -466,7 +1301,6 @@ impl TryFrom<&Path> for CmdStanModel {
 //     }
 // }
 
-use std::collections::HashMap;
 impl CmdStanModel {
     /// Associated function which provides error of default kind for `CmdStanModel`
     /// and converts the error representation (be it IO or failed process).
@@ -474,11 +1308,42 @@ impl CmdStanModel {
         Error::new(ErrorKind::Executable, e.into())
     }
 
-    fn info(&self) -> Result<HashMap<String, String>, Error> {
+    /// Return the absolute path to the executable.
+    pub fn path(&self) -> &Path {
+        &self.exec
+    }
+
+    /// Return the name of the model, i.e. the file stem of the executable.
+    pub fn name(&self) -> &OsStr {
+        self.exec.file_stem().unwrap_or_default()
+    }
+
+    /// Return the build information reported by the executable (by
+    /// `model info`), e.g. `stan_version_major` or `STAN_OPENCL`.
+    ///
+    /// The executable is run only by the first successful query; the
+    /// result is cached, and the cache is shared by all clones of `self`.
+    /// Hence, if the executable is replaced in place (other than by
+    /// [`CmdStan::compile`], which returns a new model), the result may
+    /// be stale. Concurrent first queries may each run the executable,
+    /// but all observe the same result.
+    pub fn build_info(&self) -> Result<&HashMap<String, String>, Error> {
+        if let Some(map) = self.build_info.0.get() {
+            return Ok(map);
+        }
+        let map = self.query_build_info()?;
+        Ok(self.build_info.0.get_or_init(|| map))
+    }
+
+    fn query_build_info(&self) -> Result<HashMap<String, String>, Error> {
         let output = Command::new(&self.exec)
             .arg("info")
             .output()
             .map_err(Self::error_op)?;
+        Self::parse_build_info(output)
+    }
+
+    fn parse_build_info(output: process::Output) -> Result<HashMap<String, String>, Error> {
         if output.status.success() {
             let stdout = String::from_utf8_lossy(&output.stdout[..]);
             let map: HashMap<String, String> = stdout
@@ -492,6 +1357,78 @@ impl CmdStanModel {
         }
     }
 
+    /// If `tree` requests OpenCL, check that the executable was compiled
+    /// with OpenCL support, else CmdStan fails with an unhelpful error.
+    fn check_opencl(&self, tree: &ArgTree) -> Result<(), Error> {
+        if !tree.uses_opencl() {
+            return Ok(());
+        }
+        let info = self.build_info()?;
+        self.require_opencl(info)
+    }
+
+    fn require_opencl(&self, info: &HashMap<String, String>) -> Result<(), Error> {
+        match info.get("STAN_OPENCL").map(String::as_str) {
+            Some("true") => Ok(()),
+            _ => Err(Error::validation(format!(
+                "opencl was requested, but {} was not compiled with STAN_OPENCL=true",
+                self.exec.display()
+            ))),
+        }
+    }
+
+    /// Absolute paths of the log files for a call made with `tree`
+    /// from `cwd`.
+    fn log_paths(tree: &ArgTree, cwd: &Path) -> (PathBuf, PathBuf) {
+        let out: &Path = tree.output.file.as_ref();
+        // The log name likely needs to be unique, else we risk clobbering
+        // someone's precious file of the same name.
+        let mut stdout = if out.is_relative() {
+            cwd.join(out)
+        } else {
+            out.to_path_buf()
+        };
+        stdout.set_extension("");
+        let mut stderr = stdout.clone();
+        stdout.as_mut_os_string().push("_stdout_log.txt");
+        stderr.as_mut_os_string().push("_stderr_log.txt");
+        (stdout, stderr)
+    }
+
+    /// Produce the result of a call from the (completed) `output`
+    /// of the process.
+    fn finish(
+        tree: &ArgTree,
+        cwd: PathBuf,
+        stdout: PathBuf,
+        stderr: PathBuf,
+        mut output: process::Output,
+    ) -> Result<CmdStanOutput, Error> {
+        if output.status.success() {
+            Ok(CmdStanOutput {
+                stdout_path: stdout,
+                stderr_path: stderr,
+                cwd_at_call: cwd,
+                output,
+                argtree: tree.clone(),
+                captured: false,
+            })
+        } else {
+            // However, we need cook up an equivalent `process::Output`
+            // by reading the bytes we dumped to file.
+            // Leaving the log files on disk is likely desirable,
+            // in the event that something catastrophic happens...
+            // or the user just ignores the error thrown by this call.
+            let mut out = File::open(&stdout).map_err(Self::error_op)?;
+            let mut err = File::open(&stderr).map_err(Self::error_op)?;
+            out.read_to_end(&mut output.stdout)
+                .map_err(Self::error_op)?;
+            err.read_to_end(&mut output.stderr)
+                .map_err(Self::error_op)?;
+            Err(Self::error_op(output))
+        }
+    }
+
     /// Call the compiled model with the arguments contained in `tree`.
     /// Log files, containing the `stdout` and `stderr` of the spawned process,
     /// will be created in the same directory at which the `tree.output.file`
@@ -504,29 +1441,106 @@ impl CmdStanModel {
     /// `stdout` and `stderr` read from the respective log files.
     pub fn call(&self, tree: &ArgTree) -> Result<CmdStanOutput, Error> {
         let cwd = env::current_dir().map_err(Self::error_op)?;
-        let out: &Path = tree.output.file.as_ref();
-        // The log name likely needs to be unique, else we risk clobbering
-        // someone's precious file of the same name.
-        let mut stdout = if out.is_relative() {
-            cwd.join(out)
+        self.call_in_dir(tree, &cwd)
+    }
+
+    /// Call the compiled model with the arguments contained in `tree`,
+    /// with `dir` as the working directory of the spawned process.
+    /// Relative paths in `tree` are resolved against `dir`, as is the
+    /// placement of the log files, and `dir` is recorded as
+    /// [`CmdStanOutput::cwd_at_call`]. Otherwise, identical to [`CmdStanModel::call`].
+    ///
+    /// The working directory of a process is shared by all of its threads,
+    /// hence, this is the means by which to make calls from many threads
+    /// without reliance on (or modification of) the process-wide working directory.
+    /// For the same reason, `dir` should be absolute; if it is relative,
+    /// it is joined onto the current working directory.
+    pub fn call_in_dir(&self, tree: &ArgTree, dir: &Path) -> Result<CmdStanOutput, Error> {
+        self.call_in_dir_with_env(tree, dir, &[])
+    }
+
+    /// Call the compiled model with the arguments contained in `tree`,
+    /// with `envs` added to (or replacing variables of) the environment
+    /// which the spawned process inherits from this process, e.g.
+    /// OpenCL runtime configuration. The environment of this process is
+    /// not modified. Otherwise, identical to [`CmdStanModel::call`].
+    ///
+    /// Only the run is affected: options which take effect at compilation
+    /// are given to [`CmdStan::compile`] by means of
+    /// [`CompileOptions`](crate::compile::CompileOptions).
+    pub fn call_with_env(
+        &self,
+        tree: &ArgTree,
+        envs: &[(OsString, OsString)],
+    ) -> Result<CmdStanOutput, Error> {
+        let cwd = env::current_dir().map_err(Self::error_op)?;
+        self.call_in_dir_with_env(tree, &cwd, envs)
+    }
+
+    fn call_in_dir_with_env(
+        &self,
+        tree: &ArgTree,
+        dir: &Path,
+        envs: &[(OsString, OsString)],
+    ) -> Result<CmdStanOutput, Error> {
+        self.check_opencl(tree)?;
+        let cwd = if dir.is_relative() {
+            env::current_dir().map_err(Self::error_op)?.join(dir)
         } else {
-            out.to_path_buf()
+            dir.to_path_buf()
         };
-        stdout.set_extension("");
-        let mut stderr = stdout.clone();
-        stdout.as_mut_os_string().push("_stdout_log.txt");
-        stderr.as_mut_os_string().push("_stderr_log.txt");
+        let (stdout, stderr) = Self::log_paths(tree, &cwd);
 
         // Pipe both stdout and stderr to separate log files
         let out = File::create(&stdout).map_err(Self::error_op)?;
         let err = File::create(&stderr).map_err(Self::error_op)?;
-        let mut output = Command::new(&self.exec)
+        let output = Command::new(&self.exec)
+            .current_dir(&cwd)
             .args(tree.to_args())
+            .envs(envs.iter().map(|(k, v)| (k, v)))
             .stdin(Stdio::null())
             .stdout(out)
             .stderr(err)
             .output()
             .map_err(Self::error_op)?;
+        Self::finish(tree, cwd, stdout, stderr, output)
+    }
+
+    /// Call the compiled model with the tree produced by
+    /// [`ChainIdAllocator::assign`], i.e. with a newly allocated `id`
+    /// and output files labelled with it, such that calls derived from
+    /// the same `tree` may be made concurrently, from many threads,
+    /// without collisions. Otherwise, identical to [`CmdStanModel::call`].
+    pub fn call_with_id_from(
+        &self,
+        tree: &ArgTree,
+        ids: &ChainIdAllocator,
+    ) -> Result<CmdStanOutput, Error> {
+        self.call(&ids.assign(tree))
+    }
+
+    /// Call the compiled model with the arguments contained in `tree`,
+    /// capturing the `stdout` and `stderr` of the spawned process in
+    /// memory, such that no log files are written; the output is
+    /// available only through [`CmdStanOutput::output`]. This is suited to
+    /// ephemeral use, or to environments in which only the output
+    /// files may be written. Otherwise, identical to [`CmdStanModel::call`].
+    ///
+    /// [`CmdStanOutput::stdout_file`] and [`CmdStanOutput::stderr_file`]
+    /// return the paths at which the logs would have been written;
+    /// these files are not created.
+    pub fn call_capturing(&self, tree: &ArgTree) -> Result<CmdStanOutput, Error> {
+        self.check_opencl(tree)?;
+        let cwd = env::current_dir().map_err(Self::error_op)?;
+        let (stdout, stderr) = Self::log_paths(tree, &cwd);
+
+        let output = Command::new(&self.exec)
+            .args(tree.to_args())
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .map_err(Self::error_op)?;
         if output.status.success() {
             Ok(CmdStanOutput {
                 stdout_path: stdout,
@@ -534,22 +1548,335 @@ impl CmdStanModel {
                 cwd_at_call: cwd,
                 output,
                 argtree: tree.clone(),
+                captured: true,
             })
         } else {
-            // However, we need cook up an equivalent `process::Output`
-            // by reading the bytes we dumped to file.
-            // Leaving the log files on disk is likely desirable,
-            // in the event that something catastrophic happens...
-            // or the user just ignores the error thrown by this call.
-            let mut out = File::open(&stdout).map_err(Self::error_op)?;
-            let mut err = File::open(&stderr).map_err(Self::error_op)?;
-            out.read_to_end(&mut output.stdout)
-                .map_err(Self::error_op)?;
-            err.read_to_end(&mut output.stdout)
-                .map_err(Self::error_op)?;
             Err(Self::error_op(output))
         }
     }
+
+    /// Call the compiled model with the arguments contained in `tree`,
+    /// writing the contents of `data` to the standard input of the process;
+    /// [`CmdStanModel::call`] provides a null standard input. Once `data`
+    /// is exhausted, the standard input is closed, such that the process
+    /// observes the end of input. The log files and the result are
+    /// identical to those of `call`.
+    ///
+    /// The process need not read all of its input: if it exits (or closes
+    /// its standard input) before `data` is exhausted, the remainder is discarded.
+    pub fn call_with_stdin<R: Read>(
+        &self,
+        tree: &ArgTree,
+        mut data: R,
+    ) -> Result<CmdStanOutput, Error> {
+        self.check_opencl(tree)?;
+        let cwd = env::current_dir().map_err(Self::error_op)?;
+        let (stdout, stderr) = Self::log_paths(tree, &cwd);
+
+        let out = File::create(&stdout).map_err(Self::error_op)?;
+        let err = File::create(&stderr).map_err(Self::error_op)?;
+        let mut child = Command::new(&self.exec)
+            .args(tree.to_args())
+            .stdin(Stdio::piped())
+            .stdout(out)
+            .stderr(err)
+            .spawn()
+            .map_err(Self::error_op)?;
+        // stdout and stderr are files, hence, the process cannot block
+        // on output while we write its input.
+        let mut stdin = child.stdin.take().unwrap();
+        let copy = match io::copy(&mut data, &mut stdin) {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+            x => x.map(|_| ()),
+        };
+        drop(stdin);
+        // Wait regardless of the outcome of the copy, so as not to
+        // leave a zombie behind.
+        let output = child.wait_with_output().map_err(Self::error_op)?;
+        copy.map_err(Self::error_op)?;
+        Self::finish(tree, cwd, stdout, stderr, output)
+    }
+
+    /// Call the compiled model with the arguments contained in `tree`,
+    /// invoking `f` on each progress update written by CmdStan.
+    /// The log files and the result are identical to those of [`CmdStanModel::call`].
+    ///
+    /// Progress updates are only written by CmdStan every
+    /// `tree.output.refresh` iterations, and only by methods which
+    /// iterate in the manner of the sampler. In particular, if
+    /// `refresh == 0` (see [`OutputBuilder::quiet`](crate::OutputBuilder::quiet)),
+    /// the run is silent: `f` is never called and this is equivalent
+    /// to `call`.
+    pub fn call_with_progress<F>(&self, tree: &ArgTree, f: F) -> Result<CmdStanOutput, Error>
+    where
+        F: FnMut(&Progress),
+    {
+        self.call_with_progress_config(tree, &ProgressConfig::default(), f)
+    }
+
+    /// As [`CmdStanModel::call_with_progress`], but with the buffering
+    /// of the console output and the cadence of the callback given by
+    /// `config`. A larger buffer reduces the number of reads; a non-zero
+    /// `poll_interval` bounds the overhead of a costly `f` when CmdStan
+    /// writes updates in rapid succession, at the expense of latency.
+    pub fn call_with_progress_config<F>(
+        &self,
+        tree: &ArgTree,
+        config: &ProgressConfig,
+        mut f: F,
+    ) -> Result<CmdStanOutput, Error>
+    where
+        F: FnMut(&Progress),
+    {
+        if tree.output.refresh == 0 {
+            return self.call(tree);
+        }
+        self.check_opencl(tree)?;
+        let cwd = env::current_dir().map_err(Self::error_op)?;
+        let (stdout, stderr) = Self::log_paths(tree, &cwd);
+
+        // stdout is piped through this process, and copied to the log
+        // line by line, such that the log is still populated dynamically.
+        let mut out = File::create(&stdout).map_err(Self::error_op)?;
+        let err = File::create(&stderr).map_err(Self::error_op)?;
+        let mut child = Command::new(&self.exec)
+            .args(tree.to_args())
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(err)
+            .spawn()
+            .map_err(Self::error_op)?;
+        // Every line must be consumed, else the child may block on a full pipe.
+        let pipe = child.stdout.take().unwrap();
+        let mut reader = BufReader::with_capacity(config.buffer_capacity.max(1), pipe);
+        // Lines are read as bytes, as the console output need not be UTF-8
+        // (e.g. the names of files), and this is not an error.
+        let mut line = Vec::new();
+        let mut last: Option<Instant> = None;
+        let mut pending: Option<Progress> = None;
+        let copy = loop {
+            line.clear();
+            match reader.read_until(b'\n', &mut line) {
+                Ok(0) => break Ok(()),
+                Ok(_) => {
+                    if let Err(e) = out.write_all(&line) {
+                        // The remainder is discarded, but must still be consumed.
+                        let _ = io::copy(&mut reader, &mut io::sink());
+                        break Err(e);
+                    }
+                    if let Some(progress) = Progress::from_line(&String::from_utf8_lossy(&line)) {
+                        let now = Instant::now();
+                        if last.is_none_or(|t| now.duration_since(t) >= config.poll_interval) {
+                            last = Some(now);
+                            pending = None;
+                            f(&progress);
+                        } else {
+                            pending = Some(progress);
+                        }
+                    }
+                }
+                Err(e) => {
+                    // The pipe can no longer be drained, hence, the child
+                    // must not be left to block on it.
+                    let _ = child.kill();
+                    break Err(e);
+                }
+            }
+        };
+        if let Some(progress) = pending {
+            f(&progress);
+        }
+        // Wait regardless of the outcome of the copy, so as not to
+        // leave a zombie behind.
+        let output = child.wait_with_output().map_err(Self::error_op)?;
+        copy.map_err(Self::error_op)?;
+        Self::finish(tree, cwd, stdout, stderr, output)
+    }
+
+    /// Evaluate the log density, and its gradient with respect to the
+    /// unconstrained parameters, at each set of parameter values in
+    /// `unconstrained_params`, a JSON or R dump file. The call is made
+    /// with the `log_prob` method, using `tree` for all other arguments,
+    /// e.g. the data and output files; `jacobian` determines whether the
+    /// change-of-variables adjustment is included.
+    ///
+    /// CmdStan does not write the constrained values of the parameters,
+    /// hence, these are not available; see [`LogProbs`].
+    pub fn log_prob<P: AsRef<OsStr>>(
+        &self,
+        tree: &ArgTree,
+        unconstrained_params: P,
+        jacobian: bool,
+    ) -> Result<LogProbs, Error> {
+        let mut tree = tree.clone();
+        tree.method = LogProbBuilder::new()
+            .unconstrained_params(unconstrained_params.as_ref())
+            .jacobian(jacobian)
+            .build();
+        let output = self.call(&tree)?;
+        output.log_probs().map_err(Self::error_op)
+    }
+
+    /// Call the compiled model once for each `(label, method)` pair,
+    /// using `base` for all other arguments. Each call is made with
+    /// the tree produced by [`ArgTree::with_label`], hence, each label
+    /// receives distinct output, diagnostic and profile files.
+    ///
+    /// Calls are made sequentially, in the order given, and every
+    /// call is made regardless of the success of previous calls.
+    /// Labels should be unique, else files will be overwritten.
+    pub fn run_grid<I, S>(
+        &self,
+        base: &ArgTree,
+        methods: I,
+    ) -> Vec<(String, Result<CmdStanOutput, Error>)>
+    where
+        I: IntoIterator<Item = (S, Method)>,
+        S: Into<String>,
+    {
+        methods
+            .into_iter()
+            .map(|(label, method)| {
+                let label: String = label.into();
+                let mut tree = base.with_label(&label);
+                tree.method = method;
+                let result = self.call(&tree);
+                (label, result)
+            })
+            .collect()
+    }
+}
+
+/// A grid of labeled methods, accumulated on top of a base tree, to be
+/// run by [`CmdStanModel::run_grid`], e.g.
+/// `MethodGrid::new(base).add("long", SampleBuilder::new().num_warmup(5000))`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MethodGrid {
+    base: ArgTree,
+    methods: Vec<(String, Method)>,
+}
+
+impl MethodGrid {
+    /// Create an empty grid; `base` provides all arguments other than
+    /// the method, and each label receives its own files, as by
+    /// [`ArgTree::with_label`].
+    pub fn new(base: ArgTree) -> Self {
+        Self {
+            base,
+            methods: Vec::new(),
+        }
+    }
+
+    /// Add `method` to the grid under `label`. Labels should be unique,
+    /// else files will be overwritten.
+    pub fn add<S, M>(mut self, label: S, method: M) -> Self
+    where
+        S: Into<String>,
+        M: Into<Method>,
+    {
+        self.methods.push((label.into(), method.into()));
+        self
+    }
+
+    /// Return the labels, in the order in which they will be run.
+    pub fn labels(&self) -> Vec<&str> {
+        self.methods
+            .iter()
+            .map(|(label, _)| label.as_str())
+            .collect()
+    }
+
+    /// Return the tree with which the method under `label` will be run.
+    pub fn tree(&self, label: &str) -> Option<ArgTree> {
+        self.methods
+            .iter()
+            .find(|(x, _)| x == label)
+            .map(|(_, method)| {
+                let mut tree = self.base.with_label(label);
+                tree.method = method.clone();
+                tree
+            })
+    }
+
+    /// Run each method of the grid with `model`; see [`CmdStanModel::run_grid`].
+    pub fn run(&self, model: &CmdStanModel) -> Vec<(String, Result<CmdStanOutput, Error>)> {
+        model.run_grid(&self.base, self.methods.iter().cloned())
+    }
+}
+
+impl<S: Into<String>> Extend<(S, Method)> for MethodGrid {
+    fn extend<I: IntoIterator<Item = (S, Method)>>(&mut self, iter: I) {
+        self.methods.extend(
+            iter.into_iter()
+                .map(|(label, method)| (label.into(), method)),
+        );
+    }
+}
+
+/// Asynchronous counterparts, available with the `tokio` feature.
+#[cfg(feature = "tokio")]
+impl CmdStanModel {
+    /// Asynchronous counterpart of [`CmdStanModel::call`]. The log files
+    /// are populated dynamically, and the result is identical to that
+    /// of the synchronous version.
+    ///
+    /// Dropping the future prior to completion does not kill the
+    /// spawned process.
+    pub async fn call_async(&self, tree: &ArgTree) -> Result<CmdStanOutput, Error> {
+        self.check_opencl_async(tree).await?;
+        let cwd = env::current_dir().map_err(Self::error_op)?;
+        let (stdout, stderr) = Self::log_paths(tree, &cwd);
+
+        let out = Self::create_async(&stdout).await?;
+        let err = Self::create_async(&stderr).await?;
+        let output = tokio::process::Command::new(&self.exec)
+            .current_dir(&cwd)
+            .args(tree.to_args())
+            .stdin(Stdio::null())
+            .stdout(out)
+            .stderr(err)
+            // Unlike `std`, `tokio::process::Command::output` unconditionally
+            // pipes stdout and stderr, hence, spawn then wait.
+            .spawn()
+            .map_err(Self::error_op)?
+            .wait_with_output()
+            .await
+            .map_err(Self::error_op)?;
+        // On failure, the log files are read back, which would block.
+        let tree = tree.clone();
+        tokio::task::spawn_blocking(move || Self::finish(&tree, cwd, stdout, stderr, output))
+            .await
+            .map_err(|e| Self::error_op(io::Error::from(e)))?
+    }
+
+    async fn create_async(path: &Path) -> Result<File, Error> {
+        let file = tokio::fs::File::create(path)
+            .await
+            .map_err(Self::error_op)?;
+        Ok(file.into_std().await)
+    }
+
+    /// As [`CmdStanModel::check_opencl`], but the first query of the
+    /// build information does not block the executor.
+    async fn check_opencl_async(&self, tree: &ArgTree) -> Result<(), Error> {
+        if !tree.uses_opencl() {
+            return Ok(());
+        }
+        let info = match self.build_info.0.get() {
+            Some(map) => map,
+            None => {
+                let output = tokio::process::Command::new(&self.exec)
+                    .arg("info")
+                    .stdin(Stdio::null())
+                    .output()
+                    .await
+                    .map_err(Self::error_op)?;
+                let map = Self::parse_build_info(output)?;
+                self.build_info.0.get_or_init(|| map)
+            }
+        };
+        self.require_opencl(info)
+    }
 }
 
 // #[allow(non_snake_case)]
@@ -570,6 +1897,7 @@ impl CmdStanModel {
 /// - the console output (exit status, stdout and stderr),
 /// - the argument tree with the call was made
 /// - the current working directory of the process at the time the call was made
+#[derive(Debug)]
 pub struct CmdStanOutput {
     /// Enables methods such as `output_files`, `diagnostic_files`,
     /// etc. to return absolute paths by introspection of the
@@ -581,6 +1909,9 @@ pub struct CmdStanOutput {
     argtree: ArgTree,
     stdout_path: PathBuf,
     stderr_path: PathBuf,
+    /// Whether the console output was captured in `output`, rather
+    /// than written to the log files (see [`CmdStanModel::call_capturing`]).
+    captured: bool,
 }
 impl CmdStanOutput {
     /// Convert files to absolute paths. If the file is already
@@ -588,7 +1919,7 @@ impl CmdStanOutput {
     /// otherwise the current working directory at the time this
     /// `CmdStanOutput` instance was created serves as the prefix onto
     /// which the relative path will be joined.
-    fn files<F>(&self, f: F) -> Vec<PathBuf>
+    fn planned_files<F>(&self, f: F) -> Vec<PathBuf>
     where
         F: Fn(&ArgTree) -> Vec<OsString>,
     {
@@ -605,13 +1936,45 @@ impl CmdStanOutput {
                 // as a copy of `s` would occur.
                 // self.cwd_at_call.join(s)
             })
-            .filter(|path| path.is_file())
             .collect()
     }
-    /// Return the output files associated with the call.
+    /// As above, but only those files which exist.
+    fn files<F>(&self, f: F) -> Vec<PathBuf>
+    where
+        F: Fn(&ArgTree) -> Vec<OsString>,
+    {
+        let mut files = self.planned_files(f);
+        files.retain(|path| path.is_file());
+        files
+    }
+    /// Return the output files associated with the call which exist.
+    /// In contrast, [`ArgTree::output_files`] and
+    /// [`CmdStanOutput::planned_output_files`] return the files which
+    /// the call was expected to write, whether or not these exist.
     pub fn output_files(&self) -> Vec<PathBuf> {
         self.files(|tree| tree.output_files())
     }
+    /// Return the output files which the call was expected to write,
+    /// as absolute paths, regardless of whether these exist.
+    pub fn planned_output_files(&self) -> Vec<PathBuf> {
+        self.planned_files(|tree| tree.output_files())
+    }
+    /// Return the output files of the chains with the given `ids`, in
+    /// the order of `ids`, which exist. Ids of chains which were not
+    /// run are ignored.
+    pub fn output_files_for_chains(&self, ids: &[i32]) -> Vec<PathBuf> {
+        self.files(|tree| tree.output_files_for_chains(ids))
+    }
+
+    /// Return the output files which the call was expected to write,
+    /// but which do not exist. If non-empty, then some chains failed
+    /// (or were interrupted), or the files were moved or deleted since
+    /// the call; see also [`CmdStanOutput::chain_statuses`].
+    pub fn missing_output_files(&self) -> Vec<PathBuf> {
+        let mut files = self.planned_output_files();
+        files.retain(|path| !path.is_file());
+        files
+    }
     /// Return the diagnostic files associated with the call.
     pub fn diagnostic_files(&self) -> Vec<PathBuf> {
         self.files(|tree| tree.diagnostic_files())
@@ -630,6 +1993,26 @@ impl CmdStanOutput {
         &self.stderr_path
     }
 
+    /// Remove the output, diagnostic and profile files associated with
+    /// the call, along with the log files. Files which do not exist
+    /// are skipped; the first other error encountered is returned,
+    /// in which case some files may remain.
+    pub fn delete_artifacts(self) -> io::Result<()> {
+        let files = self
+            .output_files()
+            .into_iter()
+            .chain(self.diagnostic_files())
+            .chain(self.profile_files())
+            .chain([self.stdout_path, self.stderr_path]);
+        for file in files {
+            match fs::remove_file(&file) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
     /// Return a reference to console output of the call.
     pub fn output(&self) -> &process::Output {
         &self.output
@@ -646,4 +2029,1674 @@ impl CmdStanOutput {
     pub fn argtree(&self) -> &ArgTree {
         &self.argtree
     }
+
+    /// Return the status of each chain, in order of chain id, as
+    /// determined by inspection of the respective output file.
+    ///
+    /// When multiple chains are run by a single process, the exit
+    /// status of the process does not reveal which chains failed;
+    /// a chain which failed (or was interrupted) leaves a missing
+    /// or incomplete output file. If the method does not imply a number
+    /// of draws (see [`Method::expected_draws`]), then an output file is
+    /// complete if it can be read.
+    pub fn chain_statuses(&self) -> Vec<ChainStatus> {
+        let expected = self.argtree.method.expected_draws();
+        self.planned_output_files()
+            .into_iter()
+            .map(|path| match DrawsTable::from_path(path) {
+                Err(_) => ChainStatus::Missing,
+                Ok(draws) => match expected {
+                    Some(expected) if expected != draws.num_draws() => ChainStatus::Incomplete {
+                        expected,
+                        found: draws.num_draws(),
+                    },
+                    _ => ChainStatus::Complete,
+                },
+            })
+            .collect()
+    }
+
+    /// Return the number of draws, across all chains, which reached
+    /// the `max_depth` of the NUTS engine with which the call was made.
+    /// If the call was made with `save_warmup`, the warmup draws at the
+    /// start of each output file are excluded, as by CmdStan's `diagnose`.
+    /// An error of kind `InvalidInput` is returned if the call was not
+    /// made with `sample` using the NUTS engine; errors from reading the
+    /// draws are as for [`CmdStanOutput::draws`].
+    pub fn max_treedepth_hits(&self) -> io::Result<usize> {
+        let (max_depth, warmup) = match &self.argtree.method {
+            Method::Sample {
+                num_warmup,
+                save_warmup,
+                thin,
+                algorithm:
+                    SampleAlgorithm::Hmc {
+                        engine: Engine::Nuts { max_depth },
+                        ..
+                    },
+                ..
+            } => {
+                // As for `Method::expected_draws`.
+                let warmup = if *save_warmup {
+                    (*num_warmup as usize).div_ceil(*thin as usize)
+                } else {
+                    0
+                };
+                (*max_depth as f64, warmup)
+            }
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "max_depth applies only to sample with the NUTS engine",
+                ))
+            }
+        };
+        let files = self.output_files();
+        if files.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "no output files found",
+            ));
+        }
+        let mut n = 0;
+        for file in files {
+            let draws = DrawsTable::from_path(file)?;
+            let column = draws.require_column("treedepth__")?;
+            n += column
+                .iter()
+                .skip(warmup)
+                .filter(|x| **x >= max_depth)
+                .count();
+        }
+        Ok(n)
+    }
+
+    /// Parse the end-of-run console summary of the sampler from the
+    /// stdout log, or from the captured stdout if the call was made by
+    /// [`CmdStanModel::call_capturing`]; see [`RunSummary`]. Errors are
+    /// those of reading the log.
+    pub fn run_summary(&self) -> io::Result<RunSummary> {
+        let log;
+        let console = if self.captured {
+            &self.output.stdout
+        } else {
+            log = fs::read(&self.stdout_path)?;
+            &log
+        };
+        Ok(RunSummary::from_console(&String::from_utf8_lossy(console)))
+    }
+
+    /// Read the log density and gradients from the output of a call made
+    /// with the `log_prob` method. Errors are as for [`CmdStanOutput::draws`]
+    /// and [`LogProbs::from_draws`].
+    pub fn log_probs(&self) -> io::Result<LogProbs> {
+        LogProbs::from_draws(&self.draws()?)
+    }
+
+    /// Read the adaptation of each chain from the output files, in the
+    /// order given by [`CmdStanOutput::output_files`], e.g. to warm-start
+    /// subsequent chains using [`ArgTree::warm_start_chains`].
+    pub fn adaptations(&self) -> io::Result<Vec<Adaptation>> {
+        self.output_files()
+            .into_iter()
+            .map(Adaptation::from_path)
+            .collect()
+    }
+
+    /// Return the trees with which to continue sampling from the end of
+    /// the warmup of this call: one tree per chain (see
+    /// [`ArgTree::warm_start_chains`]), as the step size adapted by each
+    /// chain differs, but CmdStan applies a single step size per process.
+    /// Each tree takes the adapted step size and inverse metric of its
+    /// chain, with `adapt engaged=0`, `num_warmup=0` and `num_samples`
+    /// set to `additional_samples`.
+    ///
+    /// Relative files are resolved against [`CmdStanOutput::cwd_at_call`],
+    /// and the output files are labelled with `continued` and the chain
+    /// id, e.g. `output_continued_1.csv`, such that the output of this call
+    /// is not overwritten. The metrics are written to files of the same
+    /// stem, e.g. `output_metric_1.json`. The chains are initialized as
+    /// given by `init`, not from the final draws, and with the same
+    /// `seed`; if the seed is not `-1`, change it to obtain new draws.
+    ///
+    /// An error of kind `InvalidInput` is returned if the method was not
+    /// `sample` using HMC, and of kind `InvalidData` if the adaptation of
+    /// a chain cannot be read, e.g. as adaptation was not engaged.
+    pub fn continue_sampling(&self, additional_samples: i32) -> io::Result<Vec<ArgTree>> {
+        let mut tree = self
+            .argtree
+            .clone()
+            .with_base(&self.cwd_at_call)
+            .with_label("continued");
+        let mut metric_file = PathBuf::from(self.argtree.resolved_output_file());
+        if metric_file.is_relative() {
+            metric_file = self.cwd_at_call.join(metric_file);
+        }
+        metric_file.set_extension("");
+        metric_file.as_mut_os_string().push("_metric.json");
+        match &mut tree.method {
+            Method::Sample {
+                num_samples,
+                num_warmup,
+                adapt,
+                algorithm: SampleAlgorithm::Hmc { metric_file: x, .. },
+                ..
+            } => {
+                *num_samples = additional_samples;
+                *num_warmup = 0;
+                adapt.engaged = false;
+                *x = metric_file.into_os_string();
+            }
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "sampling can be continued only from `sample` using HMC",
+                ))
+            }
+        }
+        tree.warm_start_chains(&self.adaptations()?)
+    }
+
+    /// Read the `lp__` column of each output file, in the order given by
+    /// [`CmdStanOutput::output_files`], e.g. to plot the trace of each chain.
+    /// Only that column is parsed, hence, this is faster than
+    /// [`CmdStanOutput::draws`]. An error of kind `InvalidData` is returned
+    /// if the column is absent, e.g. for the output of `generate_quantities`.
+    pub fn lp_trace(&self) -> io::Result<Vec<Vec<f64>>> {
+        self.output_files()
+            .into_iter()
+            .map(|file| DrawsIter::from_path(file)?.column("lp__"))
+            .collect()
+    }
+
+    /// Write the draws of all chains to `path` in `format`, as a single
+    /// table with a leading `chain` column which holds the id of the chain
+    /// of each draw. Chains whose output files do not exist are skipped;
+    /// other errors are as for [`CmdStanOutput::draws`].
+    pub fn save_draws_as<P: AsRef<Path>>(&self, format: ExportFormat, path: P) -> io::Result<()> {
+        let mut draws: Option<DrawsTable> = None;
+        let mut chain = Vec::new();
+        for (id, file) in (self.argtree.id..).zip(self.planned_output_files()) {
+            if !file.is_file() {
+                continue;
+            }
+            let x = DrawsTable::from_path(file)?;
+            chain.extend(std::iter::repeat_n(id as f64, x.num_draws()));
+            match draws.as_mut() {
+                Some(draws) => draws.append(x)?,
+                None => draws = Some(x),
+            }
+        }
+        let Some(mut draws) = draws else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "no output files found",
+            ));
+        };
+        draws.prepend_column("chain", chain);
+        draws.save_as(format, path)
+    }
+
+    /// Read the draws from the output files, concatenated in the
+    /// order given by [`CmdStanOutput::output_files`].
+    pub fn draws(&self) -> io::Result<DrawsTable> {
+        let mut files = self.output_files().into_iter();
+        let Some(file) = files.next() else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "no output files found",
+            ));
+        };
+        let mut draws = DrawsTable::from_path(file)?;
+        for file in files {
+            draws.append(DrawsTable::from_path(file)?)?;
+        }
+        Ok(draws)
+    }
+}
+
+/// The health of a sampler run, as reported by CmdStan in its console
+/// output at the end of sampling, e.g.
+/// `Warning: 12 of 1000 (1.2%) transitions ended with a divergence.`
+///
+/// This is cheap to obtain, as only the stdout log is read, but it is
+/// only as good as what CmdStan chose to print. The counts computed
+/// from the draws, by [`DrawsTable::divergences`] and
+/// [`CmdStanOutput::max_treedepth_hits`], are authoritative.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub struct RunSummary {
+    /// Number of divergent transitions, summed over the reporting chains.
+    pub divergences: u32,
+    /// Number of transitions which hit the maximum tree depth, summed
+    /// over the reporting chains.
+    pub max_treedepth_hits: u32,
+}
+
+impl RunSummary {
+    /// Parse the console output of a call. CmdStan prints nothing
+    /// when there is nothing to report, hence, lines which are not
+    /// recognized are ignored, and the absence of lines is a count of zero.
+    pub fn from_console(console: &str) -> Self {
+        let mut x = Self::default();
+        for line in console.lines() {
+            let Some(n) = Self::count(line) else {
+                continue;
+            };
+            let lower = line.to_ascii_lowercase();
+            if lower.contains("divergen") {
+                x.divergences += n;
+            } else if lower.contains("treedepth") || lower.contains("tree depth") {
+                x.max_treedepth_hits += n;
+            }
+        }
+        x
+    }
+
+    /// Return the `n` of the first `n of m` in `line`.
+    fn count(line: &str) -> Option<u32> {
+        let words: Vec<_> = line.split_whitespace().collect();
+        words.windows(3).find_map(|w| match w {
+            [n, "of", m] if m.parse::<u32>().is_ok() => n.parse::<u32>().ok(),
+            _ => None,
+        })
+    }
+
+    /// Return `true` if neither divergences nor hits of the maximum
+    /// tree depth were reported.
+    pub fn is_clean(&self) -> bool {
+        self.divergences == 0 && self.max_treedepth_hits == 0
+    }
+}
+
+/// Status of a single chain of a call, as determined by inspection of
+/// its output file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChainStatus {
+    /// The output file holds the expected number of draws.
+    Complete,
+    /// The output file holds a number of draws other than that expected.
+    Incomplete { expected: usize, found: usize },
+    /// The output file does not exist, or is not a valid Stan CSV file.
+    Missing,
+}
+
+impl ChainStatus {
+    /// Return `true` if the chain is complete.
+    pub fn is_complete(&self) -> bool {
+        matches!(self, Self::Complete)
+    }
+}
+
+/// The result of [`CmdStan::fit`]: the model, the output of the call,
+/// the draws, and the summary, which is computed on first access.
+#[derive(Debug)]
+pub struct Fit {
+    cmdstan: CmdStan,
+    model: CmdStanModel,
+    output: CmdStanOutput,
+    draws: DrawsTable,
+    summary: OnceLock<Summary>,
+    _temp: ManagedTempFiles,
+}
+
+impl Fit {
+    pub fn model(&self) -> &CmdStanModel {
+        &self.model
+    }
+
+    pub fn output(&self) -> &CmdStanOutput {
+        &self.output
+    }
+
+    /// Return the draws of all chains, concatenated in order of chain id.
+    pub fn draws(&self) -> &DrawsTable {
+        &self.draws
+    }
+
+    /// Return the summary computed by `stansummary` with the default
+    /// options (see [`CmdStan::stansummary_parsed`]). The summary is
+    /// computed on the first successful call, and stored thereafter.
+    pub fn summary(&self) -> Result<&Summary, Error> {
+        if let Some(summary) = self.summary.get() {
+            return Ok(summary);
+        }
+        let summary = self.cmdstan.stansummary_parsed(&self.output, None)?;
+        Ok(self.summary.get_or_init(|| summary))
+    }
+}
+
+/// The results of many calls, e.g. as produced by [`CmdStanModel::run_grid`],
+/// with accessors which operate on all of the calls at once.
+/// The position of each result in the set is its run index.
+#[derive(Debug)]
+pub struct CmdStanOutputSet {
+    runs: Vec<Result<CmdStanOutput, Error>>,
+}
+
+impl FromIterator<Result<CmdStanOutput, Error>> for CmdStanOutputSet {
+    fn from_iter<I: IntoIterator<Item = Result<CmdStanOutput, Error>>>(iter: I) -> Self {
+        Self {
+            runs: iter.into_iter().collect(),
+        }
+    }
+}
+impl From<Vec<Result<CmdStanOutput, Error>>> for CmdStanOutputSet {
+    fn from(runs: Vec<Result<CmdStanOutput, Error>>) -> Self {
+        Self { runs }
+    }
+}
+
+impl CmdStanOutputSet {
+    /// Return a reference to the results, in order of run index.
+    pub fn runs(&self) -> &[Result<CmdStanOutput, Error>] {
+        &self.runs
+    }
+    /// Consume `self`, returning the results in order of run index.
+    pub fn into_runs(self) -> Vec<Result<CmdStanOutput, Error>> {
+        self.runs
+    }
+    /// Return the number of runs.
+    pub fn len(&self) -> usize {
+        self.runs.len()
+    }
+    /// Return `true` if there are no runs.
+    pub fn is_empty(&self) -> bool {
+        self.runs.is_empty()
+    }
+
+    /// Return `true` if every run succeeded. Vacuously `true` if empty.
+    pub fn all_succeeded(&self) -> bool {
+        self.runs.iter().all(|run| run.is_ok())
+    }
+    /// Return the run index and error of each failed run.
+    pub fn failures(&self) -> Vec<(usize, &Error)> {
+        self.runs
+            .iter()
+            .enumerate()
+            .filter_map(|(i, run)| run.as_ref().err().map(|e| (i, e)))
+            .collect()
+    }
+    /// Return the run index and output of each successful run.
+    pub fn successes(&self) -> Vec<(usize, &CmdStanOutput)> {
+        self.runs
+            .iter()
+            .enumerate()
+            .filter_map(|(i, run)| run.as_ref().ok().map(|x| (i, x)))
+            .collect()
+    }
+
+    /// Return the output files of all successful runs, in order of run index.
+    pub fn all_output_files(&self) -> Vec<PathBuf> {
+        self.successes()
+            .into_iter()
+            .flat_map(|(_, x)| x.output_files())
+            .collect()
+    }
+
+    /// Read the draws of all successful runs, concatenated in order of
+    /// run index, with a leading `run__` column which holds the run index
+    /// of each draw. All runs must have identical column names, else
+    /// an error of kind `InvalidData` is returned.
+    pub fn combined_draws(&self) -> io::Result<DrawsTable> {
+        let mut names: Vec<String> = vec!["run__".to_string()];
+        let mut columns: Vec<Vec<f64>> = vec![Vec::new()];
+        for (n, (i, x)) in self.successes().into_iter().enumerate() {
+            let draws = x.draws()?;
+            if n == 0 {
+                names.extend_from_slice(draws.names());
+                columns.resize(names.len(), Vec::new());
+            } else if draws.names() != &names[1..] {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("column names of run {i} differ from those of previous runs"),
+                ));
+            }
+            columns[0].extend(std::iter::repeat_n(i as f64, draws.num_draws()));
+            for (lhs, rhs) in columns[1..].iter_mut().zip(draws.columns_ref()) {
+                lhs.extend_from_slice(rhs);
+            }
+        }
+        Ok(DrawsTable::from_parts(names, columns))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+
+    /// A successful call with the given output `file`, without running anything.
+    fn fake_output(dir: &Path, file: &str, contents: &str) -> CmdStanOutput {
+        let path = dir.join(file);
+        fs::write(&path, contents).unwrap();
+        let argtree = ArgTree::builder()
+            .output(crate::argtree::Output::builder().file(path))
+            .build();
+        CmdStanOutput {
+            cwd_at_call: dir.to_path_buf(),
+            output: process::Output {
+                status: Default::default(),
+                stdout: Vec::new(),
+                stderr: Vec::new(),
+            },
+            argtree,
+            stdout_path: dir.join("stdout.txt"),
+            stderr_path: dir.join("stderr.txt"),
+            captured: false,
+        }
+    }
+
+    /// A stand-in for a compiled model: a shell script with the given `body`.
+    #[cfg(unix)]
+    fn fake_model(dir: &Path, body: &str) -> CmdStanModel {
+        use std::os::unix::fs::PermissionsExt;
+        let exec = dir.join("model");
+        fs::write(&exec, format!("#!/bin/sh\n{body}\n")).unwrap();
+        fs::set_permissions(&exec, fs::Permissions::from_mode(0o755)).unwrap();
+        CmdStanModel {
+            exec,
+            temp: None,
+            build_info: BuildInfoCache::default(),
+        }
+    }
+
+    mod program {
+        use super::*;
+
+        #[test]
+        fn fnv1a() {
+            assert_eq!(super::fnv1a(b""), 0xcbf29ce484222325);
+            assert_eq!(super::fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+            assert_eq!(super::fnv1a(b"foobar"), 0x85944171f73967e8);
+        }
+
+        #[test]
+        fn content_hash() {
+            let dir = temp_dir();
+            let source = "parameters { real x; }\nmodel { x ~ std_normal(); }\n";
+            fs::write(dir.join("a.stan"), source).unwrap();
+            fs::create_dir(dir.join("sub")).unwrap();
+            fs::write(dir.join("sub").join("b.stan"), source).unwrap();
+            fs::write(dir.join("c.stan"), source.replace("x", "y")).unwrap();
+
+            let a = StanProgram::try_from(dir.join("a.stan").as_ref()).unwrap();
+            let b = StanProgram::try_from(dir.join("sub").join("b.stan").as_ref()).unwrap();
+            let c = StanProgram::try_from(dir.join("c.stan").as_ref()).unwrap();
+            assert_eq!(a.source().unwrap(), source);
+            assert_eq!(a.path(), fs::canonicalize(dir.join("a.stan")).unwrap());
+            assert_eq!(a.content_hash().unwrap(), b.content_hash().unwrap());
+            assert_ne!(a.content_hash().unwrap(), c.content_hash().unwrap());
+
+            fs::remove_file(dir.join("a.stan")).unwrap();
+            assert!(a.source().is_err());
+            assert!(a.content_hash().is_err());
+        }
+    }
+
+    #[cfg(unix)]
+    mod cmdstan {
+        use super::*;
+
+        #[test]
+        fn try_from() {
+            let stub = StubCmdStan::new();
+            let bin = stub.root().join("bin");
+            assert!(!bin.exists());
+            let cmdstan = CmdStan::try_from(stub.root()).unwrap();
+            for name in [STANC, STANSUMMARY, DIAGNOSE] {
+                assert!(bin.join(name).exists());
+            }
+            assert!(stub.root().join("output.csv").exists());
+
+            // The utilities are re-built after clean.
+            cmdstan.clean().unwrap();
+            for name in [STANC, STANSUMMARY, DIAGNOSE] {
+                assert!(bin.join(name).exists());
+            }
+        }
+
+        #[test]
+        fn try_from_err() {
+            let stub = StubCmdStan::new();
+            let e = CmdStan::try_from(stub.root().join("nonexistent").as_ref()).unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::Install);
+
+            stub.script(
+                "model",
+                "#!/bin/sh\necho 'Bayesian inference with Markov Chain Monte Carlo'\n",
+            );
+            let e = CmdStan::try_from(stub.root()).unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::Bernoulli);
+
+            let stub = StubCmdStan::new();
+            stub.script("stanc", "#!/bin/sh\nexit 1\n");
+            let e = CmdStan::try_from(stub.root()).unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::StanC);
+        }
+
+        #[test]
+        fn from_vars() {
+            let stub = StubCmdStan::new();
+            let root = stub.root().as_os_str().to_os_string();
+            let missing = stub.root().join("missing").into_os_string();
+
+            let e = CmdStan::from_vars(|_| None).unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::Install);
+            let msg = format!("{e:#}");
+            assert!(msg.contains("CMDSTAN: not set"), "{msg}");
+            assert!(msg.contains("CMDSTAN_HOME: not set"), "{msg}");
+
+            // `CMDSTAN_HOME` is used if `CMDSTAN` does not work.
+            let cmdstan = CmdStan::from_vars(|var| match var {
+                "CMDSTAN" => Some(missing.clone()),
+                _ => Some(root.clone()),
+            })
+            .unwrap();
+            let inner = cmdstan.inner.read().unwrap();
+            assert_eq!(inner.root, stub.root().canonicalize().unwrap());
+
+            let e =
+                CmdStan::from_vars(|var| (var == "CMDSTAN").then(|| missing.clone())).unwrap_err();
+            let msg = format!("{e:#}");
+            assert!(msg.contains("missing"), "{msg}");
+            assert!(msg.contains("CMDSTAN_HOME: not set"), "{msg}");
+        }
+
+        #[test]
+        fn check() {
+            let stub = StubCmdStan::new();
+            let report = CmdStan::check(stub.root());
+            assert!(report.is_ok(), "{report}");
+            assert_eq!(report.checks().len(), 9);
+            assert!(report.to_string().starts_with("directory: ok\nmake: ok\n"));
+
+            let stub = StubCmdStan::new();
+            stub.script("stansummary", "#!/bin/sh\nexit 1\n");
+            let report = CmdStan::check(stub.root());
+            assert!(!report.is_ok());
+            let status = |name: &str| {
+                let check = report.checks().iter().find(|x| x.name() == name).unwrap();
+                match check.status() {
+                    CheckStatus::Passed => "passed",
+                    CheckStatus::Failed(_) => "failed",
+                    CheckStatus::Skipped => "skipped",
+                }
+            };
+            assert_eq!(status("stansummary"), "failed");
+            assert_eq!(status("bernoulli stansummary"), "skipped");
+            // Independent checks are run regardless.
+            assert_eq!(status("diagnose"), "passed");
+            assert_eq!(status("bernoulli diagnose"), "passed");
+            let failures = report.failures();
+            assert_eq!(failures.len(), 1);
+            match failures[0].status() {
+                CheckStatus::Failed(e) => assert_eq!(e.kind(), ErrorKind::StanSummary),
+                _ => unreachable!(),
+            }
+
+            let report = CmdStan::check(stub.root().join("nonexistent").as_ref());
+            assert!(!report.checks()[0].passed());
+            assert!(report.checks()[1..]
+                .iter()
+                .all(|x| matches!(x.status(), CheckStatus::Skipped)));
+        }
+
+        #[test]
+        fn try_from_with_checks() {
+            let stub = StubCmdStan::new();
+            stub.script("diagnose", "#!/bin/sh\nexit 1\n");
+            let e = CmdStan::try_from(stub.root()).unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::Diagnose);
+            let e = CmdStan::try_from_with_checks(stub.root(), InstallChecks::all()).unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::Diagnose);
+
+            let cmdstan =
+                CmdStan::try_from_with_checks(stub.root(), InstallChecks::without_diagnose())
+                    .unwrap();
+            let program = StanProgram::try_from(stub.program("model.stan").as_ref()).unwrap();
+            assert!(cmdstan.compile::<[_; 0], &str>(&program, []).is_ok());
+
+            assert!(CmdStan::try_from_with_checks(stub.root(), InstallChecks::none()).is_ok());
+            let checks = InstallChecks {
+                bernoulli_diagnose: true,
+                ..InstallChecks::none()
+            };
+            assert!(CmdStan::try_from_with_checks(stub.root(), checks).is_err());
+            let e = CmdStan::try_from_with_checks(
+                stub.root().join("nonexistent").as_ref(),
+                InstallChecks::none(),
+            )
+            .unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::Install);
+        }
+
+        #[test]
+        fn compile_and_call() {
+            let stub = StubCmdStan::new();
+            let cmdstan = CmdStan::try_from(stub.root()).unwrap();
+            let program = StanProgram::try_from(stub.program("model.stan").as_ref()).unwrap();
+
+            let output = cmdstan.stanc(&program, ["--O1"]).unwrap();
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            assert!(stdout.starts_with("--O1 "));
+
+            let model = cmdstan.compile::<[_; 0], &str>(&program, []).unwrap();
+            let dir = stub.root().join("run");
+            fs::create_dir(&dir).unwrap();
+            let tree = ArgTree::builder()
+                .output(crate::argtree::Output::builder().file("draws.csv"))
+                .build();
+            let output = model.call_in_dir(&tree, &dir).unwrap();
+            assert_eq!(output.output_files(), vec![dir.join("draws.csv")]);
+            let draws = output.draws().unwrap();
+            assert_eq!(draws.names(), ["lp__", "theta"]);
+            assert_eq!(draws.num_draws(), 2);
+
+            let output = cmdstan.diagnose(&output).unwrap();
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            assert!(stdout.contains("no problems detected"));
+        }
+
+        #[test]
+        fn call_capturing_run_summary() {
+            let stub = StubCmdStan::new();
+            let cmdstan = CmdStan::try_from(stub.root()).unwrap();
+            let program = StanProgram::try_from(stub.program("model.stan").as_ref()).unwrap();
+            let model = cmdstan.compile::<[_; 0], &str>(&program, []).unwrap();
+            // Overwrite the executable, which retains its permissions.
+            fs::write(
+                &model.exec,
+                "#!/bin/sh\necho 'Chain [1] Warning: 2 of 10 (20%) transitions ended with a divergence.'\n",
+            )
+            .unwrap();
+            let tree = ArgTree::builder()
+                .output(crate::argtree::Output::builder().file(stub.root().join("draws.csv")))
+                .build();
+
+            let output = model.call_capturing(&tree).unwrap();
+            assert!(!output.stdout_file().exists());
+            assert_eq!(output.run_summary().unwrap().divergences, 2);
+
+            // A stale log, e.g. of an earlier `call`, is not consulted.
+            fs::write(
+                output.stdout_file(),
+                "Chain [1] Warning: 7 of 10 (70%) transitions ended with a divergence.\n",
+            )
+            .unwrap();
+            assert_eq!(output.run_summary().unwrap().divergences, 2);
+        }
+
+        #[test]
+        fn call_err_output() {
+            let stub = StubCmdStan::new();
+            let cmdstan = CmdStan::try_from(stub.root()).unwrap();
+            let program = StanProgram::try_from(stub.program("model.stan").as_ref()).unwrap();
+            let model = cmdstan.compile::<[_; 0], &str>(&program, []).unwrap();
+            // Overwrite the executable, which retains its permissions.
+            fs::write(
+                &model.exec,
+                "#!/bin/sh\necho to stdout\necho to stderr >&2\nexit 1\n",
+            )
+            .unwrap();
+
+            let tree = ArgTree::builder()
+                .output(crate::argtree::Output::builder().file(stub.root().join("draws.csv")))
+                .build();
+            let e = model.call(&tree).unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::Executable);
+            let output = e.output().unwrap();
+            assert_eq!(output.stdout, b"to stdout\n");
+            assert_eq!(output.stderr, b"to stderr\n");
+        }
+
+        #[test]
+        fn model_name() {
+            let stub = StubCmdStan::new();
+            let cmdstan = CmdStan::try_from(stub.root()).unwrap();
+            let path = stub.program("schools.stan");
+            let program = StanProgram::try_from(path.as_ref()).unwrap();
+            let model = cmdstan.compile::<[_; 0], &str>(&program, []).unwrap();
+            assert_eq!(model.name(), "schools");
+            assert_eq!(
+                model.path(),
+                fs::canonicalize(stub.root()).unwrap().join("schools")
+            );
+        }
+
+        #[test]
+        fn compile_with_warnings() {
+            let stub = StubCmdStan::new();
+            let makefile = stub.root().join("makefile");
+            let contents = fs::read_to_string(&makefile).unwrap().replace(
+                "%:: %.stan\n",
+                "%:: %.stan\n\t@echo \"Warning in '$<', line 1, column 0: theta has no priors.\" >&2\n",
+            );
+            fs::write(&makefile, contents).unwrap();
+            let cmdstan = CmdStan::try_from(stub.root()).unwrap();
+            let path = stub.program("model.stan");
+            let program = StanProgram::try_from(path.as_ref()).unwrap();
+            let (_, warnings) = cmdstan
+                .compile_with_warnings::<[_; 0], &str>(&program, [])
+                .unwrap();
+            assert_eq!(warnings.len(), 1);
+            assert_eq!(warnings[0].position, Some((1, 0)));
+            assert_eq!(warnings[0].message, "theta has no priors.");
+
+            // The warnings of a cached model are those of its compilation.
+            let (_, cached) = cmdstan
+                .compile_with_warnings::<[_; 0], &str>(&program, [])
+                .unwrap();
+            assert_eq!(cached, warnings);
+        }
+
+        #[test]
+        fn model_info() {
+            let stub = StubCmdStan::new();
+            stub.script(
+                "stanc",
+                r#"#!/bin/sh
+if [ "$1" = "--help" ]; then
+    echo "Usage: stanc [option] <model_file>"
+    exit 0
+fi
+if [ "$1" = "--info" ]; then
+    echo '{ "inputs": { "N": { "type": "int", "dimensions": 0} }, "parameters": { } }'
+    exit 0
+fi
+exit 1
+"#,
+            );
+            let cmdstan = CmdStan::try_from(stub.root()).unwrap();
+            let path = stub.program("model.stan");
+            let program = StanProgram::try_from(path.as_ref()).unwrap();
+            let x = cmdstan.model_info(&program).unwrap();
+            assert_eq!(x.input_names(), vec!["N"]);
+            assert!(x.has_no_parameters());
+        }
+
+        #[test]
+        fn format_model() {
+            let stub = StubCmdStan::new();
+            stub.script(
+                "stanc",
+                r#"#!/bin/sh
+if [ "$1" = "--help" ]; then
+    echo "Usage: stanc [option] <model_file>"
+    exit 0
+fi
+if grep -q oops "$2"; then
+    echo 'Syntax error' >&2
+    exit 1
+fi
+if [ "$1" = "--auto-format" ]; then
+    printf 'parameters {\n  real theta;\n}\n\n'
+    exit 0
+fi
+exit 1
+"#,
+            );
+            let cmdstan = CmdStan::try_from(stub.root()).unwrap();
+            let path = stub.program("model.stan");
+            let program = StanProgram::try_from(path.as_ref()).unwrap();
+            let x = cmdstan.format_model(&program).unwrap();
+            assert_eq!(x, "parameters {\n  real theta;\n}\n\n");
+
+            let path = stub.root().join("oops.stan");
+            fs::write(&path, "parameters { oops }\n").unwrap();
+            let program = StanProgram::try_from(path.as_ref()).unwrap();
+            let e = cmdstan.format_model(&program).unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::StanC);
+        }
+
+        #[test]
+        fn examples() {
+            let stub = StubCmdStan::new();
+            let dir = stub.root().join("examples").join("a");
+            fs::create_dir(&dir).unwrap();
+            fs::write(dir.join("a.stan"), "").unwrap();
+            fs::write(dir.join("a.data.json"), "{}").unwrap();
+            let cmdstan = CmdStan::try_from(stub.root()).unwrap();
+            let examples = cmdstan.examples().unwrap();
+            let names: Vec<_> = examples
+                .iter()
+                .map(|x| x.path().file_name().unwrap())
+                .collect();
+            assert_eq!(names, ["a.stan", "bernoulli.stan"]);
+
+            fs::remove_dir_all(stub.root().join("examples")).unwrap();
+            assert!(cmdstan.examples().unwrap().is_empty());
+        }
+
+        #[test]
+        fn from_source() {
+            let stub = StubCmdStan::new();
+            let cmdstan = CmdStan::try_from(stub.root()).unwrap();
+            let program = StanProgram::from_source("inline", "parameters { real theta; }").unwrap();
+            assert_eq!(program.source().unwrap(), "parameters { real theta; }");
+            let source = program.path().to_path_buf();
+            let model = cmdstan.compile::<[_; 0], &str>(&program, []).unwrap();
+            assert_eq!(model.name(), "inline");
+
+            // The model keeps the directory alive after the program is dropped.
+            drop(program);
+            assert!(source.is_file());
+            assert!(model.path().is_file());
+            let exec = model.path().to_path_buf();
+            drop(model);
+            assert!(!source.exists());
+            assert!(!exec.exists());
+
+            for name in ["../inline", "a/inline", ""] {
+                let e = StanProgram::from_source(name, "").unwrap_err();
+                assert_eq!(e.kind(), ErrorKind::Validation);
+            }
+        }
+
+        #[test]
+        fn fit() {
+            let stub = StubCmdStan::new();
+            let cmdstan = CmdStan::try_from(stub.root()).unwrap();
+            let program = StanProgram::try_from(stub.program("model.stan").as_ref()).unwrap();
+            let data = crate::argtree::Data::builder()
+                .file(stub.root().join("examples/bernoulli/bernoulli.data.json"));
+            let fit = cmdstan
+                .fit(
+                    &program,
+                    data,
+                    crate::method::SampleBuilder::new().num_samples(2),
+                )
+                .unwrap();
+            assert_eq!(fit.draws().column("theta"), Some(&[0.2, 0.3][..]));
+            assert_eq!(fit.model().name(), "model");
+            let files = fit.output().output_files();
+            assert_eq!(files.len(), 1);
+            assert!(!files[0].starts_with(stub.root()));
+
+            let summary = fit.summary().unwrap();
+            assert_eq!(summary.get("theta", "Mean"), Some(0.25));
+            assert!(std::ptr::eq(summary, fit.summary().unwrap()));
+
+            // The files are deleted with the fit.
+            drop(fit);
+            assert!(!files[0].exists());
+        }
+
+        #[test]
+        fn compile_cache() {
+            let stub = StubCmdStan::new();
+            let cmdstan = CmdStan::try_from(stub.root()).unwrap();
+            let path = stub.program("model.stan");
+            let program = StanProgram::try_from(path.as_ref()).unwrap();
+            let model = cmdstan.compile::<[_; 0], &str>(&program, []).unwrap();
+
+            // With a makefile which fails, only a cached model can be returned.
+            let makefile = stub.root().join("makefile");
+            let contents = fs::read_to_string(&makefile).unwrap();
+            let broken = "$(error broken)\n";
+            fs::write(&makefile, broken).unwrap();
+            assert_eq!(
+                cmdstan
+                    .clone()
+                    .compile::<[_; 0], &str>(&program, [])
+                    .unwrap(),
+                model
+            );
+
+            // Distinct arguments are not a hit.
+            let e = cmdstan.compile(&program, ["-j2"]).unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::Compilation);
+            assert_eq!(
+                cmdstan.compile::<[_; 0], &str>(&program, []).unwrap(),
+                model
+            );
+
+            // A change to the program invalidates the model.
+            fs::write(&path, "parameters { real mu; }\n").unwrap();
+            let e = cmdstan.compile::<[_; 0], &str>(&program, []).unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::Compilation);
+
+            fs::write(&makefile, contents).unwrap();
+            assert_eq!(
+                cmdstan.compile::<[_; 0], &str>(&program, []).unwrap(),
+                model
+            );
+            cmdstan.clean().unwrap();
+            fs::write(&makefile, broken).unwrap();
+            // The cache is empty; the executable being newer than the
+            // program does not matter.
+            assert!(cmdstan.compile::<[_; 0], &str>(&program, []).is_err());
+        }
+
+        #[test]
+        fn compile_if_stale() {
+            let stub = StubCmdStan::new();
+            stub.script(
+                "stanc",
+                r#"#!/bin/sh
+if [ "$1" = "--help" ]; then
+    echo "Usage: stanc [option] <model_file>"
+elif [ "$1" = "--info" ]; then
+    echo '{"included_files": ["functions.stanfunctions"]}'
+fi
+"#,
+            );
+            let cmdstan = CmdStan::try_from(stub.root()).unwrap();
+            let include = stub.program("functions.stanfunctions");
+            let path = stub.program("model.stan");
+            let program = StanProgram::try_from(path.as_ref()).unwrap();
+            let model = cmdstan.compile::<[_; 0], &str>(&program, []).unwrap();
+
+            // With a makefile which fails, make is evidently not invoked.
+            let makefile = stub.root().join("makefile");
+            let contents = fs::read_to_string(&makefile).unwrap();
+            fs::write(&makefile, "$(error broken)\n").unwrap();
+            assert_eq!(cmdstan.compile_if_stale(&program).unwrap(), model);
+
+            // Distinct arguments are never a hit for `compile`, even if
+            // the executable is newer than the program.
+            let e = cmdstan.compile(&program, ["STAN_OPENCL=true"]).unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::Compilation);
+
+            // Once an included file is newer, make must be invoked.
+            let later = std::time::SystemTime::now() + std::time::Duration::from_secs(60);
+            let f = File::options().write(true).open(&include).unwrap();
+            f.set_modified(later).unwrap();
+            let e = cmdstan.compile_if_stale(&program).unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::Compilation);
+
+            fs::write(&makefile, contents).unwrap();
+            assert!(cmdstan.compile_if_stale(&program).is_ok());
+        }
+
+        #[test]
+        fn version() {
+            let stub = StubCmdStan::new();
+            let cmdstan = CmdStan::try_from(stub.root()).unwrap();
+            assert_eq!(cmdstan.version(), None);
+
+            let makefile = stub.root().join("makefile");
+            let contents = fs::read_to_string(&makefile).unwrap();
+            fs::write(
+                &makefile,
+                format!("CMDSTAN_VERSION := 2.36.0-rc1\n{contents}"),
+            )
+            .unwrap();
+            assert_eq!(cmdstan.version(), Some((2, 36, 0)));
+
+            fs::write(&makefile, format!("CMDSTAN_VERSION := 2.32.2\n{contents}")).unwrap();
+            assert_eq!(cmdstan.version(), Some((2, 32, 2)));
+            let output = fake_output(stub.root(), "output.csv", "lp__\n0\n");
+            let opts = StanSummaryOptions::builder().include_param(["theta"]);
+            let e = cmdstan.stansummary(&output, opts.clone()).unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::Validation);
+            assert!(cmdstan.stansummary(&output, None).is_ok());
+
+            fs::write(&makefile, format!("CMDSTAN_VERSION := 2.33.1\n{contents}")).unwrap();
+            assert!(cmdstan.stansummary(&output, opts).is_ok());
+        }
+
+        #[test]
+        fn chains() {
+            let stub = StubCmdStan::new();
+            let cmdstan = CmdStan::try_from(stub.root()).unwrap();
+            let output = fake_output(stub.root(), "output.csv", "lp__\n0\n");
+            assert!(cmdstan.diagnose_chains(&output, &[1]).is_ok());
+            assert!(cmdstan.stansummary_chains(&output, &[1], None).is_ok());
+            let e = cmdstan.diagnose_chains(&output, &[2]).unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::Diagnose);
+            assert!(format!("{e:#}").contains("no output files present for chains [2]"));
+            let e = cmdstan.stansummary_chains(&output, &[], None).unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::StanSummary);
+        }
+
+        #[test]
+        fn no_output_files() {
+            let stub = StubCmdStan::new();
+            let cmdstan = CmdStan::try_from(stub.root()).unwrap();
+            let output = fake_output(stub.root(), "output.csv", "lp__\n0\n");
+            fs::remove_file(stub.root().join("output.csv")).unwrap();
+            let e = cmdstan.diagnose(&output).unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::Diagnose);
+            assert!(format!("{e:#}").contains("no output files present"));
+            assert!(e.output().is_none());
+            let e = cmdstan.stansummary(&output, None).unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::StanSummary);
+        }
+
+        #[test]
+        fn stansummary_parsed() {
+            let stub = StubCmdStan::new();
+            let cmdstan = CmdStan::try_from(stub.root()).unwrap();
+            let output = fake_output(stub.root(), "output.csv", "lp__\n0\n");
+            let summary = cmdstan.stansummary_parsed(&output, None).unwrap();
+            assert_eq!(summary.get("theta", "Mean"), Some(0.25));
+            assert!(!stub.root().join("stansummary.csv").exists());
+            assert!(!output.cwd_at_call.join("stansummary.csv").exists());
+
+            let csv = stub.root().join("summary.csv");
+            let opts = StanSummaryOptions::builder().csv_filename(&csv);
+            assert!(cmdstan.stansummary_parsed(&output, opts).is_ok());
+            assert!(csv.is_file());
+
+            // The stub reports the default percentiles, regardless of those requested.
+            let opts = StanSummaryOptions::builder().percentiles(vec![50.0]);
+            let e = cmdstan.stansummary_parsed(&output, opts).unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::StanSummary);
+        }
+
+        #[test]
+        fn call_with_progress() {
+            let stub = StubCmdStan::new();
+            let cmdstan = CmdStan::try_from(stub.root()).unwrap();
+            let program = StanProgram::try_from(stub.program("model.stan").as_ref()).unwrap();
+            let model = cmdstan.compile::<[_; 0], &str>(&program, []).unwrap();
+            let tree = ArgTree::builder()
+                .output(crate::argtree::Output::builder().file(stub.root().join("draws.csv")))
+                .build();
+            let mut updates = Vec::new();
+            let output = model
+                .call_with_progress(&tree, |p| updates.push(p.iteration))
+                .unwrap();
+            assert_eq!(updates, [1, 2]);
+
+            // Coalesced updates are reported at exit.
+            let config = ProgressConfig {
+                buffer_capacity: 1,
+                poll_interval: std::time::Duration::from_secs(3600),
+            };
+            let mut updates = Vec::new();
+            model
+                .call_with_progress_config(&tree, &config, |p| updates.push(p.iteration))
+                .unwrap();
+            assert_eq!(updates, [1, 2]);
+
+            let output = cmdstan.stansummary(&output, None).unwrap();
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            assert!(stdout.lines().any(|l| l.starts_with("theta")));
+        }
+
+        #[test]
+        fn call_with_progress_not_utf8() {
+            let dir = temp_dir();
+            // More than the capacity of a pipe follows the invalid line.
+            let model = fake_model(
+                &dir,
+                "printf 'file=\\377\\376\\n'\n\
+                 head -c 262144 /dev/zero | tr '\\0' x\n\
+                 echo\n\
+                 echo 'Iteration: 1 / 2 [ 50%]  (Warmup)'",
+            );
+            let tree = ArgTree::builder()
+                .output(crate::argtree::Output::builder().file(dir.join("output.csv")))
+                .build();
+            let mut updates = Vec::new();
+            let output = model
+                .call_with_progress(&tree, |p| updates.push(p.iteration))
+                .unwrap();
+            assert_eq!(updates, [1]);
+            let stdout = fs::read(output.stdout_file()).unwrap();
+            assert!(stdout.starts_with(b"file=\xff\xfe\n"));
+            assert_eq!(stdout.len(), 8 + 262144 + 1 + 34);
+        }
+    }
+
+    #[cfg(all(unix, feature = "tokio"))]
+    mod call_async {
+        use super::*;
+
+        fn block_on<F: std::future::Future>(f: F) -> F::Output {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap()
+                .block_on(f)
+        }
+
+        #[test]
+        fn compile_and_call() {
+            let stub = StubCmdStan::new();
+            let cmdstan = CmdStan::try_from(stub.root()).unwrap();
+            let program = StanProgram::try_from(stub.program("model.stan").as_ref()).unwrap();
+            let model = block_on(cmdstan.compile_async::<[_; 0], &str>(&program, [])).unwrap();
+            assert_eq!(
+                model,
+                cmdstan.compile::<[_; 0], &str>(&program, []).unwrap()
+            );
+
+            let tree = ArgTree::builder()
+                .output(crate::argtree::Output::builder().file(stub.root().join("draws.csv")))
+                .build();
+            let output = block_on(model.call_async(&tree)).unwrap();
+            assert_eq!(output.output_files(), vec![stub.root().join("draws.csv")]);
+            let stdout = fs::read_to_string(output.stdout_file()).unwrap();
+            assert!(stdout.contains("Adjust your expectations accordingly!"));
+
+            let program = StanProgram::try_from(stub.program("fails.stan").as_ref()).unwrap();
+            stub.script("model", "#!/bin/sh\necho oops >&2\nexit 3\n");
+            let e = block_on(cmdstan.compile_async::<[_; 0], &str>(&program, [])).unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::Executable);
+        }
+
+        #[test]
+        fn opencl() {
+            let dir = temp_dir();
+            let body = "if [ \"$1\" = info ]; then echo STAN_OPENCL=false; exit 0; fi\necho ran";
+            let model = fake_model(&dir, body);
+            let tree = ArgTree::builder()
+                .output(crate::argtree::Output::builder().file(dir.join("output.csv")))
+                .build()
+                .with_opencl(0, 0);
+            let e = block_on(model.call_async(&tree)).unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::Validation);
+            assert_eq!(model.build_info().unwrap()["STAN_OPENCL"], "false");
+
+            let model = fake_model(&dir, &body.replace("false", "true"));
+            assert!(block_on(model.call_async(&tree)).is_ok());
+        }
+    }
+
+    #[cfg(unix)]
+    mod call {
+        use super::*;
+
+        #[test]
+        fn call_in_dir() {
+            let dir = temp_dir();
+            let model = fake_model(&dir, "pwd; echo x > output.csv");
+            let tree = ArgTree::default();
+            let output = model.call_in_dir(&tree, &dir).unwrap();
+            assert_eq!(output.cwd_at_call(), &*dir);
+            assert_eq!(output.stdout_file(), dir.join("output_stdout_log.txt"));
+            assert_eq!(output.stderr_file(), dir.join("output_stderr_log.txt"));
+            assert_eq!(output.output_files(), vec![dir.join("output.csv")]);
+            let stdout = fs::read_to_string(output.stdout_file()).unwrap();
+            assert_eq!(Path::new(stdout.trim()), fs::canonicalize(&dir).unwrap());
+        }
+
+        #[test]
+        fn call_with_id_from() {
+            let dir = temp_dir();
+            let model = fake_model(&dir, "echo \"$@\"");
+            let tree = ArgTree::builder()
+                .output(crate::argtree::Output::builder().file(dir.join("output.csv")))
+                .build();
+            let ids = ChainIdAllocator::default();
+            let outputs: Vec<CmdStanOutput> = std::thread::scope(|scope| {
+                let handles: Vec<_> = (0..4)
+                    .map(|_| scope.spawn(|| model.call_with_id_from(&tree, &ids).unwrap()))
+                    .collect();
+                handles.into_iter().map(|h| h.join().unwrap()).collect()
+            });
+            let mut ids: Vec<i32> = outputs.iter().map(|x| x.argtree().id).collect();
+            ids.sort();
+            assert_eq!(ids, [1, 2, 3, 4]);
+            for output in outputs.iter() {
+                let id = output.argtree().id;
+                let stdout = fs::read_to_string(output.stdout_file()).unwrap();
+                assert!(stdout.contains(&format!("id={id} ")));
+                assert_eq!(
+                    output.stdout_file(),
+                    dir.join(format!("output_{id}_stdout_log.txt"))
+                );
+            }
+        }
+
+        #[test]
+        fn call_with_env() {
+            let dir = temp_dir();
+            let model = fake_model(&dir, "echo \"$CMDSTAN_RS_TEST_VAR\"");
+            let tree = ArgTree::builder()
+                .output(crate::argtree::Output::builder().file(dir.join("output.csv")))
+                .build();
+            let envs = [("CMDSTAN_RS_TEST_VAR".into(), "gpu".into())];
+            let output = model.call_with_env(&tree, &envs).unwrap();
+            let stdout = fs::read_to_string(output.stdout_file()).unwrap();
+            assert_eq!(stdout.trim(), "gpu");
+            assert!(env::var_os("CMDSTAN_RS_TEST_VAR").is_none());
+
+            let output = model.call(&tree).unwrap();
+            let stdout = fs::read_to_string(output.stdout_file()).unwrap();
+            assert_eq!(stdout.trim(), "");
+        }
+
+        #[test]
+        fn method_grid() {
+            use crate::method::{OptimizeBuilder, SampleBuilder};
+            let dir = temp_dir();
+            let model = fake_model(&dir, "echo \"$@\"");
+            let base = ArgTree::builder()
+                .output(crate::argtree::Output::builder().file(dir.join("output.csv")))
+                .build();
+            let mut grid = MethodGrid::new(base)
+                .add("long", SampleBuilder::new().num_warmup(5000))
+                .add("optimize", OptimizeBuilder::new());
+            grid.extend([("default", Method::default())]);
+            assert_eq!(grid.labels(), ["long", "optimize", "default"]);
+            let tree = grid.tree("optimize").unwrap();
+            assert_eq!(tree.output.file, dir.join("output_optimize.csv"));
+            assert_eq!(tree.method, OptimizeBuilder::new().build());
+            assert_eq!(grid.tree("missing"), None);
+
+            let results = grid.run(&model);
+            assert_eq!(results.len(), 3);
+            assert_eq!(results[0].0, "long");
+            let output = results[0].1.as_ref().unwrap();
+            let stdout = fs::read_to_string(output.stdout_file()).unwrap();
+            assert!(stdout.contains("num_warmup=5000"));
+            assert!(stdout.contains("output_long.csv"));
+        }
+
+        #[test]
+        fn call_with_stdin() {
+            let dir = temp_dir();
+            let model = fake_model(&dir, &format!("cat > {}", dir.join("stdin.txt").display()));
+            let tree = ArgTree::builder()
+                .output(crate::argtree::Output::builder().file(dir.join("output.csv")))
+                .build();
+            let data = "{\"N\": 2, \"y\": [0, 1]}";
+            let output = model.call_with_stdin(&tree, data.as_bytes()).unwrap();
+            assert_eq!(fs::read_to_string(dir.join("stdin.txt")).unwrap(), data);
+            assert_eq!(output.stdout_file(), dir.join("output_stdout_log.txt"));
+
+            // Input which is not read is discarded.
+            let model = fake_model(&dir, "exit 0");
+            let data = vec![b'x'; 1 << 20];
+            assert!(model.call_with_stdin(&tree, &data[..]).is_ok());
+
+            let model = fake_model(&dir, "read x; echo \"$x\" >&2; exit 1");
+            let e = model
+                .call_with_stdin(&tree, "oops\n".as_bytes())
+                .unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::Executable);
+            let stderr = fs::read_to_string(dir.join("output_stderr_log.txt")).unwrap();
+            assert_eq!(stderr, "oops\n");
+        }
+
+        #[test]
+        fn log_prob() {
+            let dir = temp_dir();
+            let body = format!(
+                "echo \"$@\" > {}\nprintf 'lp__,g_1\\n-1.5,0.5\\n' > {}",
+                dir.join("args.txt").display(),
+                dir.join("output.csv").display()
+            );
+            let model = fake_model(&dir, &body);
+            let tree = ArgTree::builder()
+                .output(crate::argtree::Output::builder().file(dir.join("output.csv")))
+                .build();
+            let x = model
+                .log_prob(&tree, dir.join("params.json"), false)
+                .unwrap();
+            assert_eq!(x.lp(), [-1.5]);
+            assert_eq!(x.gradients(), [vec![0.5]]);
+            let args = fs::read_to_string(dir.join("args.txt")).unwrap();
+            assert!(args.contains(&format!(
+                "log_prob unconstrained_params={} constrained_params= jacobian=0",
+                dir.join("params.json").display()
+            )));
+        }
+
+        #[test]
+        fn build_info_cached() {
+            let dir = temp_dir();
+            let body = format!(
+                "echo x >> {}; echo stan_version_major=2",
+                dir.join("count.txt").display()
+            );
+            let model = fake_model(&dir, &body);
+            let clone = model.clone();
+            assert_eq!(model.build_info().unwrap()["stan_version_major"], "2");
+            assert_eq!(clone.build_info().unwrap().len(), 1);
+            assert_eq!(model.build_info().unwrap(), clone.build_info().unwrap());
+            let count = fs::read_to_string(dir.join("count.txt")).unwrap();
+            assert_eq!(count.lines().count(), 1);
+            assert_eq!(model, clone);
+        }
+
+        #[test]
+        fn opencl() {
+            let dir = temp_dir();
+            let body = "if [ \"$1\" = info ]; then echo STAN_OPENCL=false; exit 0; fi\necho ran";
+            let model = fake_model(&dir, body);
+            assert_eq!(model.build_info().unwrap()["STAN_OPENCL"], "false");
+            let tree = ArgTree::builder()
+                .output(crate::argtree::Output::builder().file(dir.join("output.csv")))
+                .build();
+            assert!(model.call(&tree).is_ok());
+
+            let tree = tree.with_opencl(0, 0);
+            let e = model.call(&tree).unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::Validation);
+            assert!(format!("{e:#}").contains("STAN_OPENCL=true"));
+            assert!(model.call_capturing(&tree).is_err());
+
+            let model = fake_model(&dir, &body.replace("false", "true"));
+            assert!(model.call(&tree).is_ok());
+        }
+
+        #[test]
+        fn call_capturing() {
+            let dir = temp_dir();
+            let model = fake_model(&dir, "echo out; echo err >&2");
+            let tree = ArgTree::builder()
+                .output(crate::argtree::Output::builder().file(dir.join("output.csv")))
+                .build();
+            let output = model.call_capturing(&tree).unwrap();
+            assert_eq!(output.output().stdout, b"out\n");
+            assert_eq!(output.output().stderr, b"err\n");
+            assert_eq!(output.stdout_file(), dir.join("output_stdout_log.txt"));
+            assert!(!output.stdout_file().exists());
+            assert!(!output.stderr_file().exists());
+
+            let model = fake_model(&dir, "echo oops >&2; exit 1");
+            let e = model.call_capturing(&tree).unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::Executable);
+            assert!(format!("{e:#}").contains("oops"));
+            assert!(!dir.join("output_stderr_log.txt").exists());
+        }
+    }
+
+    mod output {
+        use super::*;
+        use crate::method::{HmcBuilder, OptimizeBuilder, SampleBuilder};
+
+        #[test]
+        fn continue_sampling() {
+            let dir = temp_dir();
+            let csv = |stepsize: f64, metric: f64| {
+                format!(
+                    "lp__,theta\n# Adaptation terminated\n# Step size = {stepsize}\n\
+                     # Diagonal elements of inverse mass matrix:\n# {metric}\n1,2\n"
+                )
+            };
+            fs::write(dir.join("output_1.csv"), csv(0.5, 1.5)).unwrap();
+            fs::write(dir.join("output_2.csv"), csv(0.25, 2.5)).unwrap();
+            let mut output = fake_output(&dir, "output.csv", "");
+            output.argtree.method = SampleBuilder::new().num_chains(2).build();
+            let trees = output.continue_sampling(100).unwrap();
+            assert_eq!(trees.len(), 2);
+            for (tree, (id, stepsize)) in trees.iter().zip([(1, 0.5), (2, 0.25)]) {
+                assert_eq!(tree.id, id);
+                assert_eq!(
+                    tree.output_files(),
+                    [dir.join(format!("output_continued_{id}.csv"))
+                        .into_os_string()]
+                );
+                let Method::Sample {
+                    num_samples,
+                    num_warmup,
+                    adapt,
+                    num_chains,
+                    algorithm:
+                        SampleAlgorithm::Hmc {
+                            stepsize: x,
+                            metric_file,
+                            ..
+                        },
+                    ..
+                } = &tree.method
+                else {
+                    unreachable!()
+                };
+                assert_eq!((*num_samples, *num_warmup, *num_chains), (100, 0, 1));
+                assert!(!adapt.engaged);
+                assert_eq!(*x, stepsize);
+                assert_eq!(
+                    *metric_file,
+                    dir.join(format!("output_metric_{id}.json"))
+                        .into_os_string()
+                );
+                assert!(tree.method.validate_combination().is_ok());
+            }
+            let s = fs::read_to_string(dir.join("output_metric_2.json")).unwrap();
+            assert_eq!(s, r#"{"inv_metric":[2.5]}"#);
+
+            output.argtree.method = OptimizeBuilder::new().build();
+            let e = output.continue_sampling(100).unwrap_err();
+            assert_eq!(e.kind(), io::ErrorKind::InvalidInput);
+        }
+
+        #[test]
+        fn lp_trace() {
+            let dir = temp_dir();
+            let header = "lp__,theta\n";
+            fs::write(
+                dir.join("output_1.csv"),
+                header.to_string() + "-1,2\n-3,4\n",
+            )
+            .unwrap();
+            fs::write(dir.join("output_2.csv"), header.to_string() + "-5,6\n").unwrap();
+            let mut output = fake_output(&dir, "output.csv", "");
+            output.argtree.method = SampleBuilder::new().num_chains(2).build();
+            assert_eq!(output.lp_trace().unwrap(), [vec![-1.0, -3.0], vec![-5.0]]);
+
+            fs::write(dir.join("output_2.csv"), "theta\n6\n").unwrap();
+            let e = output.lp_trace().unwrap_err();
+            assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        }
+
+        #[test]
+        fn save_draws_as() {
+            let dir = temp_dir();
+            let header = "lp__,theta\n";
+            fs::write(dir.join("output_1.csv"), header.to_string() + "1,2\n3,4\n").unwrap();
+            fs::write(dir.join("output_3.csv"), header.to_string() + "5,6\n").unwrap();
+            let mut output = fake_output(&dir, "output.csv", "");
+            output.argtree.method = SampleBuilder::new().num_chains(3).build();
+            let path = dir.join("draws.csv");
+            output.save_draws_as(ExportFormat::Csv, &path).unwrap();
+            assert_eq!(
+                fs::read_to_string(&path).unwrap(),
+                "chain,lp__,theta\n1,1,2\n1,3,4\n3,5,6\n"
+            );
+
+            output.argtree.output.file = dir.join("missing.csv").into();
+            let e = output.save_draws_as(ExportFormat::Csv, &path).unwrap_err();
+            assert_eq!(e.kind(), io::ErrorKind::NotFound);
+        }
+
+        #[test]
+        fn chain_statuses() {
+            let dir = temp_dir();
+            let header = "lp__,theta\n";
+            fs::write(dir.join("output_1.csv"), header.to_string() + "1,2\n3,4\n").unwrap();
+            fs::write(dir.join("output_2.csv"), header.to_string() + "1,2\n").unwrap();
+            fs::write(dir.join("output_4.csv"), "# truncated").unwrap();
+            let mut output = fake_output(&dir, "output.csv", "");
+            output.argtree.method = SampleBuilder::new().num_samples(2).num_chains(4).build();
+            assert_eq!(
+                output.chain_statuses(),
+                vec![
+                    ChainStatus::Complete,
+                    ChainStatus::Incomplete {
+                        expected: 2,
+                        found: 1
+                    },
+                    ChainStatus::Missing,
+                    ChainStatus::Missing,
+                ]
+            );
+            assert!(output.chain_statuses()[0].is_complete());
+
+            // Without an expected number of draws, any readable file is complete.
+            output.argtree.method = OptimizeBuilder::new().build();
+            fs::write(dir.join("output.csv"), header).unwrap();
+            assert_eq!(output.chain_statuses(), vec![ChainStatus::Complete]);
+        }
+
+        #[test]
+        fn missing_output_files() {
+            let dir = temp_dir();
+            let mut output = fake_output(&dir, "output.csv", "");
+            fs::remove_file(dir.join("output.csv")).unwrap();
+            output.argtree.method = SampleBuilder::new().num_chains(3).build();
+            fs::write(dir.join("output_2.csv"), "").unwrap();
+            let planned: Vec<_> = (1..=3)
+                .map(|i| dir.join(format!("output_{i}.csv")))
+                .collect();
+            assert_eq!(output.planned_output_files(), planned);
+            assert_eq!(output.output_files(), vec![planned[1].clone()]);
+            assert_eq!(
+                output.missing_output_files(),
+                vec![planned[0].clone(), planned[2].clone()]
+            );
+
+            fs::write(dir.join("output_1.csv"), "").unwrap();
+            fs::write(dir.join("output_3.csv"), "").unwrap();
+            assert!(output.missing_output_files().is_empty());
+        }
+
+        #[test]
+        fn output_files_for_chains() {
+            let dir = temp_dir();
+            let mut output = fake_output(&dir, "output.csv", "");
+            output.argtree.id = 3;
+            output.argtree.method = SampleBuilder::new().num_chains(3).build();
+            for id in [3, 5] {
+                fs::write(dir.join(format!("output_{id}.csv")), "").unwrap();
+            }
+            assert_eq!(
+                output.output_files_for_chains(&[5, 3]),
+                vec![dir.join("output_5.csv"), dir.join("output_3.csv")]
+            );
+            // Chain 4 is missing, and chains 1 and 6 were not run.
+            assert!(output.output_files_for_chains(&[1, 4, 6]).is_empty());
+        }
+
+        #[test]
+        fn delete_artifacts() {
+            let dir = temp_dir();
+            let mut output = fake_output(&dir, "output.csv", "lp__\n0\n");
+            output.argtree.method = SampleBuilder::new().num_chains(2).build();
+            output.argtree.output.diagnostic_file = "diagnostic.csv".into();
+            for file in [
+                "output_1.csv",
+                "output_2.csv",
+                "diagnostic_1.csv",
+                "stdout.txt",
+                "keep.csv",
+            ] {
+                fs::write(dir.join(file), "").unwrap();
+            }
+            output.delete_artifacts().unwrap();
+            let mut remaining: Vec<_> = fs::read_dir(&dir)
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name())
+                .collect();
+            remaining.sort();
+            assert_eq!(remaining, ["keep.csv", "output.csv"]);
+        }
+
+        #[test]
+        fn max_treedepth_hits() {
+            let dir = temp_dir();
+            let csv = "lp__,treedepth__\n0,3\n0,4\n0,2\n";
+            let mut output = fake_output(&dir, "output.csv", csv);
+            output.argtree.method = SampleBuilder::new()
+                .algorithm(HmcBuilder::new().nuts(|b| b.max_depth(3)))
+                .build();
+            assert_eq!(output.max_treedepth_hits().unwrap(), 2);
+
+            // Saved warmup draws are excluded from each chain.
+            let csv = "lp__,treedepth__\n0,3\n0,3\n0,3\n0,2\n0,3\n";
+            for id in [1, 2] {
+                fs::write(dir.join(format!("output_{id}.csv")), csv).unwrap();
+            }
+            output.argtree.method = SampleBuilder::new()
+                .num_warmup(5)
+                .thin(2)
+                .save_warmup(true)
+                .num_chains(2)
+                .algorithm(HmcBuilder::new().nuts(|b| b.max_depth(3)))
+                .build();
+            assert_eq!(output.max_treedepth_hits().unwrap(), 2);
+            assert_eq!(output.draws().unwrap().max_treedepth_hits(3).unwrap(), 8);
+            output.argtree.method = SampleBuilder::new()
+                .num_warmup(5)
+                .thin(2)
+                .num_chains(2)
+                .algorithm(HmcBuilder::new().nuts(|b| b.max_depth(3)))
+                .build();
+            assert_eq!(output.max_treedepth_hits().unwrap(), 8);
+
+            output.argtree.method = SampleBuilder::new()
+                .algorithm(HmcBuilder::new().static_engine(|b| b))
+                .build();
+            let e = output.max_treedepth_hits().unwrap_err();
+            assert_eq!(e.kind(), io::ErrorKind::InvalidInput);
+            output.argtree.method = OptimizeBuilder::new().build();
+            assert!(output.max_treedepth_hits().is_err());
+        }
+
+        #[test]
+        fn run_summary() {
+            let console = "Iteration: 2000 / 2000 [100%]  (Sampling)\n\
+                Chain [1] Warning: 12 of 1000 (1.2%) transitions ended with a divergence.\n\
+                Chain [2] Warning: 3 of 1000 (0.3%) transitions ended with a divergence.\n\
+                5 of 1000 (0.5%) transitions hit the maximum treedepth limit of 10, or 2^10 leapfrog steps.\n\
+                Gradient evaluation took 1e-05 seconds\n";
+            let x = RunSummary::from_console(console);
+            assert_eq!(x.divergences, 15);
+            assert_eq!(x.max_treedepth_hits, 5);
+            assert!(!x.is_clean());
+            assert!(RunSummary::from_console("Elapsed Time: 0.01 seconds\n").is_clean());
+
+            let dir = temp_dir();
+            let output = fake_output(&dir, "output.csv", "lp__\n0\n");
+            fs::write(output.stdout_file(), console).unwrap();
+            assert_eq!(output.run_summary().unwrap(), x);
+            fs::remove_file(output.stdout_file()).unwrap();
+            let e = output.run_summary().unwrap_err();
+            assert_eq!(e.kind(), io::ErrorKind::NotFound);
+        }
+    }
+
+    mod output_set {
+        use super::*;
+
+        #[test]
+        fn accessors() {
+            let dir = temp_dir();
+            let set: CmdStanOutputSet = vec![
+                Ok(fake_output(&dir, "a.csv", "x\n1\n")),
+                Err(Error::validation("bad")),
+                Ok(fake_output(&dir, "c.csv", "x\n2\n3\n")),
+            ]
+            .into();
+            assert_eq!(set.len(), 3);
+            assert!(!set.all_succeeded());
+            let failures = set.failures();
+            assert_eq!(failures.len(), 1);
+            assert_eq!(failures[0].0, 1);
+            assert_eq!(failures[0].1.kind(), ErrorKind::Validation);
+            assert_eq!(
+                set.all_output_files(),
+                vec![dir.join("a.csv"), dir.join("c.csv")]
+            );
+
+            let draws = set.combined_draws().unwrap();
+            assert_eq!(draws.names(), ["run__", "x"]);
+            assert_eq!(draws.columns_ref()[0], vec![0.0, 2.0, 2.0]);
+            assert_eq!(draws.columns_ref()[1], vec![1.0, 2.0, 3.0]);
+
+            let set: CmdStanOutputSet = set.into_runs().into_iter().filter(|x| x.is_ok()).collect();
+            assert!(set.all_succeeded());
+            assert_eq!(set.len(), 2);
+
+            let set: CmdStanOutputSet = vec![
+                Ok(fake_output(&dir, "d.csv", "x\n1\n")),
+                Ok(fake_output(&dir, "e.csv", "y\n2\n")),
+            ]
+            .into();
+            assert_eq!(
+                set.combined_draws().unwrap_err().kind(),
+                io::ErrorKind::InvalidData
+            );
+
+            let set: CmdStanOutputSet = Vec::new().into();
+            assert!(set.all_succeeded());
+            assert_eq!(set.combined_draws().unwrap().names(), ["run__"]);
+        }
+    }
 }
@@ -16,6 +16,8 @@ enum Type {
     Float,
     String,
     Bool,
+    /// `Vec<T>`, which is set from any iterable of `Into<T>`.
+    Vec,
     NotPrimitive,
 }
 
@@ -72,34 +74,70 @@ impl From<&Ident> for Type {
     }
 }
 
+/// If `ty` is `Vec<T>`, return `T`.
+fn vec_element(ty: &syn::Type) -> Option<&syn::Type> {
+    let syn::Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Vec" {
+        return None;
+    }
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) => match args.args.first()? {
+            syn::GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 struct FieldInfo {
     ident: Ident,
-    ty: Ident,
+    ty: syn::Type,
     ty_coarse: Type,
     default: Option<syn::Lit>,
+    /// Set by `#[required]`: the field has no default, hence, the
+    /// builder must be given a value.
+    required: bool,
+    /// Set by `#[no_setter]`: the builder has no generated method to
+    /// configure the field, which takes its default unless set by a
+    /// method written by hand.
+    no_setter: bool,
 }
 impl From<&syn::Field> for FieldInfo {
     fn from(f: &syn::Field) -> Self {
         let ident = f.ident.clone().unwrap();
-        let ty_ident = match &f.ty {
-            syn::Type::Path(path) => path.path.get_ident(),
-            _ => unimplemented!("type is not `TypePath`"),
-        }
-        .unwrap();
-        let ty = (*ty_ident).clone();
-        let ty_coarse = Type::from(ty_ident);
+        let ty = f.ty.clone();
+        let ty_coarse = if vec_element(&ty).is_some() {
+            Type::Vec
+        } else {
+            let ty_ident = match &f.ty {
+                syn::Type::Path(path) => path.path.get_ident(),
+                _ => unimplemented!("type is not `TypePath`"),
+            }
+            .unwrap();
+            Type::from(ty_ident)
+        };
         let default = get_default(&f.attrs[..]);
+        let required = is_required(&f.attrs[..]);
+        if required && default.is_some() {
+            unimplemented!("`#[required]` field `{}` cannot have a default", ident);
+        }
+        let no_setter = is_flag(&f.attrs[..], "no_setter");
         Self {
             ident,
             ty,
             ty_coarse,
             default,
+            required,
+            no_setter,
         }
     }
 }
 
-#[proc_macro_derive(Builder, attributes(defaults_to))]
+#[proc_macro_derive(Builder, attributes(defaults_to, required, no_setter))]
 pub fn derive_builder(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
 
@@ -113,10 +151,28 @@ pub fn derive_builder(input: proc_macro::TokenStream) -> proc_macro::TokenStream
 }
 
 fn into_fns<'a>(fields: &'a [FieldInfo]) -> impl Iterator<Item = TokenStream> + 'a {
-    fields.iter().map(
+    fields.iter().filter(|f| !f.no_setter).map(
         |FieldInfo {
-             ref ident, ref ty, ..
+             ref ident,
+             ref ty,
+             ref ty_coarse,
+             ..
          }| {
+            if *ty_coarse == Type::Vec {
+                let elem = vec_element(ty).unwrap();
+                let doc = format!("Configure `{}` with the given values.", ident);
+                return quote! {
+                    #[doc = #doc]
+                    pub fn #ident<I, S>(mut self, #ident: I) -> Self
+                    where
+                        I: IntoIterator<Item = S>,
+                        S: Into<#elem>,
+                    {
+                        self.#ident = Some(#ident.into_iter().map(Into::into).collect());
+                        self
+                    }
+                };
+            }
             let doc = format!("Configure `{}` with the given value.", ident);
             quote! {
                 #[doc = #doc]
@@ -134,23 +190,27 @@ fn build_defaults<'a>(fields: &'a [FieldInfo]) -> impl Iterator<Item = TokenStre
              ref ident,
              ref ty_coarse,
              ref default,
+             required,
              ..
          }| {
-            if ty_coarse.is_number_or_bool() {
+            if *required {
+                // Presence is checked beforehand by `check_required`.
+                quote! {
+                    let #ident = self.#ident.unwrap();
+                }
+            } else if ty_coarse.is_number_or_bool() {
                 let Some(ref default) = default else {
                     unimplemented!("default value required for {}", ident);
                 };
                 // Below is a compromise of sorts. We can take a string literal
-                // if it parses to a valid path (i.e. we are pointing to a language item).
+                // if it parses to a valid path (i.e. we are pointing to a language item)
+                // or to a negative number, as the value of an attribute cannot
+                // be a negative literal.
                 match default {
                     syn::Lit::Str(s) => {
-                        match s.parse::<syn::Path>() {
-                            Ok(path) => quote! {
-                                let #ident = self.#ident.unwrap_or(#path);
-                            },
-                            Err(_) => {
-                                unimplemented!("String literal for number or boolean field must be a valid path expression")
-                            }
+                        let expr = number_default(s);
+                        quote! {
+                            let #ident = self.#ident.unwrap_or(#expr);
                         }
                     }
                     x => {
@@ -206,6 +266,99 @@ fn build_defaults<'a>(fields: &'a [FieldInfo]) -> impl Iterator<Item = TokenStre
         },
     )
 }
+/// Parse the string literal given as the default of a number or
+/// boolean field, which must be a path (e.g. `"crate::consts::TOL_OBJ"`)
+/// or a negated numeric literal (e.g. `"-1"`).
+fn number_default(s: &syn::LitStr) -> syn::Expr {
+    if let Ok(path) = s.parse::<syn::Path>() {
+        return syn::Expr::Path(syn::ExprPath {
+            attrs: Vec::new(),
+            qself: None,
+            path,
+        });
+    }
+    let is_negative_number = |expr: &syn::Expr| match expr {
+        syn::Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Neg(_),
+            expr,
+            ..
+        }) => matches!(
+            **expr,
+            syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Int(_) | syn::Lit::Float(_),
+                ..
+            })
+        ),
+        _ => false,
+    };
+    match s.parse::<syn::Expr>() {
+        Ok(expr) if is_negative_number(&expr) => expr,
+        _ => unimplemented!(
+            "String literal for number or boolean field must be a valid path expression or negative number"
+        ),
+    }
+}
+
+/// Return the statements which return an error naming the required
+/// fields which are unset, or `None` if no field is required.
+fn check_required(fields: &[FieldInfo]) -> Option<TokenStream> {
+    let checks: Vec<_> = fields
+        .iter()
+        .filter(|f| f.required)
+        .map(|FieldInfo { ref ident, .. }| {
+            let name = ident.to_string();
+            quote! {
+                if self.#ident.is_none() {
+                    missing.push(#name);
+                }
+            }
+        })
+        .collect();
+    if checks.is_empty() {
+        None
+    } else {
+        Some(quote! {
+            let mut missing: Vec<&'static str> = Vec::new();
+            #(#checks)*
+            if !missing.is_empty() {
+                return Err(crate::error::BuilderError::new(missing));
+            }
+        })
+    }
+}
+
+/// Return the build function, which is `try_build` if any field is
+/// required, and `build` otherwise.
+fn build_fn(fields: &[FieldInfo], name: &Ident, value: TokenStream, ty_name: &str) -> TokenStream {
+    let default_stmts = build_defaults(fields);
+    match check_required(fields) {
+        Some(check) => {
+            let doc = format!(
+                "Build the `{}` instance, or return an error naming the required options which are unspecified.",
+                ty_name
+            );
+            quote! {
+                #[doc = #doc]
+                pub fn try_build(self) -> Result<#name, crate::error::BuilderError> {
+                    #check
+                    #(#default_stmts)*
+                    Ok(#value)
+                }
+            }
+        }
+        None => {
+            let doc = format!("Build the `{}` instance.", ty_name);
+            quote! {
+                #[doc = #doc]
+                pub fn build(self) -> #name {
+                    #(#default_stmts)*
+                    #value
+                }
+            }
+        }
+    }
+}
+
 fn new_impl(fields: &[FieldInfo]) -> TokenStream {
     let idents_new = fields.iter().map(|FieldInfo { ref ident, .. }| {
         quote! {
@@ -222,6 +375,21 @@ fn new_impl(fields: &[FieldInfo]) -> TokenStream {
     }
 }
 
+fn reset_impl(fields: &[FieldInfo]) -> TokenStream {
+    let idents_reset = fields.iter().map(|FieldInfo { ref ident, .. }| {
+        quote! {
+            self.#ident = None;
+        }
+    });
+    quote! {
+        /// Unspecify all options, i.e. return the builder to the state
+        /// produced by `new`, in place.
+        pub fn reset(&mut self) {
+            #(#idents_reset)*
+        }
+    }
+}
+
 fn builder_doc<T: std::fmt::Display>(name: &T) -> String {
     format!("Options builder for [`{}`].\nFor any option left unspecified, the default value indicated on `{}` will be supplied.", name, name)
 }
@@ -243,11 +411,27 @@ fn derive_struct_impl(data: &syn::DataStruct, name: &Ident) -> TokenStream {
     let builder_name = format_ident!("{}Builder", name);
     let decls = builder_fields(&fields);
     let into_fns = into_fns(&fields);
-    let default_stmts = build_defaults(&fields);
     let idents = fields.iter().map(|FieldInfo { ref ident, .. }| ident);
     let builder_doc = builder_doc(name);
-    let build_doc = format!("Build the `{}` instance.", name);
+    let build_fn = build_fn(&fields, name, quote! { #name { #(#idents),* } }, &name.to_string());
     let new_imp = new_impl(&fields);
+    let reset_imp = reset_impl(&fields);
+    // Without a value for each required field, there is no conversion
+    // from the builder, nor a default.
+    let infallible = (!fields.iter().any(|f| f.required)).then(|| {
+        quote! {
+            impl From<#builder_name> for #name {
+                fn from(x: #builder_name) -> Self {
+                    x.build()
+                }
+            }
+            impl Default for #name {
+                fn default() -> Self {
+                    #builder_name::new().build()
+                }
+            }
+        }
+    });
     quote! {
         #[derive(Debug, Clone, PartialEq)]
         #[doc = #builder_doc]
@@ -257,31 +441,18 @@ fn derive_struct_impl(data: &syn::DataStruct, name: &Ident) -> TokenStream {
         impl #builder_name {
             #new_imp
 
+            #reset_imp
+
             #(#into_fns)*
 
-            #[doc = #build_doc]
-            pub fn build(self) -> #name {
-                #(#default_stmts)*
-                #name {
-                    #(#idents),*
-                }
-            }
-        }
-        impl From<#builder_name> for #name {
-            fn from(x: #builder_name) -> Self {
-                x.build()
-            }
+            #build_fn
         }
+        #infallible
         impl Default for #builder_name {
             fn default() -> Self {
                 Self::new()
             }
         }
-        impl Default for #name {
-            fn default() -> Self {
-                #builder_name::new().build()
-            }
-        }
         impl #name {
             /// Return a builder with all options unspecified.
             pub fn builder() -> #builder_name {
@@ -331,12 +502,26 @@ fn derive_variant_impl(var: &syn::Variant, name: &Ident) -> Option<TokenStream>
         let builder_name = format_ident!("{}Builder", var_name);
         let decls = builder_fields(&fields);
         let into_fns = into_fns(&fields);
-        let default_stmts = build_defaults(&fields);
         let idents = fields.iter().map(|FieldInfo { ref ident, .. }| ident);
         let ty_variant = format!("{}::{}", name, var_name);
         let builder_doc = builder_doc(&ty_variant);
-        let build_doc = format!("Build the `{}` instance.", ty_variant);
+        let build_fn = build_fn(
+            &fields,
+            name,
+            quote! { #name::#var_name { #(#idents),* } },
+            &ty_variant,
+        );
+        let infallible = (!fields.iter().any(|f| f.required)).then(|| {
+            quote! {
+                impl From<#builder_name> for #name {
+                    fn from(x: #builder_name) -> Self {
+                        x.build()
+                    }
+                }
+            }
+        });
         let new_imp = new_impl(&fields);
+        let reset_imp = reset_impl(&fields);
         Some(quote! {
             #[derive(Debug, Clone, PartialEq)]
             #[doc = #builder_doc]
@@ -346,21 +531,13 @@ fn derive_variant_impl(var: &syn::Variant, name: &Ident) -> Option<TokenStream>
             impl #builder_name {
                 #new_imp
 
+                #reset_imp
+
                 #(#into_fns)*
 
-                #[doc = #build_doc]
-                pub fn build(self) -> #name {
-                    #(#default_stmts)*
-                    #name::#var_name {
-                        #(#idents),*
-                    }
-                }
-            }
-            impl From<#builder_name> for #name {
-                fn from(x: #builder_name) -> Self {
-                    x.build()
-                }
+                #build_fn
             }
+            #infallible
             impl Default for #builder_name {
                 fn default() -> Self {
                     Self::new()
@@ -382,6 +559,24 @@ fn is_defaults_to(a: &Attribute) -> bool {
     a.meta.path().is_ident("defaults_to")
 }
 
+fn is_required(input: &[Attribute]) -> bool {
+    is_flag(input, "required")
+}
+
+/// Return `true` if the attribute `#[name]`, which takes no value, is present.
+fn is_flag(input: &[Attribute], name: &str) -> bool {
+    input.iter().any(|a| {
+        if is_outer(a) && a.meta.path().is_ident(name) {
+            match &a.meta {
+                Meta::Path(_) => true,
+                _ => unimplemented!("`{}` attribute takes no value.", name),
+            }
+        } else {
+            false
+        }
+    })
+}
+
 fn get_default(input: &[Attribute]) -> Option<syn::Lit> {
     let mut n: usize = 0;
     let defaults = input
@@ -407,3 +602,45 @@ fn get_default(input: &[Attribute]) -> Option<syn::Lit> {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn number_default_str(s: &str) -> String {
+        let lit = syn::LitStr::new(s, proc_macro2::Span::call_site());
+        let expr = number_default(&lit);
+        quote!(#expr).to_string()
+    }
+
+    #[test]
+    fn number_default_path() {
+        assert_eq!(
+            number_default_str("crate::consts::TOL_OBJ"),
+            "crate :: consts :: TOL_OBJ"
+        );
+        assert_eq!(
+            number_default_str("std::f64::consts::TAU"),
+            "std :: f64 :: consts :: TAU"
+        );
+    }
+
+    #[test]
+    fn number_default_negative() {
+        assert_eq!(number_default_str("-1"), "- 1");
+        assert_eq!(number_default_str("-0.5"), "- 0.5");
+        assert_eq!(number_default_str("-1i64"), "- 1i64");
+    }
+
+    #[test]
+    #[should_panic(expected = "must be a valid path expression or negative number")]
+    fn number_default_not_negative_literal() {
+        number_default_str("-x");
+    }
+
+    #[test]
+    #[should_panic(expected = "must be a valid path expression or negative number")]
+    fn number_default_not_expr() {
+        number_default_str("1 +");
+    }
+}
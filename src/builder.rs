@@ -4,4 +4,65 @@ pub use builder_derive::*;
 ///
 /// For `struct`s, deriving this trait automatically derives `Default`.
 /// For `enum`s, a manual implementation of `Default` is required.
+///
+/// A field marked `#[required]` has no default; if any field is
+/// required, the builder has `try_build`, which returns a
+/// [`BuilderError`](crate::error::BuilderError) naming the required
+/// fields which are unspecified, in place of `build`, and neither
+/// `From<builder>` nor `Default` is derived.
+// Only ever used as a marker, hence, never named as a bound.
+#[allow(dead_code)]
 pub(crate) trait Builder {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{BuilderError, Error, ErrorKind};
+    use std::ffi::OsString;
+
+    #[derive(Debug, PartialEq, Clone, Builder)]
+    struct Options {
+        #[required]
+        fitted_params: OsString,
+        #[required]
+        id: i32,
+        #[defaults_to = 1]
+        num_threads: i32,
+    }
+
+    #[derive(Debug, PartialEq, Clone, Builder)]
+    enum Choice {
+        Some {
+            #[required]
+            x: f64,
+        },
+    }
+
+    #[test]
+    fn required() {
+        let x = OptionsBuilder::new()
+            .fitted_params("output.csv")
+            .id(2)
+            .try_build()
+            .unwrap();
+        assert_eq!(x.fitted_params, "output.csv");
+        assert_eq!(x.id, 2);
+        assert_eq!(x.num_threads, 1);
+
+        let e = Options::builder().num_threads(4).try_build().unwrap_err();
+        assert_eq!(e.missing(), ["fitted_params", "id"]);
+        assert_eq!(
+            e.to_string(),
+            "required option(s) unspecified: `fitted_params`, `id`"
+        );
+        let e = Options::builder().id(1).try_build().unwrap_err();
+        assert_eq!(e, BuilderError::new(vec!["fitted_params"]));
+        assert_eq!(Error::from(e).kind(), ErrorKind::Validation);
+
+        assert_eq!(
+            SomeBuilder::new().x(0.5).try_build().unwrap(),
+            Choice::Some { x: 0.5 }
+        );
+        assert!(SomeBuilder::new().try_build().is_err());
+    }
+}
@@ -0,0 +1,228 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// Options for the compilation of a Stan program, which are passed to
+/// `make` as variables by [`CmdStan::compile`](crate::CmdStan::compile).
+/// See <https://mc-stan.org/docs/cmdstan-guide/parallelization.html>
+/// for more information.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[non_exhaustive]
+pub struct CompileOptions {
+    /// Compile with support for OpenCL (`STAN_OPENCL=true`), such that
+    /// the model may be run on a GPU; see [`ArgTree::with_opencl`](crate::ArgTree::with_opencl).
+    /// Default is `false`.
+    pub stan_opencl: bool,
+    /// The OpenCL device to use by default (`OPENCL_DEVICE_ID`).
+    /// Only meaningful if `stan_opencl`.
+    pub opencl_device_id: Option<u32>,
+    /// The OpenCL platform to use by default (`OPENCL_PLATFORM_ID`).
+    /// Only meaningful if `stan_opencl`.
+    pub opencl_platform_id: Option<u32>,
+}
+impl CompileOptions {
+    pub fn builder() -> CompileOptionsBuilder {
+        CompileOptionsBuilder::new()
+    }
+
+    pub fn command_fragment(&self) -> Vec<OsString> {
+        let mut v = Vec::with_capacity(3);
+        if self.stan_opencl {
+            v.push("STAN_OPENCL=true".into());
+        }
+        if let Some(id) = self.opencl_device_id {
+            v.push(format!("OPENCL_DEVICE_ID={}", id).into());
+        }
+        if let Some(id) = self.opencl_platform_id {
+            v.push(format!("OPENCL_PLATFORM_ID={}", id).into());
+        }
+        v
+    }
+}
+
+/// A warning emitted by stanc, e.g. for deprecated syntax or, if
+/// compiled with `STANCFLAGS=--warn-pedantic`, potential problems
+/// with the model.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StancWarning {
+    /// The file to which the warning refers, if given. The file is
+    /// parsed from the text of the warning, hence, a path which is not
+    /// valid UTF-8 is lossy, unless restored by [`StancWarning::restore_file`].
+    pub file: Option<PathBuf>,
+    /// The line and column (as reported by stanc) to which the
+    /// warning refers, if given.
+    pub position: Option<(usize, usize)>,
+    /// The message, with lines joined by spaces.
+    pub message: String,
+}
+impl StancWarning {
+    /// Parse the warnings in `text`, the stderr of stanc (or make),
+    /// ignoring anything which is not a warning. A warning begins
+    /// with a line of the form `Warning in 'file', line 1, column 2: ...`
+    /// or `Warning: ...`, and continues onto the subsequent indented lines.
+    pub fn parse_all(text: &str) -> Vec<Self> {
+        let mut warnings: Vec<Self> = Vec::new();
+        let mut continued = false;
+        for line in text.lines() {
+            if let Some(rest) = line.strip_prefix("Warning") {
+                let warning = rest
+                    .strip_prefix(" in '")
+                    .and_then(Self::parse_location)
+                    .unwrap_or_else(|| Self {
+                        file: None,
+                        position: None,
+                        message: rest.strip_prefix(':').unwrap_or(rest).trim().to_string(),
+                    });
+                warnings.push(warning);
+                continued = true;
+            } else if continued && line.starts_with(char::is_whitespace) && !line.trim().is_empty()
+            {
+                let x = warnings.last_mut().unwrap();
+                if !x.message.is_empty() {
+                    x.message.push(' ');
+                }
+                x.message.push_str(line.trim());
+            } else {
+                continued = false;
+            }
+        }
+        warnings
+    }
+
+    /// If the file of `self` is the lossy (UTF-8) form of `path`, e.g.
+    /// as parsed from stderr which was decoded lossily, replace it with
+    /// `path`. [`CmdStan::compile_with_warnings`](crate::CmdStan::compile_with_warnings)
+    /// does so for the path of the program.
+    pub fn restore_file(&mut self, path: &Path) {
+        if self.file.as_deref().and_then(Path::to_str) == Some(&*path.to_string_lossy()) {
+            self.file = Some(path.to_path_buf());
+        }
+    }
+
+    /// Parse `file', line 1, column 2: message`, where the position is optional.
+    fn parse_location(s: &str) -> Option<Self> {
+        let (file, rest) = s.split_once('\'')?;
+        let rest = rest.strip_prefix(',').unwrap_or(rest);
+        let (location, message) = rest.split_once(':')?;
+        let position = location.trim().strip_prefix("line ").and_then(|s| {
+            let (line, column) = s.split_once(", column ")?;
+            Some((line.trim().parse().ok()?, column.trim().parse().ok()?))
+        });
+        Some(Self {
+            file: Some(PathBuf::from(file)),
+            position,
+            message: message.trim().to_string(),
+        })
+    }
+}
+
+impl From<CompileOptionsBuilder> for CompileOptions {
+    fn from(x: CompileOptionsBuilder) -> Self {
+        x.build()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompileOptionsBuilder {
+    stan_opencl: Option<bool>,
+    opencl_device_id: Option<u32>,
+    opencl_platform_id: Option<u32>,
+}
+impl CompileOptionsBuilder {
+    insert_field!(stan_opencl, bool);
+    insert_field!(opencl_device_id, u32);
+    insert_field!(opencl_platform_id, u32);
+
+    pub fn new() -> Self {
+        Self {
+            stan_opencl: None,
+            opencl_device_id: None,
+            opencl_platform_id: None,
+        }
+    }
+    pub fn build(self) -> CompileOptions {
+        CompileOptions {
+            stan_opencl: self.stan_opencl.unwrap_or(false),
+            opencl_device_id: self.opencl_device_id,
+            opencl_platform_id: self.opencl_platform_id,
+        }
+    }
+}
+impl Default for CompileOptionsBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_fragment() {
+        assert!(CompileOptions::default().command_fragment().is_empty());
+        let x = CompileOptions::builder()
+            .stan_opencl(true)
+            .opencl_device_id(1)
+            .opencl_platform_id(0)
+            .build();
+        assert_eq!(
+            x.command_fragment(),
+            vec![
+                "STAN_OPENCL=true",
+                "OPENCL_DEVICE_ID=1",
+                "OPENCL_PLATFORM_ID=0"
+            ]
+        );
+        let x = CompileOptions::builder().opencl_device_id(2).build();
+        assert_eq!(x.command_fragment(), vec!["OPENCL_DEVICE_ID=2"]);
+    }
+
+    #[test]
+    fn parse_warnings() {
+        let text = "\
+--- Translating Stan model to C++ code ---
+bin/stanc  --o=model.hpp model.stan
+Warning in 'model.stan', line 3, column 2: Declaration of arrays by placing
+    brackets after a variable name is deprecated.
+Warning: The parameter theta has no priors.
+make: 'model' is up to date.
+";
+        assert_eq!(
+            StancWarning::parse_all(text),
+            vec![
+                StancWarning {
+                    file: Some(PathBuf::from("model.stan")),
+                    position: Some((3, 2)),
+                    message: "Declaration of arrays by placing brackets after a variable name is deprecated.".to_string(),
+                },
+                StancWarning {
+                    file: None,
+                    position: None,
+                    message: "The parameter theta has no priors.".to_string(),
+                },
+            ]
+        );
+        assert!(StancWarning::parse_all("").is_empty());
+        assert!(StancWarning::parse_all("   indented\n").is_empty());
+
+        let x = &StancWarning::parse_all("Warning in 'a b.stan': message\n")[0];
+        assert_eq!(x.file.as_deref(), Some(Path::new("a b.stan")));
+        assert_eq!(x.position, None);
+        assert_eq!(x.message, "message");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn restore_file() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+        let path = Path::new(OsStr::from_bytes(b"/models/\xffmodel.stan"));
+        let text = format!("Warning in '{}': message\n", path.to_string_lossy());
+        let mut x = StancWarning::parse_all(&text).remove(0);
+        assert_ne!(x.file.as_deref(), Some(path));
+        x.restore_file(Path::new("/models/other.stan"));
+        assert_ne!(x.file.as_deref(), Some(path));
+        x.restore_file(path);
+        assert_eq!(x.file.as_deref(), Some(path));
+    }
+}
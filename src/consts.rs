@@ -64,7 +64,9 @@ pub(crate) const TOL_PARAM: f64 = 1e-8;
 pub(crate) const HISTORY_SIZE: i32 = 5;
 
 // Used in multiple places
-pub(crate) const NEG1_I32: i32 = -1;
-pub(crate) const NEG1_I64: i64 = -1;
 pub(crate) const OUTPUT_FILE: &str = "output.csv";
 pub(crate) const PROFILE_FILE: &str = "profile.csv";
+
+// Defaults of `output`, which are also those of CmdStan
+pub(crate) const REFRESH: i32 = 100;
+pub(crate) const SIG_FIGS: i32 = -1;
@@ -1,6 +1,6 @@
 use crate::builder::Builder;
 use crate::translate::Translate;
-use std::ffi::OsString;
+use std::{ffi::OsString, fmt};
 
 /// Diagnostic test. Defaults to [`DiagnoseTest::Gradient`].
 #[derive(Debug, PartialEq, Clone, Translate, Builder)]
@@ -28,6 +28,16 @@ impl Default for DiagnoseTest {
     }
 }
 
+/// Emits the spelling of the variant, as it appears in the argument
+/// tree, e.g. `gradient`, without the options of the test.
+impl fmt::Display for DiagnoseTest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Gradient { .. } => "gradient",
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -42,6 +52,11 @@ mod tests {
         assert_eq!(x, y);
     }
 
+    #[test]
+    fn display() {
+        assert_eq!(DiagnoseTest::default().to_string(), "gradient");
+    }
+
     #[test]
     fn to_args() {
         let x = DiagnoseTest::default();
@@ -0,0 +1,1305 @@
+//! Reading of draws from Stan CSV files.
+//!
+//! A Stan CSV file consists of comment lines (beginning with `#`),
+//! which contain the configuration, adaptation information and timing,
+//! a single header line of column names, and one line of comma-separated
+//! values per draw. Comment lines may appear before, between, or after
+//! the draws.
+
+use crate::method::InvMetric;
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::Path,
+};
+
+/// Classification of a column by name. Stan reserves names which end
+/// in `__` for quantities produced by the algorithm (e.g. `lp__`,
+/// `accept_stat__`, `log_p__`), hence, classification does not depend
+/// upon the method, nor upon the presence of any particular column.
+/// For example, the output of `generate_quantities` may consist solely
+/// of the generated quantities.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColumnKind {
+    /// A column produced by the algorithm, e.g. `lp__` or `treedepth__`.
+    Algorithm,
+    /// A parameter, transformed parameter or generated quantity of the model.
+    Model,
+}
+
+impl ColumnKind {
+    /// Classify the column with the given `name`.
+    pub fn of(name: &str) -> Self {
+        if name.ends_with("__") {
+            Self::Algorithm
+        } else {
+            Self::Model
+        }
+    }
+}
+
+/// Draws stored column-wise, with one column per name in the header.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DrawsTable {
+    names: Vec<String>,
+    columns: Vec<Vec<f64>>,
+}
+
+pub(crate) fn invalid_data<S: Into<String>>(msg: S) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+/// Comment lines and blank lines carry no draws.
+fn is_skipped(line: &str) -> bool {
+    line.is_empty() || line.starts_with('#')
+}
+
+fn parse_header(line: &str) -> Vec<String> {
+    line.split(',').map(|s| s.trim().to_string()).collect()
+}
+
+/// Parse a row of `num_columns` values from `line`, which is the
+/// `line_no`-th line of the input (for the purpose of errors).
+fn parse_row(num_columns: usize, line_no: usize, line: &str) -> io::Result<Vec<f64>> {
+    let fields: Vec<&str> = line.split(',').collect();
+    if fields.len() != num_columns {
+        return Err(invalid_data(format!(
+            "line {}: expected {} fields, found {}",
+            line_no,
+            num_columns,
+            fields.len()
+        )));
+    }
+    fields
+        .into_iter()
+        .map(|field| {
+            field.trim().parse::<f64>().map_err(|_| {
+                invalid_data(format!(
+                    "line {}: cannot parse `{}` as a number",
+                    line_no, field
+                ))
+            })
+        })
+        .collect()
+}
+
+/// Parse each of `parts` as an index, else `None` if any is not an index.
+fn parse_indices<'a, I: Iterator<Item = &'a str>>(parts: I) -> Option<Vec<usize>> {
+    parts.map(|x| x.trim().parse::<usize>().ok()).collect()
+}
+
+impl DrawsTable {
+    /// Create an instance from the given column `names` and `columns`,
+    /// which must be of equal length, as must each column.
+    pub(crate) fn from_parts(names: Vec<String>, columns: Vec<Vec<f64>>) -> Self {
+        debug_assert_eq!(names.len(), columns.len());
+        Self { names, columns }
+    }
+
+    /// Read draws in the Stan CSV format from `reader`.
+    ///
+    /// An error of kind `InvalidData` is returned if there is no
+    /// header, if any row has a number of fields which differs from
+    /// the header, or if any field cannot be parsed as a number.
+    pub fn from_reader<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut iter = DrawsIter::new(reader)?;
+        let mut columns: Vec<Vec<f64>> = vec![Vec::new(); iter.names.len()];
+        for row in &mut iter {
+            for (value, column) in row?.into_iter().zip(columns.iter_mut()) {
+                column.push(value);
+            }
+        }
+        Ok(Self {
+            names: iter.names,
+            columns,
+        })
+    }
+
+    /// Read draws in the Stan CSV format from the file at `path`.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = File::open(path)?;
+        Self::from_reader(BufReader::new(file))
+    }
+
+    /// Append the draws of `other` to `self`. The column names must
+    /// be identical, else an error of kind `InvalidData` is returned
+    /// and `self` is unchanged.
+    pub fn append(&mut self, other: DrawsTable) -> io::Result<()> {
+        if self.names != other.names {
+            return Err(invalid_data("column names differ"));
+        }
+        for (lhs, rhs) in self.columns.iter_mut().zip(other.columns) {
+            lhs.extend(rhs);
+        }
+        Ok(())
+    }
+
+    /// Return the column names, in the order in which they appear in the header.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Return the names of the columns of the given `kind`, in the
+    /// order in which they appear in the header.
+    pub fn names_of_kind(&self, kind: ColumnKind) -> Vec<&str> {
+        self.names
+            .iter()
+            .map(|name| name.as_str())
+            .filter(|name| ColumnKind::of(name) == kind)
+            .collect()
+    }
+
+    /// Return the indices and column position of each element of the
+    /// array (or vector, matrix, etc.) named `base`, in the order in which
+    /// the columns appear in the header. Stan flattens arrays into columns
+    /// named by the indices separated by `.`, e.g. `theta.1.2`; the form
+    /// `theta[1,2]` is also recognized. Indices are as written, i.e. 1-based.
+    /// A scalar named `base` has no indices. Columns whose names otherwise
+    /// begin with `base` (e.g. `theta_raw.1`, or `z.real` for complex
+    /// numbers) are excluded.
+    pub fn array_columns(&self, base: &str) -> Vec<(Vec<usize>, usize)> {
+        self.names
+            .iter()
+            .enumerate()
+            .filter_map(|(i, name)| {
+                let rest = name.strip_prefix(base)?;
+                let indices = if rest.is_empty() {
+                    Vec::new()
+                } else if let Some(rest) = rest.strip_prefix('.') {
+                    parse_indices(rest.split('.'))?
+                } else {
+                    let rest = rest.strip_prefix('[')?.strip_suffix(']')?;
+                    parse_indices(rest.split(','))?
+                };
+                Some((indices, i))
+            })
+            .collect()
+    }
+
+    /// Return the number of draws, i.e. the number of rows.
+    pub fn num_draws(&self) -> usize {
+        self.columns.first().map_or(0, |column| column.len())
+    }
+
+    /// Return the number of columns.
+    pub fn num_columns(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Return a reference to the columns, in the order of `names`.
+    pub(crate) fn columns_ref(&self) -> &[Vec<f64>] {
+        &self.columns
+    }
+
+    /// Return the column of the given `name`, if present.
+    pub fn column(&self, name: &str) -> Option<&[f64]> {
+        self.names
+            .iter()
+            .position(|x| x == name)
+            .map(|i| self.columns[i].as_slice())
+    }
+
+    /// Return an iterator over the columns, as pairs of name and values,
+    /// in the order in which these appear in the header.
+    pub fn columns(&self) -> impl Iterator<Item = (&str, &[f64])> + '_ {
+        self.names
+            .iter()
+            .map(|name| name.as_str())
+            .zip(self.columns.iter().map(|column| column.as_slice()))
+    }
+
+    /// Return an iterator over the rows, i.e. the draws. As the draws
+    /// are stored column-wise, each row is copied into a `Vec`.
+    pub fn rows(&self) -> Rows<'_> {
+        Rows {
+            table: self,
+            row: 0,
+        }
+    }
+
+    /// Copy the draws into a matrix with one row per draw and one
+    /// column per name. Available with the `ndarray` feature.
+    #[cfg(feature = "ndarray")]
+    pub fn to_ndarray(&self) -> ndarray::Array2<f64> {
+        ndarray::Array2::from_shape_fn((self.num_draws(), self.num_columns()), |(i, j)| {
+            self.columns[j][i]
+        })
+    }
+
+    /// Return the column of the given `name`, else an error of kind
+    /// `InvalidData` if absent.
+    pub(crate) fn require_column(&self, name: &str) -> io::Result<&[f64]> {
+        self.column(name)
+            .ok_or_else(|| invalid_data(format!("column `{name}` not found")))
+    }
+
+    /// Return the number of divergent transitions, i.e. the number of
+    /// draws for which `divergent__` is non-zero. The column is only
+    /// present in the output of the HMC sampler; if absent (e.g. `fixed_param`,
+    /// or methods other than `sample`), an error of kind `InvalidData`
+    /// is returned.
+    pub fn divergences(&self) -> io::Result<usize> {
+        let column = self.require_column("divergent__")?;
+        Ok(column.iter().filter(|x| **x != 0.0).count())
+    }
+
+    /// Return the number of draws for which the NUTS tree depth,
+    /// `treedepth__`, reached `max_depth`, i.e. for which the trajectory
+    /// was terminated prematurely. An error of kind `InvalidData` is
+    /// returned if the column is absent.
+    ///
+    /// Every row of the table is counted, including any warmup draws
+    /// (i.e. if the call was made with `save_warmup`), which CmdStan's
+    /// `diagnose` would exclude. See also
+    /// [`CmdStanOutput::max_treedepth_hits`](crate::CmdStanOutput::max_treedepth_hits),
+    /// which uses the `max_depth` with which the call was made, and
+    /// excludes warmup draws.
+    pub fn max_treedepth_hits(&self, max_depth: i32) -> io::Result<usize> {
+        let column = self.require_column("treedepth__")?;
+        let max_depth = max_depth as f64;
+        Ok(column.iter().filter(|x| **x >= max_depth).count())
+    }
+
+    /// Return the mean number of leapfrog steps per draw, `n_leapfrog__`.
+    /// An error of kind `InvalidData` is returned if the column is absent.
+    /// If there are no draws, the mean is NaN.
+    pub fn mean_leapfrog(&self) -> io::Result<f64> {
+        let column = self.require_column("n_leapfrog__")?;
+        Ok(column.iter().sum::<f64>() / column.len() as f64)
+    }
+
+    /// Insert `column` before all others, under `name`. The column must
+    /// have one value per draw.
+    pub(crate) fn prepend_column(&mut self, name: &str, column: Vec<f64>) {
+        debug_assert_eq!(column.len(), self.num_draws());
+        self.names.insert(0, name.to_string());
+        self.columns.insert(0, column);
+    }
+}
+
+/** Export. */
+impl DrawsTable {
+    /// Write the draws in `format` to `path`; see [`ExportFormat`].
+    pub fn save_as<P: AsRef<Path>>(&self, format: ExportFormat, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        match format {
+            ExportFormat::Csv => {
+                let mut writer = BufWriter::new(File::create(path)?);
+                self.write_csv(&mut writer)?;
+                writer.flush()
+            }
+            ExportFormat::NpyDir => self.write_npy_dir(path),
+            #[cfg(feature = "parquet")]
+            ExportFormat::Parquet => self.write_parquet(path),
+            #[cfg(not(feature = "parquet"))]
+            ExportFormat::Parquet => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "export to Parquet requires the `parquet` feature",
+            )),
+        }
+    }
+
+    /// Write the draws to `writer` as CSV: a header of the column names,
+    /// then one line per draw. Values are written as the shortest
+    /// spelling which round-trips, with `nan`, `inf` and `-inf` spelled
+    /// as by Stan, hence, the result may be read by [`DrawsTable::from_reader`].
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "{}", self.names.join(","))?;
+        for row in self.rows() {
+            for (j, x) in row.iter().enumerate() {
+                if j != 0 {
+                    writer.write_all(b",")?;
+                }
+                if x.is_nan() {
+                    writer.write_all(b"nan")?;
+                } else {
+                    write!(writer, "{x}")?;
+                }
+            }
+            writer.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    /// Write each column to `{name}.npy` in the directory `dir`, which is
+    /// created if it does not exist, as a one-dimensional array of
+    /// little-endian `f64` in the NumPy format (version 1.0).
+    pub fn write_npy_dir<P: AsRef<Path>>(&self, dir: P) -> io::Result<()> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        for (name, column) in self.columns() {
+            let mut writer = BufWriter::new(File::create(dir.join(format!("{name}.npy")))?);
+            write_npy(&mut writer, column)?;
+            writer.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Write the draws to a Parquet file at `path`, with one required
+    /// `DOUBLE` column per name, in a single row group. Available with
+    /// the `parquet` feature.
+    #[cfg(feature = "parquet")]
+    pub fn write_parquet<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        use parquet::{
+            basic::{Repetition, Type as PhysicalType},
+            data_type::DoubleType,
+            file::{properties::WriterProperties, writer::SerializedFileWriter},
+            schema::types::Type,
+        };
+        use std::sync::Arc;
+
+        let fields = self
+            .names
+            .iter()
+            .map(|name| {
+                Type::primitive_type_builder(name, PhysicalType::DOUBLE)
+                    .with_repetition(Repetition::REQUIRED)
+                    .build()
+                    .map(Arc::new)
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(io::Error::other)?;
+        let schema = Type::group_type_builder("draws")
+            .with_fields(fields)
+            .build()
+            .map_err(io::Error::other)?;
+        let props = Arc::new(WriterProperties::builder().build());
+        let file = File::create(path)?;
+        let mut writer =
+            SerializedFileWriter::new(file, Arc::new(schema), props).map_err(io::Error::other)?;
+        let mut group = writer.next_row_group().map_err(io::Error::other)?;
+        for column in self.columns.iter() {
+            let mut col = group
+                .next_column()
+                .map_err(io::Error::other)?
+                .ok_or_else(|| io::Error::other("fewer columns in schema than in table"))?;
+            col.typed::<DoubleType>()
+                .write_batch(column, None, None)
+                .map_err(io::Error::other)?;
+            col.close().map_err(io::Error::other)?;
+        }
+        group.close().map_err(io::Error::other)?;
+        writer.close().map_err(io::Error::other)?;
+        Ok(())
+    }
+}
+
+/// Write `values` to `writer` as a one-dimensional `.npy` array.
+fn write_npy<W: Write>(writer: &mut W, values: &[f64]) -> io::Result<()> {
+    let mut header = format!(
+        "{{'descr': '<f8', 'fortran_order': False, 'shape': ({},), }}",
+        values.len()
+    );
+    // The magic string, version and length of the header occupy 10 bytes;
+    // the total must be a multiple of 64, including the terminating newline.
+    let len = 10 + header.len() + 1;
+    header.extend(std::iter::repeat_n(' ', len.next_multiple_of(64) - len));
+    header.push('\n');
+    writer.write_all(b"\x93NUMPY\x01\x00")?;
+    writer.write_all(&(header.len() as u16).to_le_bytes())?;
+    writer.write_all(header.as_bytes())?;
+    for x in values {
+        writer.write_all(&x.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// Format in which to export draws; see [`DrawsTable::save_as`] and
+/// [`CmdStanOutput::save_draws_as`](crate::CmdStanOutput::save_draws_as).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExportFormat {
+    /// A single CSV file; see [`DrawsTable::write_csv`].
+    Csv,
+    /// A directory of `.npy` files, one per column; see
+    /// [`DrawsTable::write_npy_dir`].
+    NpyDir,
+    /// A single Parquet file; see `DrawsTable::write_parquet`.
+    /// Requires the `parquet` feature, without which export fails
+    /// with an error of kind `Unsupported`.
+    Parquet,
+}
+
+/// Iterator over the rows of a [`DrawsTable`]; see [`DrawsTable::rows`].
+#[derive(Debug, Clone)]
+pub struct Rows<'a> {
+    table: &'a DrawsTable,
+    row: usize,
+}
+
+impl Iterator for Rows<'_> {
+    type Item = Vec<f64>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.row < self.table.num_draws() {
+            let row = self.table.columns.iter().map(|x| x[self.row]).collect();
+            self.row += 1;
+            Some(row)
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.table.num_draws() - self.row;
+        (n, Some(n))
+    }
+}
+
+impl ExactSizeIterator for Rows<'_> {}
+
+impl<'a> IntoIterator for &'a DrawsTable {
+    type Item = Vec<f64>;
+    type IntoIter = Rows<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.rows()
+    }
+}
+
+/// Iterator over the draws in the Stan CSV format, one row at a time,
+/// which does not hold more than a single row in memory. Each row holds
+/// one value per column, in the order of [`DrawsIter::names`]. Errors
+/// are as for [`DrawsTable::from_reader`].
+#[derive(Debug)]
+pub struct DrawsIter<R> {
+    lines: io::Lines<R>,
+    names: Vec<String>,
+    line_no: usize,
+}
+
+impl<R: BufRead> DrawsIter<R> {
+    /// Read from `reader` up to (and including) the header.
+    pub fn new(reader: R) -> io::Result<Self> {
+        let mut lines = reader.lines();
+        for (i, line) in (&mut lines).enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if is_skipped(line) {
+                continue;
+            }
+            return Ok(Self {
+                lines,
+                names: parse_header(line),
+                line_no: i + 1,
+            });
+        }
+        Err(invalid_data("no header found"))
+    }
+
+    /// Return the column names, in the order in which they appear in the header.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Consume the remaining rows, returning only the values of the
+    /// column `name`; the other fields of each row are not parsed, nor
+    /// is the number of fields checked beyond that which is necessary
+    /// to reach the column. An error of kind `InvalidData` is returned
+    /// if the column is absent, or a value cannot be parsed as a number.
+    pub fn column(mut self, name: &str) -> io::Result<Vec<f64>> {
+        let index = self
+            .names
+            .iter()
+            .position(|x| x == name)
+            .ok_or_else(|| invalid_data(format!("column `{name}` not found")))?;
+        let mut column = Vec::new();
+        for line in &mut self.lines {
+            self.line_no += 1;
+            let line = line?;
+            let line = line.trim();
+            if is_skipped(line) {
+                continue;
+            }
+            let field = line.split(',').nth(index).ok_or_else(|| {
+                invalid_data(format!(
+                    "line {}: expected {} fields, found fewer",
+                    self.line_no,
+                    self.names.len()
+                ))
+            })?;
+            let x = field.trim().parse::<f64>().map_err(|_| {
+                invalid_data(format!(
+                    "line {}: cannot parse `{}` as a number",
+                    self.line_no, field
+                ))
+            })?;
+            column.push(x);
+        }
+        Ok(column)
+    }
+}
+
+impl DrawsIter<BufReader<File>> {
+    /// Open the file at `path` and read up to (and including) the header.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = File::open(path)?;
+        Self::new(BufReader::new(file))
+    }
+}
+
+impl<R: BufRead> Iterator for DrawsIter<R> {
+    type Item = io::Result<Vec<f64>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for line in &mut self.lines {
+            self.line_no += 1;
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e)),
+            };
+            let line = line.trim();
+            if is_skipped(line) {
+                continue;
+            }
+            return Some(parse_row(self.names.len(), self.line_no, line));
+        }
+        None
+    }
+}
+
+/// Iterator over the draws in the Stan CSV format, as for [`DrawsIter`],
+/// which reads from a memory-mapped file. Lines are sliced from the
+/// mapped bytes, rather than copied into a buffer, which may be faster
+/// for very large files. Available with the `memmap2` feature.
+///
+/// The file must not be modified while mapped (e.g. by a call which
+/// is still running), else the behavior is undefined.
+#[cfg(feature = "memmap2")]
+#[derive(Debug)]
+pub struct MmapDrawsIter {
+    map: memmap2::Mmap,
+    pos: usize,
+    names: Vec<String>,
+    line_no: usize,
+}
+
+#[cfg(feature = "memmap2")]
+impl MmapDrawsIter {
+    /// Map the file at `path` and read up to (and including) the header.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: see the type-level documentation; the map is read-only
+        // and lives no longer than `self`.
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        let mut iter = Self {
+            map,
+            pos: 0,
+            names: Vec::new(),
+            line_no: 0,
+        };
+        while let Some((_, line)) = iter.next_line() {
+            let line = line?.trim();
+            if is_skipped(line) {
+                continue;
+            }
+            iter.names = parse_header(line);
+            return Ok(iter);
+        }
+        Err(invalid_data("no header found"))
+    }
+
+    /// Return the column names, in the order in which they appear in the header.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Slice the next line (without the terminator) from the map,
+    /// returning it with its line number.
+    fn next_line(&mut self) -> Option<(usize, io::Result<&str>)> {
+        let rest = &self.map[self.pos..];
+        if rest.is_empty() {
+            return None;
+        }
+        let (line, advance) = match rest.iter().position(|b| *b == b'\n') {
+            Some(i) => (&rest[..i], i + 1),
+            None => (rest, rest.len()),
+        };
+        self.pos += advance;
+        self.line_no += 1;
+        let line_no = self.line_no;
+        let line = std::str::from_utf8(line)
+            .map_err(|_| invalid_data(format!("line {line_no}: invalid UTF-8")));
+        Some((line_no, line))
+    }
+}
+
+#[cfg(feature = "memmap2")]
+impl Iterator for MmapDrawsIter {
+    type Item = io::Result<Vec<f64>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let num_columns = self.names.len();
+        while let Some((line_no, line)) = self.next_line() {
+            let line = match line {
+                Ok(line) => line.trim(),
+                Err(e) => return Some(Err(e)),
+            };
+            if is_skipped(line) {
+                continue;
+            }
+            return Some(parse_row(num_columns, line_no, line));
+        }
+        None
+    }
+}
+
+/// Per-column mean and variance of draws, computed in a single pass
+/// by Welford's algorithm, such that the draws need not be held in memory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DrawsStats {
+    names: Vec<String>,
+    num_draws: usize,
+    means: Vec<f64>,
+    // Sum of squared deviations from the running mean.
+    m2: Vec<f64>,
+}
+
+impl DrawsStats {
+    /// Consume the rows of `iter`, stopping at the first error.
+    // `FromIterator` cannot be implemented, as rows may be errors.
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_iter<R: BufRead>(mut iter: DrawsIter<R>) -> io::Result<Self> {
+        let n = iter.names.len();
+        let mut stats = Self {
+            names: Vec::new(),
+            num_draws: 0,
+            means: vec![0.0; n],
+            m2: vec![0.0; n],
+        };
+        for row in &mut iter {
+            stats.num_draws += 1;
+            let count = stats.num_draws as f64;
+            for ((x, mean), m2) in row?.into_iter().zip(&mut stats.means).zip(&mut stats.m2) {
+                let delta = x - *mean;
+                *mean += delta / count;
+                *m2 += delta * (x - *mean);
+            }
+        }
+        stats.names = iter.names;
+        Ok(stats)
+    }
+
+    /// Return the column names, in the order in which they appear in the header.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Return the number of draws.
+    pub fn num_draws(&self) -> usize {
+        self.num_draws
+    }
+
+    /// Return the mean of each column. If there are no draws, each is zero.
+    pub fn means(&self) -> &[f64] {
+        &self.means
+    }
+
+    /// Return the sample variance (with denominator `n - 1`) of each
+    /// column. If there are fewer than two draws, each is NaN.
+    pub fn variances(&self) -> Vec<f64> {
+        let denom = self.num_draws as f64 - 1.0;
+        self.m2
+            .iter()
+            .map(|m2| {
+                if self.num_draws < 2 {
+                    f64::NAN
+                } else {
+                    m2 / denom
+                }
+            })
+            .collect()
+    }
+}
+
+/// The output of the `log_prob` method: for each set of parameter values,
+/// the log density (`lp__`) and its gradient with respect to the
+/// unconstrained parameters, one component per remaining column.
+///
+/// CmdStan does not write the parameter values themselves, on either
+/// scale, hence, `log_prob` cannot be used to transform between the
+/// constrained and unconstrained scales; only the columns which CmdStan
+/// writes are exposed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogProbs {
+    gradient_names: Vec<String>,
+    lp: Vec<f64>,
+    gradients: Vec<Vec<f64>>,
+}
+
+impl LogProbs {
+    /// Extract the log density and gradients from `draws`, which must
+    /// have been read from the output of `log_prob`. An error of kind
+    /// `InvalidData` is returned if the `lp__` column is absent.
+    pub fn from_draws(draws: &DrawsTable) -> io::Result<Self> {
+        let lp = draws.require_column("lp__")?.to_vec();
+        let (gradient_names, columns): (Vec<_>, Vec<_>) = draws
+            .names
+            .iter()
+            .zip(draws.columns.iter())
+            .filter(|(name, _)| *name != "lp__")
+            .unzip();
+        let gradients = (0..lp.len())
+            .map(|i| columns.iter().map(|column| column[i]).collect())
+            .collect();
+        Ok(Self {
+            gradient_names: gradient_names.into_iter().cloned().collect(),
+            lp,
+            gradients,
+        })
+    }
+
+    /// Return the log density of each set of parameter values.
+    pub fn lp(&self) -> &[f64] {
+        &self.lp
+    }
+
+    /// Return the names of the components of the gradient, in the
+    /// order in which they appear in the header.
+    pub fn gradient_names(&self) -> &[String] {
+        &self.gradient_names
+    }
+
+    /// Return the gradient of each set of parameter values, in the
+    /// order of [`LogProbs::gradient_names`].
+    pub fn gradients(&self) -> &[Vec<f64>] {
+        &self.gradients
+    }
+
+    /// Return the number of sets of parameter values.
+    pub fn len(&self) -> usize {
+        self.lp.len()
+    }
+
+    /// Return `true` if there are no sets of parameter values.
+    pub fn is_empty(&self) -> bool {
+        self.lp.is_empty()
+    }
+}
+
+/// The step size and inverse metric adapted during warmup by a single
+/// chain of `sample` using HMC, as recorded in the comments of its
+/// output file, e.g.
+///
+/// ```text
+/// # Adaptation terminated
+/// # Step size = 0.932037
+/// # Diagonal elements of inverse mass matrix:
+/// # 0.591014, 1.20335
+/// ```
+///
+/// for which a dense metric has the heading `Elements of inverse mass
+/// matrix:` followed by one comment line per row. Either may continue
+/// over any number of comment lines: the elements are read until the
+/// first line which is not a row of values, and a dense metric is
+/// reshaped into a square matrix by the total number of elements.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Adaptation {
+    pub stepsize: f64,
+    pub inv_metric: InvMetric,
+}
+
+/// Parse the comma-separated values of a comment line, if it consists
+/// solely of such values. A trailing comma, as at the end of a row
+/// which continues on the next line, is permitted.
+fn parse_comment_row(comment: &str) -> Option<Vec<f64>> {
+    let comment = comment.strip_suffix(',').unwrap_or(comment);
+    comment
+        .split(',')
+        .map(|s| s.trim().parse::<f64>().ok())
+        .collect()
+}
+
+impl Adaptation {
+    /// Read the adaptation from a Stan CSV file. An error of kind
+    /// `InvalidData` is returned if the step size or inverse metric is
+    /// absent, e.g. if adaptation was not engaged.
+    pub fn from_reader<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut stepsize = None;
+        // `Some(diag)` once the heading of the inverse metric is seen.
+        let mut heading: Option<bool> = None;
+        let mut elements: Vec<f64> = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            let comment = line.strip_prefix('#').map(str::trim);
+            if heading.is_some() {
+                match comment.and_then(parse_comment_row) {
+                    Some(row) => elements.extend(row),
+                    None => break,
+                }
+            } else if let Some(comment) = comment {
+                if let Some(x) = comment.strip_prefix("Step size =") {
+                    let x = x
+                        .trim()
+                        .parse::<f64>()
+                        .map_err(|e| invalid_data(format!("invalid step size: {e}")))?;
+                    stepsize = Some(x);
+                } else if comment == "Diagonal elements of inverse mass matrix:" {
+                    heading = Some(true);
+                } else if comment == "Elements of inverse mass matrix:" {
+                    heading = Some(false);
+                }
+            }
+        }
+        let stepsize = stepsize.ok_or_else(|| invalid_data("step size not found"))?;
+        let inv_metric = match heading {
+            _ if elements.is_empty() => return Err(invalid_data("inverse metric not found")),
+            Some(true) => InvMetric::Diag(elements),
+            _ => {
+                let n = elements.len().isqrt();
+                if n * n != elements.len() {
+                    return Err(invalid_data(format!(
+                        "dense inverse metric has {} elements, which is not a square",
+                        elements.len()
+                    )));
+                }
+                InvMetric::Dense(elements.chunks(n).map(<[f64]>::to_vec).collect())
+            }
+        };
+        Ok(Self {
+            stepsize,
+            inv_metric,
+        })
+    }
+
+    /// Read the adaptation from the Stan CSV file at `path`.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static BERNOULLI_CSV: &str = "# model = bernoulli_model
+# method = sample (Default)
+lp__,accept_stat__,stepsize__,treedepth__,n_leapfrog__,divergent__,energy__,theta
+# Adaptation terminated
+# Step size = 0.932037
+# Diagonal elements of inverse mass matrix:
+# 0.591014
+-6.74827,1,0.932037,1,1,0,6.82685,0.242
+-6.80877,0.98608,0.932037,1,3,0,6.83513,0.2
+-7.01291,0.958047,0.932037,2,3,0,7.17054,0.3
+#
+#  Elapsed Time: 0.005 seconds (Warm-up)
+";
+
+    #[test]
+    fn from_reader() {
+        let x = DrawsTable::from_reader(BERNOULLI_CSV.as_bytes()).unwrap();
+        assert_eq!(
+            x.names(),
+            [
+                "lp__",
+                "accept_stat__",
+                "stepsize__",
+                "treedepth__",
+                "n_leapfrog__",
+                "divergent__",
+                "energy__",
+                "theta"
+            ]
+        );
+        assert_eq!(x.num_draws(), 3);
+        assert_eq!(x.num_columns(), 8);
+        assert_eq!(x.columns_ref()[7], vec![0.242, 0.2, 0.3]);
+        assert_eq!(x.columns_ref()[0], vec![-6.74827, -6.80877, -7.01291]);
+
+        let x = DrawsTable::from_reader("a,b\n1,nan\n-inf,2\n".as_bytes()).unwrap();
+        assert!(x.columns_ref()[1][0].is_nan());
+        assert_eq!(x.columns_ref()[0][1], f64::NEG_INFINITY);
+
+        // Header, but no draws.
+        let x = DrawsTable::from_reader("# comment\na,b\n".as_bytes()).unwrap();
+        assert_eq!(x.num_draws(), 0);
+        assert_eq!(x.num_columns(), 2);
+    }
+
+    #[test]
+    fn generate_quantities() {
+        // Older versions of CmdStan write only the generated quantities.
+        let s = "# method = generate_quantities
+#   generate_quantities
+#     fitted_params = output.csv
+y_rep.1,y_rep.2,log_lik.1
+0,1,-0.2
+1,1,-1.7
+";
+        let x = DrawsTable::from_reader(s.as_bytes()).unwrap();
+        assert_eq!(x.names(), ["y_rep.1", "y_rep.2", "log_lik.1"]);
+        assert_eq!(x.num_draws(), 2);
+        assert_eq!(x.columns_ref()[2], vec![-0.2, -1.7]);
+        assert!(x.names_of_kind(ColumnKind::Algorithm).is_empty());
+        assert_eq!(
+            x.names_of_kind(ColumnKind::Model),
+            ["y_rep.1", "y_rep.2", "log_lik.1"]
+        );
+
+        // Later versions also write the draws of `fitted_params`.
+        let s = "lp__,accept_stat__,theta,y_rep.1
+-6.7,0.9,0.24,1
+";
+        let x = DrawsTable::from_reader(s.as_bytes()).unwrap();
+        assert_eq!(
+            x.names_of_kind(ColumnKind::Algorithm),
+            ["lp__", "accept_stat__"]
+        );
+        assert_eq!(x.names_of_kind(ColumnKind::Model), ["theta", "y_rep.1"]);
+    }
+
+    #[test]
+    fn array_columns() {
+        let s = "lp__,theta,theta_raw.1,z.1.1,z.2.1,z.1.2,z.2.2,w.real,y[1],y[2],y,x.10\n";
+        let x = DrawsTable::from_reader(s.as_bytes()).unwrap();
+        assert_eq!(
+            x.array_columns("z"),
+            vec![
+                (vec![1, 1], 3),
+                (vec![2, 1], 4),
+                (vec![1, 2], 5),
+                (vec![2, 2], 6)
+            ]
+        );
+        assert_eq!(x.array_columns("theta"), vec![(vec![], 1)]);
+        assert_eq!(x.array_columns("theta_raw"), vec![(vec![1], 2)]);
+        assert_eq!(
+            x.array_columns("y"),
+            vec![(vec![1], 8), (vec![2], 9), (vec![], 10)]
+        );
+        assert_eq!(x.array_columns("x"), vec![(vec![10], 11)]);
+        assert!(x.array_columns("w").is_empty());
+        assert!(x.array_columns("missing").is_empty());
+
+        let names = vec!["m[1, 2]".to_string(), "m[2,2]".to_string()];
+        let x = DrawsTable::from_parts(names, vec![Vec::new(); 2]);
+        assert_eq!(x.array_columns("m"), vec![(vec![1, 2], 0), (vec![2, 2], 1)]);
+    }
+
+    #[test]
+    fn column_kind() {
+        assert_eq!(ColumnKind::of("lp__"), ColumnKind::Algorithm);
+        assert_eq!(ColumnKind::of("log_p__"), ColumnKind::Algorithm);
+        assert_eq!(ColumnKind::of("lp_approx__"), ColumnKind::Algorithm);
+        assert_eq!(ColumnKind::of("theta"), ColumnKind::Model);
+        assert_eq!(ColumnKind::of("theta_"), ColumnKind::Model);
+        assert_eq!(ColumnKind::of("z.1.2"), ColumnKind::Model);
+    }
+
+    #[test]
+    fn divergences() {
+        let x = DrawsTable::from_reader(BERNOULLI_CSV.as_bytes()).unwrap();
+        assert_eq!(x.divergences().unwrap(), 0);
+
+        let s = "lp__,divergent__,theta\n-7,1,0.2\n-7,0,0.3\n-8,1,0.1\n";
+        let x = DrawsTable::from_reader(s.as_bytes()).unwrap();
+        assert_eq!(x.divergences().unwrap(), 2);
+
+        let x = DrawsTable::from_reader("lp__,theta\n0,0.2\n".as_bytes()).unwrap();
+        let e = x.divergences().unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        assert!(e.to_string().contains("divergent__"));
+    }
+
+    #[test]
+    fn treedepth_and_leapfrog() {
+        let x = DrawsTable::from_reader(BERNOULLI_CSV.as_bytes()).unwrap();
+        assert_eq!(x.max_treedepth_hits(10).unwrap(), 0);
+        assert_eq!(x.max_treedepth_hits(2).unwrap(), 1);
+        assert_eq!(x.max_treedepth_hits(1).unwrap(), 3);
+        assert!((x.mean_leapfrog().unwrap() - 7.0 / 3.0).abs() < 1e-12);
+
+        let x = DrawsTable::from_reader("lp__,theta\n0,0.2\n".as_bytes()).unwrap();
+        assert!(x.max_treedepth_hits(10).is_err());
+        assert!(x.mean_leapfrog().is_err());
+
+        let x = DrawsTable::from_reader("n_leapfrog__\n".as_bytes()).unwrap();
+        assert!(x.mean_leapfrog().unwrap().is_nan());
+    }
+
+    #[test]
+    fn columns_and_rows() {
+        let x = DrawsTable::from_reader("lp__,theta\n-7,0.2\n-6,0.3\n".as_bytes()).unwrap();
+        assert_eq!(x.column("theta"), Some(&[0.2, 0.3][..]));
+        assert_eq!(x.column("mu"), None);
+        assert_eq!(
+            x.columns().collect::<Vec<_>>(),
+            vec![("lp__", &[-7.0, -6.0][..]), ("theta", &[0.2, 0.3][..])]
+        );
+        assert_eq!(x.rows().len(), 2);
+        let rows: Vec<_> = x.into_iter().collect();
+        assert_eq!(rows, vec![vec![-7.0, 0.2], vec![-6.0, 0.3]]);
+        let mut n = 0;
+        for row in &x {
+            assert_eq!(row.len(), 2);
+            n += 1;
+        }
+        assert_eq!(n, 2);
+
+        let x = DrawsTable::from_reader("lp__,theta\n".as_bytes()).unwrap();
+        assert_eq!(x.rows().next(), None);
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn to_ndarray() {
+        let x = DrawsTable::from_reader(BERNOULLI_CSV.as_bytes()).unwrap();
+        let a = x.to_ndarray();
+        assert_eq!(a.dim(), (3, 8));
+        assert_eq!(a[[0, 0]], -6.74827);
+        assert_eq!(a[[2, 7]], 0.3);
+    }
+
+    #[test]
+    fn crlf() {
+        let csv = BERNOULLI_CSV.replace('\n', "\r\n");
+        let lhs = DrawsTable::from_reader(csv.as_bytes()).unwrap();
+        let rhs = DrawsTable::from_reader(BERNOULLI_CSV.as_bytes()).unwrap();
+        assert_eq!(lhs, rhs);
+        assert_eq!(lhs.names()[7], "theta");
+    }
+
+    #[test]
+    fn draws_iter() {
+        let mut iter = DrawsIter::new(BERNOULLI_CSV.as_bytes()).unwrap();
+        assert_eq!(iter.names().len(), 8);
+        let row = iter.next().unwrap().unwrap();
+        assert_eq!(row[0], -6.74827);
+        assert_eq!(row[7], 0.242);
+        assert_eq!(iter.count(), 2);
+
+        // Line numbers count comments and the header.
+        let mut iter = DrawsIter::new("# c\na,b\n1,2\n\n1,x\n".as_bytes()).unwrap();
+        assert_eq!(iter.next().unwrap().unwrap(), vec![1.0, 2.0]);
+        let e = iter.next().unwrap().unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        assert!(e.to_string().starts_with("line 5:"));
+        assert!(DrawsIter::new("# c\n".as_bytes()).is_err());
+    }
+
+    #[cfg(feature = "memmap2")]
+    #[test]
+    fn mmap_draws_iter() {
+        let dir = crate::test_support::temp_dir();
+        let path = dir.join("output.csv");
+        std::fs::write(&path, BERNOULLI_CSV.replace('\n', "\r\n")).unwrap();
+        let iter = MmapDrawsIter::from_path(&path).unwrap();
+        assert_eq!(iter.names().len(), 8);
+        let rows: Vec<Vec<f64>> = iter.collect::<io::Result<_>>().unwrap();
+        let expected: Vec<Vec<f64>> = DrawsIter::new(BERNOULLI_CSV.as_bytes())
+            .unwrap()
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(rows, expected);
+
+        std::fs::write(&path, "a,b\n1,2\n3").unwrap();
+        let e = MmapDrawsIter::from_path(&path)
+            .unwrap()
+            .nth(1)
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(e.to_string(), "line 3: expected 2 fields, found 1");
+
+        std::fs::write(&path, "").unwrap();
+        assert!(MmapDrawsIter::from_path(&path).is_err());
+    }
+
+    #[test]
+    fn draws_stats() {
+        let s = "a,b,c\n1,-2,1e8\n2,0.5,1e8\n4,3,1e8\n7,-1,1.00000001e8\n";
+        let stats = DrawsStats::from_iter(DrawsIter::new(s.as_bytes()).unwrap()).unwrap();
+        let x = DrawsTable::from_reader(s.as_bytes()).unwrap();
+        assert_eq!(stats.names(), x.names());
+        assert_eq!(stats.num_draws(), 4);
+        let variances = stats.variances();
+        for (i, column) in x.columns_ref().iter().enumerate() {
+            let n = column.len() as f64;
+            let mean = column.iter().sum::<f64>() / n;
+            let var = column.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0);
+            assert!((stats.means()[i] - mean).abs() <= 1e-12 * mean.abs().max(1.0));
+            assert!((variances[i] - var).abs() <= 1e-9 * var.max(1.0));
+        }
+        assert_eq!(stats.means()[0], 3.5);
+        assert_eq!(variances[0], 7.0);
+
+        let stats = DrawsStats::from_iter(DrawsIter::new("a\n1\n".as_bytes()).unwrap()).unwrap();
+        assert_eq!(stats.means(), [1.0]);
+        assert!(stats.variances()[0].is_nan());
+
+        let iter = DrawsIter::new("a,b\n1,2\n3\n".as_bytes()).unwrap();
+        assert!(DrawsStats::from_iter(iter).is_err());
+    }
+
+    #[test]
+    fn log_probs() {
+        let s = "# method = log_prob
+lp__,g_1,g_2
+-3.5,0.25,-1
+-4,0.5,2
+";
+        let draws = DrawsTable::from_reader(s.as_bytes()).unwrap();
+        let x = LogProbs::from_draws(&draws).unwrap();
+        assert_eq!(x.len(), 2);
+        assert_eq!(x.lp(), [-3.5, -4.0]);
+        assert_eq!(x.gradient_names(), ["g_1", "g_2"]);
+        assert_eq!(x.gradients(), [vec![0.25, -1.0], vec![0.5, 2.0]]);
+
+        let draws = DrawsTable::from_reader("lp__\n".as_bytes()).unwrap();
+        assert!(LogProbs::from_draws(&draws).unwrap().is_empty());
+
+        let draws = DrawsTable::from_reader("a,b\n1,2\n".as_bytes()).unwrap();
+        let e = LogProbs::from_draws(&draws).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn from_reader_err() {
+        let f = |s: &str| DrawsTable::from_reader(s.as_bytes()).unwrap_err().kind();
+        assert_eq!(f(""), io::ErrorKind::InvalidData);
+        assert_eq!(f("# only comments\n"), io::ErrorKind::InvalidData);
+        assert_eq!(f("a,b\n1\n"), io::ErrorKind::InvalidData);
+        assert_eq!(f("a,b\n1,2,3\n"), io::ErrorKind::InvalidData);
+        assert_eq!(f("a,b\n1,x\n"), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn append() {
+        let mut x = DrawsTable::from_reader("a,b\n1,2\n".as_bytes()).unwrap();
+        let y = DrawsTable::from_reader("a,b\n3,4\n5,6\n".as_bytes()).unwrap();
+        x.append(y).unwrap();
+        assert_eq!(x.num_draws(), 3);
+        assert_eq!(x.columns_ref()[1], vec![2.0, 4.0, 6.0]);
+
+        let z = DrawsTable::from_reader("b,a\n3,4\n".as_bytes()).unwrap();
+        assert!(x.append(z).is_err());
+        assert_eq!(x.num_draws(), 3);
+    }
+
+    #[test]
+    fn draws_iter_column() {
+        let iter = DrawsIter::new(BERNOULLI_CSV.as_bytes()).unwrap();
+        assert_eq!(iter.column("lp__").unwrap(), [-6.74827, -6.80877, -7.01291]);
+        let iter = DrawsIter::new(BERNOULLI_CSV.as_bytes()).unwrap();
+        let e = iter.column("x").unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+
+        // Fields beyond the column are not parsed.
+        let iter = DrawsIter::new("a,b\n1,x\n2\n".as_bytes()).unwrap();
+        assert_eq!(iter.column("a").unwrap(), [1.0, 2.0]);
+        let iter = DrawsIter::new("a,b\n1,x\n2\n".as_bytes()).unwrap();
+        let e = iter.column("b").unwrap_err();
+        assert!(e.to_string().starts_with("line 2:"));
+    }
+
+    #[test]
+    fn write_csv() {
+        let s = "a,b\n1,nan\n-inf,0.25\n";
+        let x = DrawsTable::from_reader(s.as_bytes()).unwrap();
+        let mut buf = Vec::new();
+        x.write_csv(&mut buf).unwrap();
+        assert_eq!(String::from_utf8(buf.clone()).unwrap(), s);
+        let y = DrawsTable::from_reader(&buf[..]).unwrap();
+        assert_eq!(y.names(), x.names());
+        assert_eq!(y.column("a"), x.column("a"));
+    }
+
+    #[test]
+    fn write_npy_dir() {
+        let dir = crate::test_support::temp_dir();
+        let x = DrawsTable::from_reader("lp__,theta.1\n-7,0.5\n-8,0.25\n".as_bytes()).unwrap();
+        x.save_as(ExportFormat::NpyDir, dir.join("npy")).unwrap();
+        let bytes = fs::read(dir.join("npy").join("theta.1.npy")).unwrap();
+        assert_eq!(&bytes[..8], b"\x93NUMPY\x01\x00");
+        let header_len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+        assert_eq!((10 + header_len) % 64, 0);
+        let header = std::str::from_utf8(&bytes[10..10 + header_len]).unwrap();
+        assert!(header.starts_with("{'descr': '<f8', 'fortran_order': False, 'shape': (2,), }"));
+        assert!(header.ends_with('\n'));
+        let values: Vec<f64> = bytes[10 + header_len..]
+            .chunks(8)
+            .map(|b| f64::from_le_bytes(b.try_into().unwrap()))
+            .collect();
+        assert_eq!(values, [0.5, 0.25]);
+        assert!(dir.join("npy").join("lp__.npy").is_file());
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn write_parquet() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+        let dir = crate::test_support::temp_dir();
+        let path = dir.join("draws.parquet");
+        let x =
+            DrawsTable::from_reader("lp__,theta\n-7,0.5\n-8,0.25\n-9,0.1\n".as_bytes()).unwrap();
+        x.save_as(ExportFormat::Parquet, &path).unwrap();
+        let reader = SerializedFileReader::new(File::open(&path).unwrap()).unwrap();
+        let metadata = reader.metadata().file_metadata();
+        assert_eq!(metadata.num_rows(), 3);
+        let names: Vec<&str> = metadata
+            .schema_descr()
+            .columns()
+            .iter()
+            .map(|c| c.name())
+            .collect();
+        assert_eq!(names, ["lp__", "theta"]);
+    }
+
+    #[cfg(not(feature = "parquet"))]
+    #[test]
+    fn write_parquet_unsupported() {
+        let x = DrawsTable::from_reader("lp__\n-7\n".as_bytes()).unwrap();
+        let path = std::env::temp_dir().join("cmdstan-rs-unsupported.parquet");
+        let e = x.save_as(ExportFormat::Parquet, &path).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::Unsupported);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn adaptation() {
+        let x = Adaptation::from_reader(BERNOULLI_CSV.as_bytes()).unwrap();
+        assert_eq!(x.stepsize, 0.932037);
+        assert_eq!(x.inv_metric, InvMetric::Diag(vec![0.591014]));
+
+        let s = "a,b
+# Adaptation terminated
+# Step size = 0.5
+# Elements of inverse mass matrix:
+# 1, 0.25
+# 0.25, 2
+1,2
+";
+        let x = Adaptation::from_reader(s.as_bytes()).unwrap();
+        assert_eq!(x.stepsize, 0.5);
+        assert_eq!(
+            x.inv_metric,
+            InvMetric::Dense(vec![vec![1.0, 0.25], vec![0.25, 2.0]])
+        );
+
+        // A dense metric of arbitrary dimension, with rows which wrap.
+        let s = "# Step size = 0.25
+# Elements of inverse mass matrix:
+# 1, 0.1, 0.2,
+# 0.3
+# 0.1, 2, 0.4, 0.5
+# 0.2, 0.4, 3, 0.6
+# 0.3, 0.5, 0.6, 4
+#
+#  Elapsed Time: 0.005 seconds (Warm-up)
+";
+        let x = Adaptation::from_reader(s.as_bytes()).unwrap();
+        let InvMetric::Dense(rows) = x.inv_metric else {
+            panic!("expected a dense metric");
+        };
+        assert_eq!(rows.len(), 4);
+        assert!(rows.iter().all(|row| row.len() == 4));
+        assert_eq!(rows[0], [1.0, 0.1, 0.2, 0.3]);
+        assert_eq!(rows[3], [0.3, 0.5, 0.6, 4.0]);
+
+        let f = |s: &str| Adaptation::from_reader(s.as_bytes()).unwrap_err().kind();
+        let s = "# Step size = 1\n# Elements of inverse mass matrix:\n# 1, 0\n# 0\na\n";
+        assert_eq!(f(s), io::ErrorKind::InvalidData);
+        assert_eq!(f("a,b\n1,2\n"), io::ErrorKind::InvalidData);
+        assert_eq!(f("# Step size = 1\na\n1\n"), io::ErrorKind::InvalidData);
+        assert_eq!(f("# Step size = x\n"), io::ErrorKind::InvalidData);
+    }
+}
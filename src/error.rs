@@ -13,6 +13,30 @@ impl Error {
     pub(crate) fn new(kind: ErrorKind, repr: Repr) -> Self {
         Self { kind, repr }
     }
+    /// Error for an argument which CmdStan would reject; `msg`
+    /// should identify the argument and the permissible values.
+    pub(crate) fn validation<S: Into<String>>(msg: S) -> Self {
+        Self::new(
+            ErrorKind::Validation,
+            io::Error::new(io::ErrorKind::InvalidInput, msg.into()).into(),
+        )
+    }
+    /// Validation error, unless `ok`, of the form
+    /// "`name` must be `constraint`, got `value`".
+    pub(crate) fn require<T: fmt::Display>(
+        ok: bool,
+        name: &str,
+        constraint: &str,
+        value: T,
+    ) -> Result<(), Self> {
+        if ok {
+            Ok(())
+        } else {
+            Err(Self::validation(format!(
+                "{name} must be {constraint}, got {value}"
+            )))
+        }
+    }
 
     /// Check `output`: if the process exited successfully, then
     /// an ok result should contain the needle for the respective `kind`.
@@ -21,21 +45,39 @@ impl Error {
         output: process::Output,
         // needle: &'static str,
     ) -> Result<(), Self> {
-        if output.status.success() {
-            let stdout = String::from_utf8_lossy(&output.stdout[..]);
-            if !stdout.contains(kind.needle()) {
-                Err(Self::new(kind, output.into()))
-            } else {
-                Ok(())
-            }
+        let output = Self::ensure_success(kind, output)?;
+        let stdout = String::from_utf8_lossy(&output.stdout[..]);
+        if !stdout.contains(kind.needle()) {
+            Err(Self::new(kind, output.into()))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Check `output`: the process must have exited successfully,
+    /// in which case `output` is returned.
+    pub(crate) fn ensure_success(
+        kind: ErrorKind,
+        output: process::Output,
+    ) -> Result<process::Output, Self> {
+        if RunOutcome::classify(&output).is_ok() {
+            Ok(output)
         } else {
             Err(Self::new(kind, output.into()))
         }
     }
 }
+/// The kind of the error is displayed, as the detail is the
+/// [`source`](error::Error::source), which reporters such as `anyhow`
+/// display in turn. The alternate form (`{:#}`) also displays the
+/// detail, e.g. `make: process exit status not zero: 2`.
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}: {}", self.kind.as_str(), &self.repr)
+        f.write_str(self.kind.as_str())?;
+        if f.alternate() {
+            write!(f, ": {}", &self.repr)?;
+        }
+        Ok(())
     }
 }
 
@@ -43,11 +85,111 @@ impl error::Error for Error {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match &self.repr {
             Repr::Io(e) => Some(e),
-            Repr::UnsuccessfulExit(_) => None,
+            Repr::UnsuccessfulExit(e) => Some(e),
+        }
+    }
+}
+
+impl Error {
+    /// Return the output of the process, if the error arose from a process
+    /// which exited unsuccessfully (or did not produce the expected output).
+    /// Equivalent to downcasting the [`source`](error::Error::source) to [`ExitError`].
+    pub fn output(&self) -> Option<&process::Output> {
+        match &self.repr {
+            Repr::Io(_) => None,
+            Repr::UnsuccessfulExit(e) => Some(e.output()),
+        }
+    }
+}
+
+/// The source of an [`Error`] which arose from a process which exited
+/// unsuccessfully, or which exited successfully but did not produce
+/// the expected output.
+pub struct ExitError {
+    output: process::Output,
+}
+
+impl ExitError {
+    /// Return a reference to the output of the process.
+    pub fn output(&self) -> &process::Output {
+        &self.output
+    }
+    /// Return the classification of the output of the process.
+    pub fn outcome(&self) -> RunOutcome {
+        RunOutcome::classify(&self.output)
+    }
+}
+
+impl fmt::Display for ExitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.outcome() {
+            // The process exited successfully, but did not produce the expected output.
+            RunOutcome::Ok => f.write_str("process output not as expected"),
+            outcome => fmt::Display::fmt(&outcome, f),
         }
     }
 }
 
+impl fmt::Debug for ExitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UnsuccessfulExit")
+            .field("stdout", &String::from_utf8_lossy(&self.output.stdout[..]))
+            .field("stderr", &String::from_utf8_lossy(&self.output.stderr[..]))
+            .finish()
+    }
+}
+
+impl error::Error for ExitError {}
+
+/// Error returned by the `try_build` method of the builder of a type
+/// which has required options, i.e. options without a default.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BuilderError {
+    missing: Vec<&'static str>,
+}
+
+impl BuilderError {
+    // Only called by builders derived with `#[required]` fields.
+    #[allow(dead_code)]
+    pub(crate) fn new(missing: Vec<&'static str>) -> Self {
+        Self { missing }
+    }
+    /// Return the names of the required options which were unspecified,
+    /// in the order of declaration.
+    pub fn missing(&self) -> &[&'static str] {
+        &self.missing
+    }
+}
+
+impl fmt::Display for BuilderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("required option(s) unspecified: ")?;
+        for (i, name) in self.missing.iter().enumerate() {
+            if i != 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "`{name}`")?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for BuilderError {}
+
+impl From<BuilderError> for Error {
+    fn from(e: BuilderError) -> Self {
+        Self::validation(e.to_string())
+    }
+}
+
+/// An I/O error which is not attributable to one of the tools, e.g.
+/// that of reading the output files of a call.
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Self::new(ErrorKind::Io, e.into())
+    }
+}
+
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum ErrorKind {
     Bernoulli,
@@ -55,10 +197,12 @@ pub enum ErrorKind {
     Diagnose,
     Executable,
     Install,
+    Io,
     Make,
     ModelFile,
     StanC,
     StanSummary,
+    Validation,
 }
 
 impl ErrorKind {
@@ -70,10 +214,12 @@ impl ErrorKind {
             Diagnose => MAKE_DIAGNOSE,
             Executable => "model executable",
             Install => "cmdstan install",
+            Io => "i/o",
             Make => MAKE,
             ModelFile => "model file",
             StanC => MAKE_STANC,
             StanSummary => MAKE_STANSUMMARY,
+            Validation => "argument validation",
         }
     }
     /// Not every kind has a meaningful needle with which to probe
@@ -91,10 +237,12 @@ impl ErrorKind {
             Diagnose => "diagnose <filename 1>",
             Executable => "Bayesian inference with Markov Chain Monte Carlo",
             Install => "",
+            Io => "",
             Make => "Build CmdStan utilities",
             ModelFile => "",
             StanC => "stanc [option]",
             StanSummary => "stansummary [OPTIONS]",
+            Validation => "",
         }
     }
 }
@@ -104,16 +252,77 @@ impl fmt::Display for ErrorKind {
     }
 }
 
+/// Classification of the outcome of a process, based on its exit
+/// status and console output.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RunOutcome {
+    /// Exit status zero.
+    Ok,
+    /// Non-zero exit status, with nothing written to stderr.
+    NonZeroExit { code: i32 },
+    /// Terminated by a signal, hence, no exit code.
+    Signalled,
+    /// Non-zero exit status, with the (trimmed) content of stderr as
+    /// the `message`; CmdStan reports errors such as missing data or
+    /// an invalid argument in this manner.
+    StanError { message: String },
+}
+
+impl RunOutcome {
+    /// Classify the outcome of the process which produced `output`.
+    pub fn classify(output: &process::Output) -> Self {
+        if output.status.success() {
+            return Self::Ok;
+        }
+        match output.status.code() {
+            Some(code) => {
+                let stderr = String::from_utf8_lossy(&output.stderr[..]);
+                let message = stderr.trim();
+                if message.is_empty() {
+                    Self::NonZeroExit { code }
+                } else {
+                    Self::StanError {
+                        message: message.to_string(),
+                    }
+                }
+            }
+            None => Self::Signalled,
+        }
+    }
+
+    /// Return `true` if the process exited successfully.
+    pub fn is_ok(&self) -> bool {
+        matches!(self, Self::Ok)
+    }
+}
+
+impl From<&process::Output> for RunOutcome {
+    fn from(output: &process::Output) -> Self {
+        Self::classify(output)
+    }
+}
+
+impl fmt::Display for RunOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ok => f.write_str("process exited successfully"),
+            Self::NonZeroExit { code } => write!(f, "process exit status not zero: {code}"),
+            Self::Signalled => f.write_str("process terminated by signal"),
+            Self::StanError { message } => write!(f, "process exit status not zero: {message}"),
+        }
+    }
+}
+
 pub(crate) enum Repr {
     Io(io::Error),
-    UnsuccessfulExit(process::Output),
+    UnsuccessfulExit(ExitError),
 }
 
 impl fmt::Display for Repr {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Io(e) => fmt::Display::fmt(e, f),
-            Self::UnsuccessfulExit(_) => f.write_str("process exit status not zero"),
+            Self::UnsuccessfulExit(e) => fmt::Display::fmt(e, f),
         }
     }
 }
@@ -122,11 +331,7 @@ impl fmt::Debug for Repr {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Io(e) => fmt::Debug::fmt(e, f),
-            Self::UnsuccessfulExit(output) => f
-                .debug_struct("UnsuccessfulExit")
-                .field("stdout", &String::from_utf8_lossy(&output.stdout[..]))
-                .field("stderr", &String::from_utf8_lossy(&output.stderr[..]))
-                .finish(),
+            Self::UnsuccessfulExit(e) => fmt::Debug::fmt(e, f),
         }
     }
 }
@@ -138,6 +343,95 @@ impl From<io::Error> for Repr {
 }
 impl From<process::Output> for Repr {
     fn from(output: process::Output) -> Self {
-        Self::UnsuccessfulExit(output)
+        Self::UnsuccessfulExit(ExitError { output })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn source() {
+        use std::error::Error as _;
+        fn assert_send_sync<T: Send + Sync + 'static>() {}
+        assert_send_sync::<Error>();
+
+        let e = Error::validation("x must be > 0, got 0");
+        let source = e.source().unwrap();
+        let io = source.downcast_ref::<io::Error>().unwrap();
+        assert_eq!(io.kind(), io::ErrorKind::InvalidInput);
+        assert!(e.output().is_none());
+
+        let e = Error::from(io::Error::new(io::ErrorKind::NotFound, "output.csv"));
+        assert_eq!(e.kind(), ErrorKind::Io);
+        assert_eq!(format!("{e:#}"), "i/o: output.csv");
+        assert_eq!(e.source().unwrap().to_string(), "output.csv");
+    }
+
+    #[cfg(unix)]
+    mod run_outcome {
+        use super::*;
+        use std::os::unix::process::ExitStatusExt;
+
+        fn output(raw: i32, stdout: &str, stderr: &str) -> process::Output {
+            process::Output {
+                status: process::ExitStatus::from_raw(raw),
+                stdout: stdout.into(),
+                stderr: stderr.into(),
+            }
+        }
+
+        #[test]
+        fn classify() {
+            assert_eq!(RunOutcome::classify(&output(0, "", "")), RunOutcome::Ok);
+            assert_eq!(
+                RunOutcome::classify(&output(0, "", "warning")),
+                RunOutcome::Ok
+            );
+            assert_eq!(
+                RunOutcome::classify(&output(1 << 8, "", "")),
+                RunOutcome::NonZeroExit { code: 1 }
+            );
+            assert_eq!(
+                RunOutcome::classify(&output(70 << 8, "some output", " \n")),
+                RunOutcome::NonZeroExit { code: 70 }
+            );
+            assert_eq!(
+                RunOutcome::classify(&output(1 << 8, "", "Error reading data\n")),
+                RunOutcome::StanError {
+                    message: "Error reading data".to_string()
+                }
+            );
+            // SIGKILL
+            assert_eq!(
+                RunOutcome::classify(&output(9, "", "")),
+                RunOutcome::Signalled
+            );
+            assert!(RunOutcome::from(&output(0, "", "")).is_ok());
+            assert!(!RunOutcome::from(&output(9, "", "")).is_ok());
+        }
+
+        #[test]
+        fn appears_ok() {
+            let needle = ErrorKind::Make.needle();
+            assert!(Error::appears_ok(ErrorKind::Make, output(0, needle, "")).is_ok());
+
+            let e = Error::appears_ok(ErrorKind::Make, output(0, "", "")).unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::Make);
+            assert_eq!(format!("{e:#}"), "make: process output not as expected");
+
+            let e = Error::appears_ok(ErrorKind::Make, output(2 << 8, needle, "")).unwrap_err();
+            assert_eq!(format!("{e:#}"), "make: process exit status not zero: 2");
+            // The detail is displayed only by the source.
+            assert_eq!(e.to_string(), "make");
+
+            // The output is retained by the source.
+            let source = std::error::Error::source(&e).unwrap();
+            let exit = source.downcast_ref::<ExitError>().unwrap();
+            assert_eq!(exit.outcome(), RunOutcome::NonZeroExit { code: 2 });
+            assert_eq!(exit.to_string(), "process exit status not zero: 2");
+            assert_eq!(e.output().unwrap().stdout, needle.as_bytes());
+        }
     }
 }
@@ -4,19 +4,28 @@ mod internal_macros;
 pub mod argtree;
 mod base;
 pub(crate) mod builder;
+pub mod compile;
 mod consts;
 mod diagnose;
+pub mod draws;
 pub mod error;
 pub mod method;
+pub mod model_info;
 mod optimize;
 mod sample;
 pub mod stansummary;
+pub mod temp;
 mod variational;
 
 pub mod parser;
+pub mod prelude;
+pub mod progress;
 
 pub mod translate;
 
+#[cfg(test)]
+mod test_support;
+
 pub use argtree::*;
 pub use method::*;
 
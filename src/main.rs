@@ -1,40 +1,16 @@
-use cmdstan::argument_tree::{ArgumentTreeBuilder, Data, OutputBuilder, Random};
-use cmdstan::control::*;
-use cmdstan::sample::{HmcBuilder, Metric, NutsBuilder, SampleBuilder};
+use cmdstan::stansummary::StanSummaryOptions;
+use cmdstan::translate::Translate;
 use cmdstan::*;
+use std::env;
 
 fn main() {
-    // let model = "/nfs/site/home/aradclif/aradclif/org/org-linux/stan/examples/bernoulli/bernoulli";
+    let mut path = env::current_dir().unwrap();
+    path.push("examples");
+    path.push("bernoulli");
 
-    // let bigcmd = "method=sample num_samples=1000 num_warmup=1000 save_warmup=0 thin=1 adapt engaged=1 gamma=0.05 delta=0.8 kappa=0.75 t0=10 init_buffer=75 term_buffer=50 window=25 algorithm=hmc engine=nuts max_depth=10 metric=diag_e stepsize=1 stepsize_jitter=0 num_chains=4 data file=bernoulli.data.json id=2 init=1 random seed=12345 output file=output.csv refresh=100 sig_figs=-1 num_threads=1";
-
-    // let stan_program = StanProgram::from(
-    //     "data {
-    //        int<lower=0> N;
-    //        array[N] int<lower=0,upper=1> y;
-    //      }
-    //      parameters {
-    //        real<lower=0,upper=1> theta;
-    //      }
-    //      model {
-    //        theta ~ beta(1,1);   //uniform prior on interval 0,1
-    //        y ~ bernoulli(theta);
-    //      }
-    //      ",
-    // );
-    let stan_program = StanProgram::from(std::path::Path::new(
-        "/nfs/site/home/aradclif/aradclif/org/org-linux/stan/examples/bernoulli/bernoulli.stan",
-    ));
-
-    let workspace = Workspace {
-        model_name: "bernoulli".to_string(),
-        directory: "/nfs/site/home/aradclif/aradclif/org/org-linux/stan/examples/bernoulli/inner4"
-            .to_string(),
-        stan_program,
-    };
-    println!("{:#?}", workspace.setup());
-
-    let tree = ArgumentTreeBuilder::new()
+    // Nested builders need not be built prior to being passed to the
+    // setter of the outer builder.
+    let tree = ArgTree::builder()
         .method(
             SampleBuilder::new()
                 .num_samples(1234)
@@ -43,93 +19,34 @@ fn main() {
                 .thin(2)
                 .algorithm(
                     HmcBuilder::new()
-                        .engine(NutsBuilder::new().max_depth(100).build())
-                        .metric(Metric::DenseE)
-                        .build(),
+                        .engine(NutsBuilder::new().max_depth(100))
+                        .metric(Metric::DenseE),
                 )
-                .num_chains(4)
-                .build(),
+                .num_chains(4),
         )
-        .data(Data {
-            file: "/nfs/site/home/aradclif/aradclif/org/org-linux/stan/examples/bernoulli/bernoulli.data.json".to_string(),
-        })
+        .data(Data::builder().file(path.join("bernoulli.data.json")))
         .id(2)
-        .init("1".to_string())
-        .random(Random { seed: 12345 })
-        .output(OutputBuilder::new().sig_figs(4).build())
+        .init("1")
+        .random(Random::builder().seed(12345))
+        .output(Output::builder().sig_figs(4).file(path.join("output.csv")))
         .num_threads(48)
         .build();
-    let bigcmd = tree.command_string();
-    println!("{}", bigcmd);
-
-    // let con = Control::new("/nfs/site/home/aradclif/aradclif/cmdstan-2.33.1", model);
-    let con = Control::new(
-        "/nfs/site/home/aradclif/aradclif/cmdstan-2.33.1",
-        &workspace.model(),
-    );
-    // let con = Control::try_from(model).unwrap();
-    if !con.executable_works().unwrap_or(false) {
-        println!("{:#?}", con.compile());
-    }
-    println!("{:#?}", con.call_executable(&tree));
+    println!("{}", tree.to_stmt_quoted().to_string_lossy());
 
-    println!("{:#?}", con.diagnose(&tree));
-    println!("{:#?}", con.stansummary(&tree, None));
-
-    // let summary_opts = StanSummaryOptions::new()
-    //     .csv_filename("hello2.csv".to_string())
-    //     .sig_figs(6)
-    //     .percentiles(vec![5, 25, 50, 75, 95]);
-    let summary_opts = StanSummaryOptions {
-        autocorr: None,
-        csv_filename: Some("hello3.csv".to_string()),
-        percentiles: Some(vec![5, 25, 50, 75, 95]),
-        sig_figs: Some(6),
+    // `CMDSTAN`, else `CMDSTAN_HOME`, must point to an installation.
+    let Ok(cmdstan) = CmdStan::from_env() else {
+        return;
     };
-    println!("{:#?}", con.stansummary(&tree, Some(summary_opts)));
-
-    // env::set_var(
-    //     "CMDSTAN_HOME",
-    //     "/nfs/site/home/aradclif/aradclif/cmdstan-2.33.1",
-    // );
-    // if let Ok(home) = env::var("CMDSTAN_HOME") {
-    //     println!("CMDSTAN_HOME={}", home);
-    //     if let Ok(()) = env::set_current_dir(home) {
-    //         let output = Command::new("make")
-    //             .arg(model)
-    //             .output()
-    //             .expect("Failed to execute command");
-    //         println!("{:#?}", output);
-    //         let stdout = String::from_utf8(output.stdout).unwrap();
-    //         if stdout.contains("is up to date.\n") {
-    //             if let Ok(()) = env::set_current_dir(model.rsplit_once('/').unwrap().0) {
-    //                 // Different ways to create the same command
-    //                 // let output = Command::new(model)
-    //                 //     .arg("method=sample")
-    //                 //     .arg("help")
-    //                 //     .output()
-    //                 //     .expect("Failed to execute command");
-    //                 // println!("{:#?}", output);
-
-    //                 // let output = Command::new(model)
-    //                 //     .args(["method=sample", "help"])
-    //                 //     .output()
-    //                 //     .expect("Failed to execute command");
-    //                 // println!("{:#?}", output);
-    //                 // let output = Command::new(model)
-    //                 //     .args("method=sample help".split_whitespace())
-    //                 //     .output()
-    //                 //     .expect("Failed to execute command");
-    //                 // println!("{:#?}", output);
-
-    //                 // Test on a larger argument. Works.
-    //                 let output = Command::new(model)
-    //                     .args(bigcmd.split_whitespace())
-    //                     .output()
-    //                     .expect("Failed to execute big command");
-    //                 println!("{:#?}", output);
-    //             }
-    //         }
-    //     }
-    // }
+    let program = StanProgram::try_from(path.join("bernoulli.stan").as_ref()).unwrap();
+    let model = cmdstan.compile::<[_; 0], &str>(&program, []).unwrap();
+    let output = model.call(&tree).unwrap();
+
+    println!("{:#?}", cmdstan.diagnose(&output));
+    println!("{:#?}", cmdstan.stansummary(&output, None));
+
+    let summary_opts = StanSummaryOptions::builder()
+        .csv_filename(path.join("hello3.csv"))
+        .percentiles([5.0, 25.0, 50.0, 75.0, 95.0])
+        .sig_figs(6);
+    println!("{:#?}", cmdstan.stansummary(&output, summary_opts));
 }
@@ -13,6 +13,7 @@ in the documentation respective to each `struct` or `enum`.
 */
 use crate::builder::Builder;
 pub use crate::diagnose::*;
+use crate::error::Error;
 pub use crate::optimize::*;
 pub use crate::sample::*;
 use crate::translate::Translate;
@@ -28,7 +29,7 @@ pub enum Method {
     /// [`SampleBuilder`] for parameterized construction with optional defaults.
     #[non_exhaustive]
     Sample {
-        /// Number of warmup iterations.
+        /// Number of sampling iterations.
         /// Valid values: `0 <= num_samples`.
         /// Defaults to `1000`.
         #[defaults_to = 1000]
@@ -276,6 +277,313 @@ impl Default for Method {
         SampleBuilder::new().build()
     }
 }
+
+impl Method {
+    /// Return a fixed index for the variant, in the order in which
+    /// CmdStan lists the methods: `sample`, `optimize`, `variational`,
+    /// `diagnose`, `generate_quantities`, `pathfinder`, `log_prob`,
+    /// `laplace`. Unlike the order of declaration, this is stable
+    /// as variants are added.
+    pub fn ordinal(&self) -> u8 {
+        match self {
+            Method::Sample { .. } => 0,
+            Method::Optimize { .. } => 1,
+            Method::Variational { .. } => 2,
+            Method::Diagnose { .. } => 3,
+            Method::GenerateQuantities { .. } => 4,
+            Method::Pathfinder { .. } => 5,
+            Method::LogProb { .. } => 6,
+            Method::Laplace { .. } => 7,
+        }
+    }
+
+    /// Return the spelling of the method, as it appears in the argument tree.
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Method::Sample { .. } => "sample",
+            Method::Optimize { .. } => "optimize",
+            Method::Variational { .. } => "variational",
+            Method::Diagnose { .. } => "diagnose",
+            Method::GenerateQuantities { .. } => "generate_quantities",
+            Method::Pathfinder { .. } => "pathfinder",
+            Method::LogProb { .. } => "log_prob",
+            Method::Laplace { .. } => "laplace",
+        }
+    }
+
+    /// Return the number of chains run by a single call. Only `sample`
+    /// supports multiple chains; all other methods run exactly one.
+    pub fn num_chains(&self) -> i32 {
+        match self {
+            Method::Sample { num_chains, .. } => *num_chains,
+            _ => 1,
+        }
+    }
+
+    /// Configure the number of chains, which must be positive. Only
+    /// `sample` supports multiple chains, hence, for any other method,
+    /// a request for `num_chains != 1` is an error, rather than silently
+    /// producing a single output file.
+    pub fn with_num_chains(mut self, num_chains: i32) -> Result<Self, Error> {
+        Error::require(num_chains > 0, "num_chains", "> 0", num_chains)?;
+        match &mut self {
+            Method::Sample { num_chains: n, .. } => {
+                *n = num_chains;
+                Ok(self)
+            }
+            _ if num_chains == 1 => Ok(self),
+            _ => Err(Error::validation(format!(
+                "num_chains must be 1 for {}, got {}; only sample supports multiple chains",
+                self.name(),
+                num_chains
+            ))),
+        }
+    }
+
+    /// Check constraints which span several fields, and which are
+    /// therefore missed by the checks of the individual values:
+    ///
+    /// - `sample` with `fixed_param` performs no adaptation, hence,
+    ///   adaptation cannot be engaged with `num_warmup > 0`.
+    /// - `sample` with `hmc` requires `num_warmup > 0` if adaptation
+    ///   is engaged.
+    /// - `sample` with `hmc` and a metric which is adapted (`diag_e` or
+    ///   `dense_e`) requires that the stages of adaptation fit within
+    ///   `num_warmup`, else CmdStan silently substitutes its own; see
+    ///   [`SampleAdapt::stages_fit`].
+    /// - `metric_file` requires a metric which is read from file,
+    ///   i.e. `diag_e` or `dense_e`, not `unit_e`.
+    /// - `log_prob` requires exactly one of `unconstrained_params`
+    ///   and `constrained_params`.
+    ///
+    /// This is not invoked by [`CmdStanModel::call`](crate::CmdStanModel::call);
+    /// call it prior, if desired.
+    pub fn validate_combination(&self) -> Result<(), Error> {
+        match self {
+            Method::Sample {
+                num_warmup,
+                adapt,
+                algorithm: SampleAlgorithm::FixedParam,
+                ..
+            } if adapt.engaged && *num_warmup > 0 => Err(Error::validation(
+                "fixed_param performs no adaptation; set num_warmup=0 or adapt engaged=0",
+            )),
+            Method::Sample {
+                num_warmup,
+                adapt,
+                algorithm:
+                    SampleAlgorithm::Hmc {
+                        metric,
+                        metric_file,
+                        ..
+                    },
+                ..
+            } => {
+                if adapt.engaged && *num_warmup == 0 {
+                    return Err(Error::validation("adapt engaged=1 requires num_warmup > 0"));
+                }
+                if adapt.engaged && *metric != Metric::UnitE && !adapt.stages_fit(*num_warmup) {
+                    return Err(Error::validation(format!(
+                        "init_buffer + window + term_buffer must be <= num_warmup, got {} > {}",
+                        adapt.stages_len(),
+                        num_warmup
+                    )));
+                }
+                if !metric_file.is_empty() && *metric == Metric::UnitE {
+                    return Err(Error::validation(
+                        "metric_file requires metric diag_e or dense_e, got unit_e",
+                    ));
+                }
+                Ok(())
+            }
+            Method::LogProb {
+                unconstrained_params,
+                constrained_params,
+                ..
+            } => match (
+                unconstrained_params.is_empty(),
+                constrained_params.is_empty(),
+            ) {
+                (false, true) | (true, false) => Ok(()),
+                _ => Err(Error::validation(
+                    "log_prob requires exactly one of unconstrained_params and constrained_params",
+                )),
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Return a key by which to sort methods deterministically: first
+    /// by [`Method::ordinal`], then by the statement of the method
+    /// in command line language. `Method` does not implement `Ord`,
+    /// as it holds floating point values, hence, use e.g.
+    /// `methods.sort_by_cached_key(Method::sort_key)`.
+    pub fn sort_key(&self) -> (u8, OsString) {
+        (self.ordinal(), self.to_stmt())
+    }
+
+    /// Return the number of draws which a single chain is expected to
+    /// write to its output file, if implied by the configuration.
+    /// Only `sample` implies a number of draws: `ceil(num_samples / thin)`,
+    /// plus `ceil(num_warmup / thin)` if `save_warmup`. For all other
+    /// methods, or if the configuration is invalid, this is `None`.
+    pub fn expected_draws(&self) -> Option<usize> {
+        match self {
+            Method::Sample {
+                num_samples,
+                num_warmup,
+                save_warmup,
+                thin,
+                ..
+            } if *thin > 0 && *num_samples >= 0 && *num_warmup >= 0 => {
+                let f = |n: i32| (n as usize).div_ceil(*thin as usize);
+                let warmup = if *save_warmup { f(*num_warmup) } else { 0 };
+                Some(f(*num_samples) + warmup)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Configure the algorithm of a method by means of a closure applied
+/// to the respective sub-builder, which avoids the nesting of builders.
+impl SampleBuilder {
+    /// Configure `algorithm` as [`SampleAlgorithm::Hmc`], with options
+    /// set by `f`.
+    pub fn hmc<F>(self, f: F) -> Self
+    where
+        F: FnOnce(HmcBuilder) -> HmcBuilder,
+    {
+        self.algorithm(f(HmcBuilder::new()))
+    }
+    /// Configure `algorithm` as [`SampleAlgorithm::FixedParam`].
+    pub fn fixed_param(self) -> Self {
+        self.algorithm(SampleAlgorithm::FixedParam)
+    }
+
+    /// A configuration for smoke tests: 150 warmup iterations, which
+    /// is the least number into which the default adaptation windows
+    /// fit, and 100 sampling iterations. The draws are not suited to
+    /// inference.
+    pub fn preset_quick() -> Self {
+        Self::new().num_warmup(150).num_samples(100)
+    }
+
+    /// The defaults of CmdStan: 1000 warmup and 1000 sampling iterations,
+    /// with `adapt delta=0.8`. Identical to [`SampleBuilder::new`].
+    pub fn preset_default() -> Self {
+        Self::new()
+    }
+
+    /// A configuration for difficult posteriors: 2000 warmup and 2000
+    /// sampling iterations, with `adapt delta=0.95`, which reduces the
+    /// step size, hence, divergences, at the expense of time per iteration.
+    pub fn preset_thorough() -> Self {
+        Self::new()
+            .num_warmup(2000)
+            .num_samples(2000)
+            .adapt(SampleAdapt::builder().delta(0.95))
+    }
+
+    /// Build the `Method::Sample` instance, returning an error if
+    /// any value would be rejected by CmdStan, as documented for each
+    /// field of [`Method::Sample`] and [`SampleAlgorithm`].
+    pub fn try_build(self) -> Result<Method, Error> {
+        let x = self.build();
+        if let Method::Sample {
+            num_samples,
+            num_warmup,
+            thin,
+            algorithm,
+            num_chains,
+            ..
+        } = &x
+        {
+            Error::require(*num_samples >= 0, "num_samples", ">= 0", num_samples)?;
+            Error::require(*num_warmup >= 0, "num_warmup", ">= 0", num_warmup)?;
+            Error::require(*thin > 0, "thin", "> 0", thin)?;
+            Error::require(*num_chains > 0, "num_chains", "> 0", num_chains)?;
+            algorithm.validate()?;
+        }
+        Ok(x)
+    }
+}
+
+impl OptimizeBuilder {
+    /// Configure `algorithm` as [`OptimizeAlgorithm::Bfgs`], with
+    /// options set by `f`.
+    pub fn bfgs<F>(self, f: F) -> Self
+    where
+        F: FnOnce(BfgsBuilder) -> BfgsBuilder,
+    {
+        self.algorithm(f(BfgsBuilder::new()))
+    }
+    /// Configure `algorithm` as [`OptimizeAlgorithm::Lbfgs`], with
+    /// options set by `f`.
+    pub fn lbfgs<F>(self, f: F) -> Self
+    where
+        F: FnOnce(LbfgsBuilder) -> LbfgsBuilder,
+    {
+        self.algorithm(f(LbfgsBuilder::new()))
+    }
+    /// Configure `algorithm` as [`OptimizeAlgorithm::Newton`].
+    pub fn newton(self) -> Self {
+        self.algorithm(OptimizeAlgorithm::Newton)
+    }
+}
+
+impl VariationalBuilder {
+    /// Configure `algorithm` as [`VariationalAlgorithm::MeanField`].
+    pub fn meanfield(self) -> Self {
+        self.algorithm(VariationalAlgorithm::MeanField)
+    }
+    /// Configure `algorithm` as [`VariationalAlgorithm::FullRank`].
+    pub fn fullrank(self) -> Self {
+        self.algorithm(VariationalAlgorithm::FullRank)
+    }
+
+    /// Build the `Method::Variational` instance, returning an error if
+    /// any value would be rejected by CmdStan, as documented for each
+    /// field of [`Method::Variational`] and [`VariationalAdapt`].
+    pub fn try_build(self) -> Result<Method, Error> {
+        let x = self.build();
+        if let Method::Variational {
+            iter,
+            grad_samples,
+            elbo_samples,
+            eta,
+            adapt,
+            tol_rel_obj,
+            eval_elbo,
+            output_samples,
+            ..
+        } = &x
+        {
+            Error::require(*iter > 0, "(variational) iter", "> 0", iter)?;
+            Error::require(*grad_samples > 0, "grad_samples", "> 0", grad_samples)?;
+            Error::require(*elbo_samples > 0, "elbo_samples", "> 0", elbo_samples)?;
+            // NaN fails every comparison, hence, is rejected.
+            Error::require(*eta > 0.0, "eta", "> 0", eta)?;
+            Error::require(*tol_rel_obj >= 0.0, "tol_rel_obj", ">= 0", tol_rel_obj)?;
+            Error::require(*eval_elbo > 0, "eval_elbo", "> 0", eval_elbo)?;
+            Error::require(*output_samples > 0, "output_samples", "> 0", output_samples)?;
+            adapt.validate()?;
+        }
+        Ok(x)
+    }
+}
+
+impl DiagnoseBuilder {
+    /// Configure `test` as [`DiagnoseTest::Gradient`], with options
+    /// set by `f`.
+    pub fn gradient<F>(self, f: F) -> Self
+    where
+        F: FnOnce(GradientBuilder) -> GradientBuilder,
+    {
+        self.test(f(GradientBuilder::new()))
+    }
+}
+
 // macro_rules! from_impl {
 //     ($T:ident) => {
 //         impl From<$T> for Method {
@@ -298,9 +606,205 @@ impl Default for Method {
 mod tests {
     use super::*;
 
+    #[test]
+    fn sample_presets() {
+        let presets = [
+            SampleBuilder::preset_quick().build(),
+            SampleBuilder::preset_default().build(),
+            SampleBuilder::preset_thorough().build(),
+        ];
+        let expected = [(150, 100, 0.8), (1000, 1000, 0.8), (2000, 2000, 0.95)];
+        for (x, (warmup, samples, delta)) in presets.iter().zip(expected) {
+            match x {
+                Method::Sample {
+                    num_warmup,
+                    num_samples,
+                    adapt,
+                    ..
+                } => {
+                    assert_eq!(*num_warmup, warmup);
+                    assert_eq!(*num_samples, samples);
+                    assert_eq!(adapt.delta, delta);
+                }
+                _ => panic!("expected sample"),
+            }
+            assert!(x.validate_combination().is_ok());
+        }
+        assert_eq!(presets[1], Method::default());
+    }
+
+    #[test]
+    fn validate_combination() {
+        assert!(Method::default().validate_combination().is_ok());
+        assert!(OptimizeBuilder::new()
+            .build()
+            .validate_combination()
+            .is_ok());
+
+        let x = SampleBuilder::new().fixed_param().build();
+        let e = x.validate_combination().unwrap_err();
+        assert_eq!(e.kind(), crate::error::ErrorKind::Validation);
+        assert!(format!("{e:#}").contains("fixed_param"));
+        let x = SampleBuilder::new().fixed_param().num_warmup(0).build();
+        assert!(x.validate_combination().is_ok());
+        let x = SampleBuilder::new()
+            .fixed_param()
+            .adapt(SampleAdapt::builder().engaged(false))
+            .build();
+        assert!(x.validate_combination().is_ok());
+
+        let x = SampleBuilder::new().num_warmup(0).build();
+        assert!(x.validate_combination().is_err());
+        let x = SampleBuilder::new()
+            .num_warmup(0)
+            .adapt(SampleAdapt::builder().engaged(false))
+            .build();
+        assert!(x.validate_combination().is_ok());
+
+        let x = SampleBuilder::new().num_warmup(100).build();
+        let e = x.validate_combination().unwrap_err();
+        assert!(format!("{e:#}").contains("got 150 > 100"));
+        let x = SampleBuilder::new()
+            .num_warmup(100)
+            .adapt(SampleAdapt::builder().init_buffer(25_u32))
+            .build();
+        assert!(x.validate_combination().is_ok());
+        let x = SampleBuilder::new()
+            .num_warmup(100)
+            .hmc(|b| b.metric(Metric::UnitE))
+            .build();
+        assert!(x.validate_combination().is_ok());
+        assert!(SampleBuilder::new()
+            .num_warmup(10)
+            .build()
+            .validate_combination()
+            .is_ok());
+
+        let x = SampleBuilder::new()
+            .hmc(|b| b.metric(Metric::UnitE).metric_file("metric.json"))
+            .build();
+        assert!(x.validate_combination().is_err());
+        let x = SampleBuilder::new()
+            .hmc(|b| b.metric(Metric::DenseE).metric_file("metric.json"))
+            .build();
+        assert!(x.validate_combination().is_ok());
+
+        assert!(LogProbBuilder::new()
+            .build()
+            .validate_combination()
+            .is_err());
+        let x = LogProbBuilder::new().unconstrained_params("u.json").build();
+        assert!(x.validate_combination().is_ok());
+        let x = LogProbBuilder::new().constrained_params("c.json").build();
+        assert!(x.validate_combination().is_ok());
+        let x = LogProbBuilder::new()
+            .unconstrained_params("u.json")
+            .constrained_params("c.json")
+            .build();
+        assert!(x.validate_combination().is_err());
+    }
+
+    #[test]
+    fn num_chains() {
+        assert_eq!(Method::default().num_chains(), 1);
+        let x = SampleBuilder::new().num_chains(4).build();
+        assert_eq!(x.num_chains(), 4);
+        assert_eq!(
+            x.with_num_chains(2).unwrap(),
+            SampleBuilder::new().num_chains(2).build()
+        );
+
+        for x in [
+            OptimizeBuilder::new().build(),
+            VariationalBuilder::new().build(),
+            PathfinderBuilder::new().build(),
+            LaplaceBuilder::new().build(),
+        ] {
+            assert_eq!(x.num_chains(), 1);
+            assert_eq!(x.clone().with_num_chains(1).unwrap(), x);
+            let e = x.clone().with_num_chains(4).unwrap_err();
+            assert_eq!(e.kind(), crate::error::ErrorKind::Validation);
+            assert!(format!("{e:#}").contains(x.name()));
+        }
+        assert!(Method::default().with_num_chains(0).is_err());
+        assert!(OptimizeBuilder::new().build().with_num_chains(-1).is_err());
+    }
+
+    #[test]
+    fn sort_key() {
+        let mut methods = vec![
+            LaplaceBuilder::new().build(),
+            OptimizeBuilder::new().iter(20).build(),
+            LogProbBuilder::new().build(),
+            SampleBuilder::new().num_samples(10).build(),
+            PathfinderBuilder::new().build(),
+            GenerateQuantitiesBuilder::new().build(),
+            OptimizeBuilder::new().iter(10).build(),
+            DiagnoseBuilder::new().build(),
+            VariationalBuilder::new().build(),
+            SampleBuilder::new().num_samples(5).build(),
+        ];
+        methods.sort_by_cached_key(Method::sort_key);
+        let ordinals: Vec<u8> = methods.iter().map(Method::ordinal).collect();
+        assert_eq!(ordinals, [0, 0, 1, 1, 2, 3, 4, 5, 6, 7]);
+        // Ties are broken by the statement.
+        assert_eq!(methods[0], SampleBuilder::new().num_samples(10).build());
+        assert_eq!(methods[2], OptimizeBuilder::new().iter(10).build());
+    }
+
     mod sample {
         use super::*;
 
+        #[test]
+        fn try_build() {
+            let x = SampleBuilder::new().try_build().unwrap();
+            assert_eq!(x, SampleBuilder::new().build());
+            let x = SampleBuilder::new()
+                .num_samples(0)
+                .num_warmup(0)
+                .try_build()
+                .unwrap();
+            assert_eq!(x.expected_draws(), Some(0));
+
+            let f = |x: SampleBuilder| format!("{:#}", x.try_build().unwrap_err());
+            assert!(f(SampleBuilder::new().num_samples(-1))
+                .contains("num_samples must be >= 0, got -1"));
+            assert!(
+                f(SampleBuilder::new().num_warmup(-1)).contains("num_warmup must be >= 0, got -1")
+            );
+            assert!(f(SampleBuilder::new().thin(0)).contains("thin"));
+            assert!(f(SampleBuilder::new().num_chains(0)).contains("num_chains"));
+            assert!(f(SampleBuilder::new().hmc(|x| x.stepsize(0.0))).contains("stepsize"));
+        }
+
+        #[test]
+        fn reset() {
+            let mut x = SampleBuilder::new().num_samples(2).thin(5).num_chains(4);
+            x.reset();
+            assert_eq!(x, SampleBuilder::new());
+            assert_eq!(x.build(), Method::default());
+        }
+
+        #[test]
+        fn expected_draws() {
+            assert_eq!(Method::default().expected_draws(), Some(1000));
+            let f = |x: SampleBuilder| x.build().expected_draws();
+            assert_eq!(f(SampleBuilder::new().thin(3)), Some(334));
+            assert_eq!(f(SampleBuilder::new().save_warmup(true)), Some(2000));
+            assert_eq!(
+                f(SampleBuilder::new()
+                    .num_samples(10)
+                    .num_warmup(5)
+                    .save_warmup(true)
+                    .thin(2)),
+                Some(8)
+            );
+            assert_eq!(f(SampleBuilder::new().num_samples(0)), Some(0));
+            assert_eq!(f(SampleBuilder::new().thin(0)), None);
+            assert_eq!(f(SampleBuilder::new().num_samples(-1)), None);
+            assert_eq!(OptimizeBuilder::new().build().expected_draws(), None);
+        }
+
         #[test]
         fn builder() {
             let x = SampleBuilder::new();
@@ -354,6 +858,27 @@ mod tests {
             );
         }
 
+        #[test]
+        fn algorithm() {
+            let x = SampleBuilder::new()
+                .hmc(|b| b.metric(Metric::DenseE).nuts(|b| b.max_depth(3)))
+                .build();
+            let y = SampleBuilder::new()
+                .algorithm(
+                    HmcBuilder::new()
+                        .metric(Metric::DenseE)
+                        .engine(NutsBuilder::new().max_depth(3)),
+                )
+                .build();
+            assert_eq!(x, y);
+
+            let x = SampleBuilder::new().fixed_param().build();
+            let y = SampleBuilder::new()
+                .algorithm(SampleAlgorithm::FixedParam)
+                .build();
+            assert_eq!(x, y);
+        }
+
         #[test]
         fn to_args() {
             let x = SampleBuilder::new().build();
@@ -412,6 +937,33 @@ mod tests {
             );
         }
 
+        #[test]
+        fn algorithm() {
+            let x = OptimizeBuilder::new().lbfgs(|b| b.history_size(3)).build();
+            let y = OptimizeBuilder::new()
+                .algorithm(LbfgsBuilder::new().history_size(3))
+                .build();
+            assert_eq!(x, y);
+
+            let x = OptimizeBuilder::new().bfgs(|b| b.tol_obj(0.1)).build();
+            let y = OptimizeBuilder::new()
+                .algorithm(BfgsBuilder::new().tol_obj(0.1))
+                .build();
+            assert_eq!(x, y);
+
+            let x = OptimizeBuilder::new().newton().iter(5).build();
+            let y = OptimizeBuilder::new()
+                .algorithm(OptimizeAlgorithm::Newton)
+                .iter(5)
+                .build();
+            assert_eq!(x, y);
+
+            // The last call wins, as with any other option.
+            let x = OptimizeBuilder::new().newton().bfgs(|b| b).build();
+            let y = OptimizeBuilder::new().algorithm(BfgsBuilder::new()).build();
+            assert_eq!(x, y);
+        }
+
         #[test]
         fn to_args() {
             let x = OptimizeBuilder::new().build();
@@ -438,6 +990,35 @@ mod tests {
     mod variational {
         use super::*;
 
+        #[test]
+        fn try_build() {
+            let x = VariationalBuilder::new().try_build().unwrap();
+            assert_eq!(x, VariationalBuilder::new().build());
+            let x = VariationalBuilder::new()
+                .tol_rel_obj(0.0)
+                .eta(1e-3)
+                .try_build()
+                .unwrap();
+            assert_eq!(
+                x,
+                VariationalBuilder::new().tol_rel_obj(0.0).eta(1e-3).build()
+            );
+
+            let f = |x: VariationalBuilder| format!("{:#}", x.try_build().unwrap_err());
+            assert!(f(VariationalBuilder::new().iter(0)).contains("iter must be > 0, got 0"));
+            assert!(f(VariationalBuilder::new().grad_samples(0)).contains("grad_samples"));
+            assert!(f(VariationalBuilder::new().elbo_samples(-1)).contains("elbo_samples"));
+            assert!(f(VariationalBuilder::new().eta(0.0)).contains("eta"));
+            assert!(f(VariationalBuilder::new().eta(f64::NAN)).contains("eta"));
+            assert!(f(VariationalBuilder::new().tol_rel_obj(-0.1)).contains("tol_rel_obj"));
+            assert!(f(VariationalBuilder::new().eval_elbo(0)).contains("eval_elbo"));
+            assert!(f(VariationalBuilder::new().output_samples(0)).contains("output_samples"));
+            assert!(
+                f(VariationalBuilder::new().adapt(VariationalAdapt::builder().iter(0)))
+                    .contains("adapt iter")
+            );
+        }
+
         #[test]
         fn builder() {
             let x = VariationalBuilder::new()
@@ -483,6 +1064,17 @@ mod tests {
             );
         }
 
+        #[test]
+        fn algorithm() {
+            let x = VariationalBuilder::new().fullrank().build();
+            let y = VariationalBuilder::new()
+                .algorithm(VariationalAlgorithm::FullRank)
+                .build();
+            assert_eq!(x, y);
+            let x = VariationalBuilder::new().fullrank().meanfield().build();
+            assert_eq!(x, VariationalBuilder::new().build());
+        }
+
         #[test]
         fn to_args() {
             let x = VariationalBuilder::new().build();
@@ -528,6 +1120,17 @@ mod tests {
             );
         }
 
+        #[test]
+        fn test() {
+            let x = DiagnoseBuilder::new()
+                .gradient(|b| b.epsilon(0.1).error(0.2))
+                .build();
+            let y = DiagnoseBuilder::new()
+                .test(GradientBuilder::new().epsilon(0.1).error(0.2))
+                .build();
+            assert_eq!(x, y);
+        }
+
         #[test]
         fn to_args() {
             let x = DiagnoseBuilder::new().build();
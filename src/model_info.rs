@@ -0,0 +1,45 @@
+//! The structure of a Stan program, as reported by `stanc --info`.
+//! See [`CmdStan::model_info`](crate::CmdStan::model_info).
+
+use std::path::PathBuf;
+
+/// A variable declared in one of the blocks of a Stan program.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Variable {
+    pub name: String,
+    /// The type, as reported by stanc, e.g. `real`, `int` or `vector`.
+    pub type_name: String,
+    /// The number of dimensions, e.g. 0 for a scalar, 1 for a vector
+    /// or a one-dimensional array of scalars.
+    pub dimensions: usize,
+}
+
+/// The variables declared by a Stan program, by block, in the order of
+/// declaration, along with the functions and distributions which it uses.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct ModelStructure {
+    /// The variables of the `data` block, which must be present in
+    /// the data file.
+    pub inputs: Vec<Variable>,
+    pub parameters: Vec<Variable>,
+    pub transformed_parameters: Vec<Variable>,
+    pub generated_quantities: Vec<Variable>,
+    pub functions: Vec<String>,
+    pub distributions: Vec<String>,
+    /// The files included by the program (by `#include`). These are
+    /// parsed from the output of stanc, which is JSON, hence, UTF-8.
+    pub included_files: Vec<PathBuf>,
+}
+
+impl ModelStructure {
+    /// Return `true` if the program declares no parameters, in which
+    /// case CmdStan requires the `fixed_param` sampler.
+    pub fn has_no_parameters(&self) -> bool {
+        self.parameters.is_empty()
+    }
+
+    /// Return the names of the variables which the data file must provide.
+    pub fn input_names(&self) -> Vec<&str> {
+        self.inputs.iter().map(|x| x.name.as_str()).collect()
+    }
+}
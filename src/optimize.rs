@@ -1,6 +1,6 @@
 use crate::builder::Builder;
 use crate::translate::Translate;
-use std::ffi::OsString;
+use std::{ffi::OsString, fmt};
 
 /// Optimization algorithm. Defaults to [`OptimizeAlgorithm::Lbfgs`].
 #[derive(Debug, PartialEq, Clone, Translate, Builder)]
@@ -90,6 +90,18 @@ impl Default for OptimizeAlgorithm {
     }
 }
 
+/// Emits the spelling of the variant, as it appears in the argument
+/// tree, e.g. `lbfgs`, without the options of the algorithm.
+impl fmt::Display for OptimizeAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Bfgs { .. } => "bfgs",
+            Self::Lbfgs { .. } => "lbfgs",
+            Self::Newton => "newton",
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -150,6 +162,16 @@ mod tests {
         );
     }
 
+    #[test]
+    fn display() {
+        assert_eq!(BfgsBuilder::new().build().to_string(), "bfgs");
+        assert_eq!(
+            LbfgsBuilder::new().history_size(3).build().to_string(),
+            "lbfgs"
+        );
+        assert_eq!(OptimizeAlgorithm::Newton.to_string(), "newton");
+    }
+
     #[test]
     fn default() {
         let x = LbfgsBuilder::new().build();
@@ -45,7 +45,13 @@ impl Random {
                     let value = pair.as_str().parse::<i64>()?;
                     seed = Some(value);
                 }
-                Ok(seed.map(|seed| Random { seed }).unwrap_or_default())
+                match seed {
+                    Some(seed) => match Seed::try_from(seed) {
+                        Ok(_) => Ok(Random { seed }),
+                        Err(_) => Err(SeedOutOfRange(seed)),
+                    },
+                    None => Ok(Random::default()),
+                }
             }
             r => Err(RuleError(r)),
         }
@@ -162,67 +168,124 @@ impl ArgTree {
     }
 
     pub fn from_reader<R: Read>(rdr: R) -> io::Result<Result<Self, ParseGrammarError>> {
-        fn remove_newline(s: &mut String) {
-            if s.ends_with('\n') {
-                s.pop();
-                if s.ends_with('\r') {
-                    s.pop();
-                }
+        let lines = read_header(rdr)?;
+        Ok(statement(&lines).parse::<Self>())
+    }
+
+    /// As [`ArgTree::from_reader`], but additionally record whether each
+    /// value was marked `(Default)` by CmdStan; see [`ArgTreeWithProvenance`].
+    pub fn from_reader_with_provenance<R: Read>(
+        rdr: R,
+    ) -> io::Result<Result<ArgTreeWithProvenance, ParseGrammarError>> {
+        let lines = read_header(rdr)?;
+        let tree = match statement(&lines).parse::<Self>() {
+            Ok(tree) => tree,
+            Err(e) => return Ok(Err(e)),
+        };
+        // A stack of the enclosing arguments, with the indentation of each.
+        let mut path: Vec<(usize, &str)> = Vec::new();
+        let mut values = Vec::new();
+        for line in &lines {
+            let l = line.trim_start_matches('#');
+            let body = l.trim_start();
+            let indent = l.len() - body.len();
+            while path.last().is_some_and(|(n, _)| *n >= indent) {
+                path.pop();
             }
-        }
-        fn consume(s: &mut String, line: &str) -> bool {
-            let l = line
-                .trim_start_matches('#')
-                .trim_start()
-                .trim_end_matches("(Default)");
-            if let Some((prefix, suffix)) = l.split_once(" = ") {
-                s.push_str(prefix);
-                s.push('=');
-                s.push_str(suffix);
-                s.push(' ');
-            } else if !s.trim().ends_with(l.trim_end()) {
-                s.push_str(l);
-                s.push(' ');
+            let (name, is_value) = match body.split_once(" = ") {
+                Some((name, _)) => (name, true),
+                None => (body.trim_end(), false),
+            };
+            if name.is_empty() {
+                continue;
+            }
+            path.push((indent, name));
+            if is_value {
+                let key: Vec<&str> = path.iter().map(|(_, name)| *name).collect();
+                values.push((key.join("."), body.trim_end().ends_with("(Default)")));
             }
-            // Are we done?
-            // The stop symbol is num_threads, at least under the current Stan format.
-            l.starts_with("num_threads")
         }
-        let mut file = BufReader::new(rdr);
-
-        // For lines which do not contain values, 256 bytes should be sufficient
-        // even for very long paths. Add 64 bytes for the long keywords.
-        let mut l = String::with_capacity(320);
-        // Worst case scenario: 5 paths at 256 bytes each = 1280 bytes,
-        // leaves us 768 bytes for the remaining input.
-        let mut s = String::with_capacity(2048);
-
-        // Read until start
-        // We try our best to find the start symbol, at the risk
-        // of reading arbitrarily large inputs.
-        loop {
-            if file.read_line(&mut l)? == 0
-                || l.trim_start_matches('#').trim_start().starts_with("method")
-            {
-                break;
+        Ok(Ok(ArgTreeWithProvenance { tree, values }))
+    }
+}
+
+/// Read the lines of the header of a Stan CSV file which hold the argument
+/// tree, i.e. from `method` through `num_threads`, without line terminators.
+fn read_header<R: Read>(rdr: R) -> io::Result<Vec<String>> {
+    fn remove_newline(s: &mut String) {
+        if s.ends_with('\n') {
+            s.pop();
+            if s.ends_with('\r') {
+                s.pop();
             }
-            l.clear();
         }
+    }
+    let mut file = BufReader::new(rdr);
+    let mut lines = Vec::new();
+
+    // For lines which do not contain values, 256 bytes should be sufficient
+    // even for very long paths. Add 64 bytes for the long keywords.
+    let mut l = String::with_capacity(320);
+
+    // Read until start
+    // We try our best to find the start symbol, at the risk
+    // of reading arbitrarily large inputs.
+    loop {
+        if file.read_line(&mut l)? == 0
+            || l.trim_start_matches('#').trim_start().starts_with("method")
+        {
+            break;
+        }
+        l.clear();
+    }
+    remove_newline(&mut l);
+    lines.push(l.clone());
+    l.clear();
+    // Then read until we hit the end of meaningful input
+    // If we have iterated through 255 lines, then something is clearly wrong.
+    // The stop symbol is num_threads, at least under the current Stan format.
+    let mut stop = false;
+    let mut n: u8 = 0;
+    while !stop & (n != 255) && file.read_line(&mut l)? != 0 {
         remove_newline(&mut l);
-        consume(&mut s, &l);
+        stop = l
+            .trim_start_matches('#')
+            .trim_start()
+            .starts_with("num_threads");
+        lines.push(l.clone());
+        n += 1;
         l.clear();
-        // Then read until we hit the end of meaningful input
-        // If we have iterated through 255 lines, then something is clearly wrong.
-        let mut stop = false;
-        let mut n: u8 = 0;
-        while !stop & (n != 255) && file.read_line(&mut l)? != 0 {
-            remove_newline(&mut l);
-            stop = consume(&mut s, &l);
-            n += 1;
-            l.clear();
+    }
+    Ok(lines)
+}
+
+/// Translate the lines of the header to a statement in command line language.
+fn statement(lines: &[String]) -> String {
+    // Worst case scenario: 5 paths at 256 bytes each = 1280 bytes,
+    // leaves us 768 bytes for the remaining input.
+    let mut s = String::with_capacity(2048);
+    for line in lines {
+        let l = line
+            .trim_start_matches('#')
+            .trim_start()
+            .trim_end()
+            .trim_end_matches("(Default)");
+        if let Some((prefix, suffix)) = l.split_once(" = ") {
+            s.push_str(prefix);
+            s.push('=');
+            s.push_str(suffix);
+            s.push(' ');
+        } else if let Some(prefix) = l.strip_suffix(" =") {
+            // An empty value without the `(Default)` marker, from which
+            // the trailing space has been trimmed.
+            s.push_str(prefix);
+            s.push_str("= ");
+        } else if !s.trim().ends_with(l.trim_end()) {
+            s.push_str(l);
+            s.push(' ');
         }
-        Ok(s.trim().parse::<Self>())
     }
+    s.trim().to_string()
 }
 
 impl FromStr for ArgTree {
@@ -285,6 +348,15 @@ mod tests {
 
             let s = "random seed=123 seed=456 seed=789 seed";
             assert_eq!(s.parse::<Random>().unwrap(), Random { seed: 789 });
+
+            assert_eq!(
+                "random seed=-5".parse::<Random>().unwrap_err(),
+                SeedOutOfRange(-5)
+            );
+            assert_eq!(
+                "random seed=4294967296".parse::<Random>().unwrap_err(),
+                SeedOutOfRange(4294967296)
+            );
         }
     }
 
@@ -305,10 +377,122 @@ mod tests {
 
     mod argtree {
         use super::*;
-        use crate::method::{OptimizeBuilder, VariationalBuilder};
+        use crate::method::{OptimizeBuilder, SampleAdapt, SampleBuilder, VariationalBuilder};
         use crate::optimize::*;
         use crate::variational::*;
 
+        static HEADER: &str = "# stan_version_major = 2
+# model = bernoulli_model
+# method = sample (Default)
+#   sample
+#     num_samples = 500
+#     num_warmup = 1000 (Default)
+#     save_warmup = 0 (Default)
+#     thin = 1 (Default)
+#     adapt
+#       engaged = 1 (Default)
+#       delta = 0.9
+#     algorithm = hmc (Default)
+#       hmc
+#         engine = nuts (Default)
+#           nuts
+#             max_depth = 10 (Default)
+#         metric = diag_e (Default)
+#     num_chains = 1 (Default)
+# id = 1 (Default)
+# data
+#   file = bernoulli.data.json
+# init = 2 (Default)
+# random
+#   seed = 1234
+# output
+#   file = output.csv (Default)
+# num_threads = 1 (Default)
+# stanc_version = stanc3 v2.33.1
+lp__,accept_stat__,theta
+";
+
+        #[test]
+        fn from_reader() {
+            let lhs = ArgTree::from_reader(HEADER.as_bytes()).unwrap().unwrap();
+            let rhs = ArgTree::builder()
+                .method(
+                    SampleBuilder::new()
+                        .num_samples(500)
+                        .adapt(SampleAdapt::builder().delta(0.9)),
+                )
+                .data(Data::builder().file("bernoulli.data.json"))
+                .random(Random::builder().seed(1234))
+                .build();
+            assert_eq!(lhs, rhs);
+        }
+
+        #[test]
+        fn from_reader_with_provenance() {
+            let x = ArgTree::from_reader_with_provenance(HEADER.as_bytes())
+                .unwrap()
+                .unwrap();
+            assert_eq!(
+                x.tree(),
+                &ArgTree::from_reader(HEADER.as_bytes()).unwrap().unwrap()
+            );
+            assert_eq!(
+                x.explicit(),
+                [
+                    "method.sample.num_samples",
+                    "method.sample.adapt.delta",
+                    "data.file",
+                    "random.seed"
+                ]
+            );
+            assert_eq!(x.is_default("method"), Some(true));
+            assert_eq!(x.is_default("method.sample.adapt.engaged"), Some(true));
+            assert_eq!(
+                x.is_default("method.sample.algorithm.hmc.engine.nuts.max_depth"),
+                Some(true)
+            );
+            assert_eq!(
+                x.is_default("method.sample.algorithm.hmc.metric"),
+                Some(true)
+            );
+            assert_eq!(x.is_default("num_threads"), Some(true));
+            assert_eq!(x.is_default("data.file"), Some(false));
+            assert_eq!(x.is_default("method.sample.adapt.gamma"), None);
+            assert_eq!(x.is_default("stanc_version"), None);
+
+            let x = ArgTree::from_reader_with_provenance("# method = smaple\n".as_bytes()).unwrap();
+            assert!(x.is_err());
+        }
+
+        #[test]
+        fn crlf() {
+            let header = HEADER.replace('\n', "\r\n");
+            assert_eq!(
+                ArgTree::from_reader(header.as_bytes()).unwrap().unwrap(),
+                ArgTree::from_reader(HEADER.as_bytes()).unwrap().unwrap()
+            );
+            let x = ArgTree::from_reader_with_provenance(header.as_bytes())
+                .unwrap()
+                .unwrap();
+            assert_eq!(x.is_default("method.sample.num_samples"), Some(false));
+            assert_eq!(x.is_default("num_threads"), Some(true));
+
+            // Line terminators separate arguments, and do not become part of paths.
+            let lhs = "method=sample\r\ndata file=bernoulli.json\r\noutput file=out.csv\r\n"
+                .parse::<ArgTree>()
+                .unwrap();
+            let rhs = "method=sample data file=bernoulli.json output file=out.csv"
+                .parse::<ArgTree>()
+                .unwrap();
+            assert_eq!(lhs, rhs);
+            assert_eq!(
+                "data\r\n  file=bernoulli.json\n".parse::<Data>().unwrap(),
+                Data {
+                    file: "bernoulli.json".into()
+                }
+            );
+        }
+
         #[test]
         fn from_str() {
             let rhs = ArgTree::default();
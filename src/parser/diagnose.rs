@@ -2,7 +2,10 @@ use crate::diagnose::*;
 use crate::method::{DiagnoseBuilder, Method};
 use crate::parser::*;
 
-impl_from_str! { DiagnoseTest, DiagnoseTestError, diagnose_test_as_type }
+impl_from_str! {
+    DiagnoseTest, DiagnoseTestError, diagnose_test_as_type,
+    "test" => ["gradient"]
+}
 
 fn unify_gradient_fields(pair: Pair<'_, Rule>) -> (Option<f64>, Option<f64>) {
     let pairs = pair.into_inner();
@@ -113,6 +116,9 @@ mod tests {
                 s.parse::<DiagnoseTest>().unwrap(),
                 GradientBuilder::new().epsilon(0.2).error(0.2).build()
             );
+
+            assert_eq!("gradient".parse::<DiagnoseTest>().unwrap(), rhs);
+            assert_eq!(rhs.to_string().parse::<DiagnoseTest>().unwrap(), rhs);
         }
     }
 
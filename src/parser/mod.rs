@@ -18,6 +18,7 @@ use std::str::FromStr;
 #[grammar = "parser/random.pest"]
 #[grammar = "parser/output.pest"]
 #[grammar = "parser/argtree.pest"]
+#[grammar = "parser/model_info.pest"]
 pub struct GrammarParser;
 
 #[derive(Debug, PartialEq)]
@@ -37,7 +38,9 @@ pub enum ParseGrammarError {
     RandomError(usize),
     DataError(usize),
     ArgTreeError(usize),
+    ModelInfoError(usize),
     TopLevelDuplicate(&'static str),
+    SeedOutOfRange(i64),
     MethodNotSpecified,
     RuleError(Rule),
 }
@@ -70,12 +73,16 @@ impl fmt::Display for ParseGrammarError {
             RandomError(n) => ("random", n),
             DataError(n) => ("data", n),
             ArgTreeError(n) => ("top-level", n),
+            ModelInfoError(n) => ("model info", n),
             RuleError(r) => {
                 return write!(f, "internal parsing error: {:?}", r);
             }
             TopLevelDuplicate(s) => {
                 return write!(f, "{} was declared more than once", s);
             }
+            SeedOutOfRange(n) => {
+                return write!(f, "seed must be -1 or in [0, 4294967296), got {}", n);
+            }
             MethodNotSpecified => {
                 return write!(f, "A method must be specified!");
             }
@@ -95,6 +102,66 @@ impl fmt::Display for ParseGrammarError {
 }
 impl std::error::Error for ParseGrammarError {}
 
+impl ParseGrammarError {
+    /// Return the position (byte offset into the input) at which the
+    /// error occurred, if the error is associated with a position.
+    pub fn position(&self) -> Option<usize> {
+        match self {
+            MetricError(n)
+            | EngineError(n)
+            | SampleAdaptError(n)
+            | SampleAlgorithmError(n)
+            | OptimizeAlgorithmError(n)
+            | VariationalAdaptError(n)
+            | VariationalAlgorithmError(n)
+            | DiagnoseTestError(n)
+            | MethodError(n)
+            | OutputError(n)
+            | RandomError(n)
+            | DataError(n)
+            | ArgTreeError(n)
+            | ModelInfoError(n) => Some(*n),
+            IntError(_) | FloatError(_) | TopLevelDuplicate(_) | SeedOutOfRange(_)
+            | MethodNotSpecified | RuleError(_) => None,
+        }
+    }
+
+    /// Render the error with the line and column at which it occurred
+    /// in `input`, which must be the input which produced the error,
+    /// along with the offending line, underlined by a caret. For example,
+    /// ```text
+    /// method does not conform to grammar at position 7
+    ///  --> 1:8
+    ///   |
+    /// 1 | method=smaple
+    ///   |        ^
+    /// ```
+    /// If the error is not associated with a position, this is
+    /// equivalent to the `Display` implementation.
+    pub fn context(&self, input: &str) -> String {
+        let Some(pos) = self.position() else {
+            return self.to_string();
+        };
+        // Positions produced by the parser lie on character boundaries,
+        // but guard against input which is not that which was parsed.
+        let mut pos = pos.min(input.len());
+        while !input.is_char_boundary(pos) {
+            pos -= 1;
+        }
+        let start = input[..pos].rfind('\n').map_or(0, |i| i + 1);
+        let end = input[pos..].find('\n').map_or(input.len(), |i| pos + i);
+        let line = input[start..end].trim_end_matches('\r');
+        let line_no = input[..start].matches('\n').count() + 1;
+        let column = input[start..pos].chars().count() + 1;
+        let gutter = line_no.to_string();
+        let pad = " ".repeat(gutter.len());
+        format!(
+            "{self}\n{pad}--> {line_no}:{column}\n{pad} |\n{gutter} | {line}\n{pad} | {}^",
+            " ".repeat(column - 1)
+        )
+    }
+}
+
 // Common macros
 macro_rules! number_arm {
     ($B:ident, $P:ident, $F:ident, $T:ty) => {
@@ -147,7 +214,47 @@ macro_rules! impl_from_str {
                 }
             }
         }
-    }
+    };
+    // Lenient form: the bare spelling of each variant is accepted,
+    // in addition to the form which appears in the argument tree,
+    // and is parsed as the declaration of the variant, e.g. `lbfgs`
+    // as `algorithm=lbfgs`, hence, any fields take their defaults.
+    { $T:ident, $E:ident, $R:ident, $D:literal => [$($S:literal),+] } => {
+        impl FromStr for $T {
+            type Err = ParseGrammarError;
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s {
+                    $($S)|+ => Self::from_str(&format!("{}={}", $D, s)),
+                    _ => match GrammarParser::parse(Rule::$R, s) {
+                        Ok(mut pair) => {
+                            let pair = pair.next().unwrap().into_inner().next().unwrap();
+                            Self::try_from_pair(pair)
+                        }
+                        Err(e) => error_position!(e, $E),
+                    },
+                }
+            }
+        }
+    };
+}
+
+/// Return one instance of each variant of the enum `$T`, given as
+/// `Variant => expr`, for use in tests which must cover every variant.
+/// Within this crate, `#[non_exhaustive]` does not require a wildcard
+/// arm, hence, the match fails to compile if a variant is added to `$T`
+/// without being listed.
+#[cfg(test)]
+macro_rules! all_variants {
+    ($T:ident { $($V:ident => $e:expr),+ $(,)? }) => {{
+        fn guard(x: &$T) {
+            match x {
+                $($T::$V { .. } => (),)+
+            }
+        }
+        let all: Vec<$T> = vec![$($e),+];
+        all.iter().for_each(guard);
+        all
+    }};
 }
 
 mod argtree;
@@ -156,7 +263,64 @@ mod generate_quantities;
 mod laplace;
 mod log_prob;
 mod method;
+mod model_info;
 mod optimize;
 mod pathfinder;
 mod sample;
 mod variational;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::argtree::ArgTree;
+
+    #[test]
+    fn position() {
+        assert_eq!(MethodError(5).position(), Some(5));
+        assert_eq!(ArgTreeError(0).position(), Some(0));
+        assert_eq!(MethodNotSpecified.position(), None);
+        assert_eq!(TopLevelDuplicate("id").position(), None);
+    }
+
+    #[test]
+    fn context() {
+        let e = MethodError(7);
+        assert_eq!(
+            e.context("method=smaple"),
+            "method does not conform to grammar at position 7\n --> 1:8\n  |\n1 | method=smaple\n  |        ^"
+        );
+
+        let input = "method=sample\nid=1\ndata file=x\nfoo bar\n";
+        let e = ArgTreeError(31);
+        assert_eq!(
+            e.context(input),
+            "top-level does not conform to grammar at position 31\n --> 4:1\n  |\n4 | foo bar\n  | ^"
+        );
+        let e = ArgTreeError(35);
+        assert!(e.context(input).ends_with("4 | foo bar\n  |     ^"));
+
+        // At the end of the input
+        let e = ArgTreeError(4);
+        assert!(e.context("abcd").ends_with("1 | abcd\n  |     ^"));
+
+        // Line numbers with more than one digit widen the gutter.
+        let input = "\n".repeat(9) + "xyz";
+        let e = DataError(10);
+        assert!(e
+            .context(&input)
+            .ends_with("  --> 10:2\n   |\n10 | xyz\n   |  ^"));
+
+        // Multi-byte characters count as a single column.
+        let e = OutputError(9);
+        assert!(e
+            .context("file=\u{e9}\u{e9}x")
+            .ends_with("1 | file=\u{e9}\u{e9}x\n  |        ^"));
+
+        let e = MethodNotSpecified;
+        assert_eq!(e.context("id=1"), e.to_string());
+
+        let input = "method=sample num_samples=x";
+        let e = input.parse::<ArgTree>().unwrap_err();
+        assert!(e.context(input).contains("1 | method=sample num_samples=x"));
+    }
+}
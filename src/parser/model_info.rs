@@ -0,0 +1,251 @@
+use crate::model_info::*;
+use crate::parser::*;
+
+impl FromStr for ModelStructure {
+    type Err = ParseGrammarError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match GrammarParser::parse(Rule::model_info, s) {
+            Ok(mut pair) => {
+                let pair = pair.next().unwrap().into_inner().next().unwrap();
+                Self::try_from_pair(pair)
+            }
+            Err(e) => error_position!(e, ModelInfoError),
+        }
+    }
+}
+
+/// Return the key and value of a `json_pair`.
+fn key_value(pair: Pair<'_, Rule>) -> (String, Pair<'_, Rule>) {
+    let mut pairs = pair.into_inner();
+    let key = string(pairs.next().unwrap());
+    (key, pairs.next().unwrap())
+}
+
+/// Return the code unit of a `\uXXXX` escape, the `\u` of which
+/// has been consumed from `chars`, else `None` if not 4 hex digits.
+fn code_unit(chars: &mut std::str::Chars<'_>) -> Option<u32> {
+    let digits = chars.as_str().get(..4)?;
+    if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let unit = u32::from_str_radix(digits, 16).ok()?;
+    chars.nth(3);
+    Some(unit)
+}
+
+/// Return the character of a `\uXXXX` escape, the `\u` of which has
+/// been consumed from `chars`. A character outside of the basic
+/// multilingual plane is escaped as a surrogate pair, e.g. `\ud83d\ude00`.
+/// An unpaired surrogate yields U+FFFD, as does an invalid escape,
+/// in which case the characters which follow `\u` are kept.
+fn unicode_escape(chars: &mut std::str::Chars<'_>) -> char {
+    let Some(high) = code_unit(chars) else {
+        return char::REPLACEMENT_CHARACTER;
+    };
+    if (0xD800..0xDC00).contains(&high) {
+        let mut rest = chars.clone();
+        if rest.next() == Some('\\') && rest.next() == Some('u') {
+            if let Some(low @ 0xDC00..0xE000) = code_unit(&mut rest) {
+                *chars = rest;
+                let c = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+                return char::from_u32(c).unwrap_or(char::REPLACEMENT_CHARACTER);
+            }
+        }
+    }
+    char::from_u32(high).unwrap_or(char::REPLACEMENT_CHARACTER)
+}
+
+/// Return the unescaped contents of a `json_string`.
+fn string(pair: Pair<'_, Rule>) -> String {
+    let raw = pair.into_inner().next().unwrap().as_str();
+    let mut s = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') => s.push('\n'),
+                Some('t') => s.push('\t'),
+                Some('r') => s.push('\r'),
+                Some('b') => s.push('\u{8}'),
+                Some('f') => s.push('\u{c}'),
+                Some('u') => s.push(unicode_escape(&mut chars)),
+                Some(c) => s.push(c),
+                None => (),
+            },
+            c => s.push(c),
+        }
+    }
+    s
+}
+
+fn expect(pair: &Pair<'_, Rule>, rule: Rule) -> Result<(), ParseGrammarError> {
+    if pair.as_rule() == rule {
+        Ok(())
+    } else {
+        Err(ModelInfoError(pair.as_span().start()))
+    }
+}
+
+fn strings(pair: Pair<'_, Rule>) -> Result<Vec<String>, ParseGrammarError> {
+    expect(&pair, Rule::json_array)?;
+    pair.into_inner()
+        .map(|pair| {
+            expect(&pair, Rule::json_string)?;
+            Ok(string(pair))
+        })
+        .collect()
+}
+
+fn variables(pair: Pair<'_, Rule>) -> Result<Vec<Variable>, ParseGrammarError> {
+    expect(&pair, Rule::json_object)?;
+    pair.into_inner()
+        .map(|pair| {
+            let (name, value) = key_value(pair);
+            expect(&value, Rule::json_object)?;
+            let pos = value.as_span().start();
+            let mut type_name = None;
+            let mut dimensions = None;
+            for pair in value.into_inner() {
+                let (key, value) = key_value(pair);
+                match key.as_str() {
+                    "type" => {
+                        expect(&value, Rule::json_string)?;
+                        type_name = Some(string(value));
+                    }
+                    "dimensions" => {
+                        expect(&value, Rule::json_number)?;
+                        dimensions = Some(value.as_str().parse::<usize>()?);
+                    }
+                    _ => (),
+                }
+            }
+            match (type_name, dimensions) {
+                (Some(type_name), Some(dimensions)) => Ok(Variable {
+                    name,
+                    type_name,
+                    dimensions,
+                }),
+                _ => Err(ModelInfoError(pos)),
+            }
+        })
+        .collect()
+}
+
+impl ModelStructure {
+    fn try_from_pair(pair: Pair<'_, Rule>) -> Result<Self, ParseGrammarError> {
+        match pair.as_rule() {
+            Rule::json_object => {
+                let mut x = Self::default();
+                // Keys which are not recognized are ignored, as later
+                // versions of stanc may add to the output.
+                for pair in pair.into_inner() {
+                    let (key, value) = key_value(pair);
+                    match key.as_str() {
+                        "inputs" => x.inputs = variables(value)?,
+                        "parameters" => x.parameters = variables(value)?,
+                        "transformed parameters" => x.transformed_parameters = variables(value)?,
+                        "generated quantities" => x.generated_quantities = variables(value)?,
+                        "functions" => x.functions = strings(value)?,
+                        "distributions" => x.distributions = strings(value)?,
+                        "included_files" => {
+                            x.included_files = strings(value)?.into_iter().map(Into::into).collect()
+                        }
+                        _ => (),
+                    }
+                }
+                Ok(x)
+            }
+            r => Err(RuleError(r)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INFO: &str = r#"{ "inputs": { "N": { "type": "int", "dimensions": 0},
+  "y": { "type": "int", "dimensions": 1} },
+  "parameters": { "theta": { "type": "real", "dimensions": 0} },
+  "transformed parameters": {  },
+  "generated quantities": { "y_rep": { "type": "int", "dimensions": 1} },
+  "functions": [  ],
+  "distributions": [ "bernoulli_vector", "beta" ],
+  "included_files": [  ] }
+"#;
+
+    #[test]
+    fn from_str() {
+        let x = INFO.parse::<ModelStructure>().unwrap();
+        assert_eq!(x.input_names(), vec!["N", "y"]);
+        assert_eq!(
+            x.parameters,
+            vec![Variable {
+                name: "theta".to_string(),
+                type_name: "real".to_string(),
+                dimensions: 0
+            }]
+        );
+        assert!(x.transformed_parameters.is_empty());
+        assert_eq!(x.generated_quantities[0].dimensions, 1);
+        assert!(x.functions.is_empty());
+        assert_eq!(x.distributions, vec!["bernoulli_vector", "beta"]);
+        assert!(!x.has_no_parameters());
+
+        let x = r#"{"parameters": {}, "stanlib_calls": [], "x": {"y": null}}"#
+            .parse::<ModelStructure>()
+            .unwrap();
+        assert!(x.has_no_parameters());
+        assert_eq!(x, ModelStructure::default());
+
+        let x = r#"{"included_files": ["functions/a.stanfunctions"]}"#
+            .parse::<ModelStructure>()
+            .unwrap();
+        assert_eq!(
+            x.included_files,
+            vec![std::path::PathBuf::from("functions/a.stanfunctions")]
+        );
+    }
+
+    #[test]
+    fn unicode_escapes() {
+        let x = r#"{"functions": ["caf\u00e9", "\ud83d\ude00!", "\u00E9\u0041", "a\/b"]}"#
+            .parse::<ModelStructure>()
+            .unwrap();
+        assert_eq!(x.functions, ["café", "😀!", "éA", "a/b"]);
+
+        // Invalid escapes and unpaired surrogates are replaced.
+        let x = r#"{"functions": ["\ud83d", "\ud83dx", "\ude00", "\u00zz", "\u+0e9", "\u00"]}"#
+            .parse::<ModelStructure>()
+            .unwrap();
+        assert_eq!(
+            x.functions,
+            [
+                "\u{fffd}",
+                "\u{fffd}x",
+                "\u{fffd}",
+                "\u{fffd}00zz",
+                "\u{fffd}+0e9",
+                "\u{fffd}00"
+            ]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!("".parse::<ModelStructure>(), Err(ModelInfoError(0)));
+        assert_eq!(
+            "{\"inputs\": []}".parse::<ModelStructure>(),
+            Err(ModelInfoError(11))
+        );
+        assert_eq!(
+            "{\"inputs\": {\"N\": {\"type\": \"int\"}}}".parse::<ModelStructure>(),
+            Err(ModelInfoError(17))
+        );
+        assert!(
+            "{\"inputs\": {\"N\": {\"type\": \"int\", \"dimensions\": -1}}}"
+                .parse::<ModelStructure>()
+                .is_err()
+        );
+    }
+}
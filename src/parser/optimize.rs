@@ -2,7 +2,10 @@ use crate::method::{Method, OptimizeBuilder};
 use crate::optimize::*;
 use crate::parser::*;
 
-impl_from_str! { OptimizeAlgorithm, OptimizeAlgorithmError, optimize_algorithm_as_type }
+impl_from_str! {
+    OptimizeAlgorithm, OptimizeAlgorithmError, optimize_algorithm_as_type,
+    "algorithm" => ["bfgs", "lbfgs", "newton"]
+}
 
 macro_rules! unify_bfgs_terms {
     ($B:ident, $bfgs:ident) => {
@@ -164,10 +167,23 @@ mod tests {
                 .build();
             assert_eq!(lhs, rhs);
         }
+
+        #[test]
+        fn bare() {
+            for x in [
+                BfgsBuilder::new().build(),
+                LbfgsBuilder::new().build(),
+                OptimizeAlgorithm::Newton,
+            ] {
+                assert_eq!(x.to_string().parse::<OptimizeAlgorithm>().unwrap(), x);
+            }
+            assert!("LBFGS".parse::<OptimizeAlgorithm>().is_err());
+        }
     }
 
     mod method {
         use super::*;
+        use crate::translate::Translate;
 
         #[test]
         fn from_str() {
@@ -195,5 +211,106 @@ mod tests {
             let s = "method=optimize algorithm=lbfgs init_alpha=0.2 iter algorithm=bfgs algorithm iter=10 save_iterations=+1 jacobian=-0 jacobian=+0 jacobian=+1 jacobian save_iterations jacobian save_iterations";
             assert_eq!(s.parse::<Method>().unwrap(), rhs);
         }
+
+        #[test]
+        fn newton() {
+            let rhs = OptimizeBuilder::new()
+                .algorithm(OptimizeAlgorithm::Newton)
+                .iter(50)
+                .build();
+            let s = "method=optimize algorithm=newton iter=50";
+            assert_eq!(s.parse::<Method>().unwrap(), rhs);
+
+            // The unit variant supersedes the field-carrying variants,
+            // and vice versa, as does `fixed_param` for sample.
+            let s = "method=optimize algorithm=bfgs init_alpha=0.1 iter=50 algorithm=newton";
+            assert_eq!(s.parse::<Method>().unwrap(), rhs);
+            let s = "method=optimize algorithm=newton algorithm=bfgs init_alpha=0.1";
+            assert_eq!(
+                s.parse::<Method>().unwrap(),
+                OptimizeBuilder::new()
+                    .algorithm(BfgsBuilder::new().init_alpha(0.1))
+                    .build()
+            );
+        }
+
+        #[test]
+        fn float_round_trip() {
+            // `Display` for `f64` produces the shortest decimal which
+            // parses to the same value, never in scientific notation.
+            for x in [
+                1e-12,
+                0.05,
+                0.1 + 0.2,
+                123456789.123,
+                1e300,
+                f64::MAX,
+                1e-300,
+                f64::MIN_POSITIVE,
+                // subnormals
+                f64::MIN_POSITIVE / 3.0,
+                5e-324,
+            ] {
+                let method = OptimizeBuilder::new()
+                    .algorithm(BfgsBuilder::new().init_alpha(x).tol_obj(-x))
+                    .build();
+                let s = method.to_stmt();
+                let y = s.to_str().unwrap().parse::<Method>().unwrap();
+                let Method::Optimize {
+                    algorithm:
+                        OptimizeAlgorithm::Bfgs {
+                            init_alpha,
+                            tol_obj,
+                            ..
+                        },
+                    ..
+                } = y
+                else {
+                    unreachable!()
+                };
+                assert_eq!(init_alpha.to_bits(), x.to_bits());
+                assert_eq!(tol_obj.to_bits(), (-x).to_bits());
+            }
+
+            // Spellings which CmdStan may echo
+            let f = |s: &str| {
+                let s = format!("method=optimize algorithm=bfgs tol_obj={s}");
+                let Method::Optimize {
+                    algorithm: OptimizeAlgorithm::Bfgs { tol_obj, .. },
+                    ..
+                } = s.parse::<Method>().unwrap()
+                else {
+                    unreachable!()
+                };
+                tol_obj
+            };
+            assert_eq!(f("1e-12"), 1e-12);
+            assert_eq!(f("1E-12"), 1e-12);
+            assert_eq!(f("1e+300"), 1e300);
+            assert_eq!(f("9.9999999999999998e-13"), 1e-12);
+            assert_eq!(f("0.050000000000000003"), 0.05);
+            assert_eq!(f("4.9406564584124654e-324"), 5e-324);
+            assert_eq!(f("-0"), 0.0);
+            assert!(f("-0").is_sign_negative());
+            assert_eq!(f("inf"), f64::INFINITY);
+            assert!(f("nan").is_nan());
+        }
+
+        #[test]
+        fn round_trip() {
+            for algorithm in [
+                OptimizeAlgorithm::Newton,
+                BfgsBuilder::new().tol_obj(1e-9).build(),
+                LbfgsBuilder::new().history_size(7).build(),
+            ] {
+                let x = OptimizeBuilder::new()
+                    .algorithm(algorithm)
+                    .jacobian(true)
+                    .iter(50)
+                    .build();
+                let s = x.to_stmt();
+                assert_eq!(s.to_str().unwrap().parse::<Method>().unwrap(), x);
+            }
+        }
     }
 }
@@ -2,10 +2,19 @@ use crate::method::{Method, SampleBuilder};
 use crate::parser::*;
 use crate::sample::*;
 
-impl_from_str! { Metric, MetricError, metric_as_type }
-impl_from_str! { Engine, EngineError, engine_as_type }
+impl_from_str! {
+    Metric, MetricError, metric_as_type,
+    "metric" => ["unit_e", "diag_e", "dense_e"]
+}
+impl_from_str! {
+    Engine, EngineError, engine_as_type,
+    "engine" => ["static", "nuts"]
+}
 impl_from_str! { SampleAdapt, SampleAdaptError, sample_adapt_as_type }
-impl_from_str! { SampleAlgorithm, SampleAlgorithmError, sample_algorithm_as_type }
+impl_from_str! {
+    SampleAlgorithm, SampleAlgorithmError, sample_algorithm_as_type,
+    "algorithm" => ["hmc", "fixed_param"]
+}
 
 impl Metric {
     fn try_from_pair(pair: Pair<'_, Rule>) -> Result<Self, ParseGrammarError> {
@@ -297,13 +306,42 @@ mod tests {
             assert_eq!("metric=diag_e".parse::<Metric>().unwrap(), DiagE);
             assert_eq!("metric=dense_e".parse::<Metric>().unwrap(), DenseE);
             assert!("".parse::<Metric>().is_err());
+
+            assert_eq!("unit_e".parse::<Metric>().unwrap(), UnitE);
+            assert_eq!("diag_e".parse::<Metric>().unwrap(), DiagE);
+            assert_eq!("dense_e".parse::<Metric>().unwrap(), DenseE);
+            assert!("dense".parse::<Metric>().is_err());
+            assert!("metric=".parse::<Metric>().is_err());
+            assert!("DIAG_E".parse::<Metric>().is_err());
+        }
+
+        #[test]
+        fn display_round_trip() {
+            for x in [UnitE, DiagE, DenseE] {
+                assert_eq!(x.to_string().parse::<Metric>().unwrap(), x);
+            }
         }
     }
 
     mod engine {
         use super::*;
+        use crate::translate::Translate;
+        use std::ffi::OsStr;
         use Engine::*;
 
+        #[test]
+        fn bare() {
+            assert_eq!("nuts".parse::<Engine>().unwrap(), Engine::default());
+            assert_eq!(
+                "static".parse::<Engine>().unwrap(),
+                StaticBuilder::new().build()
+            );
+            assert!("static int_time=3".parse::<Engine>().is_err());
+            for x in [Engine::default(), StaticBuilder::new().build()] {
+                assert_eq!(x.to_string().parse::<Engine>().unwrap(), x);
+            }
+        }
+
         #[test]
         fn from_str() {
             assert_eq!("engine".parse::<Engine>().unwrap(), Engine::default());
@@ -321,6 +359,28 @@ mod tests {
 
             let s = "engine=nuts max_depth=9999999999";
             assert!(s.parse::<Engine>().is_err());
+
+            // The default `int_time`, as echoed by CmdStan (`%.17g`) and as
+            // written by `to_args` (shortest round-trip), denotes `TAU` exactly.
+            let default = StaticBuilder::new().build();
+            assert_eq!(
+                default,
+                Static {
+                    int_time: std::f64::consts::TAU
+                }
+            );
+            for s in [
+                "engine=static",
+                "engine=static int_time",
+                "engine=static int_time=6.2831853071795862",
+                "engine=static int_time=6.283185307179586",
+            ] {
+                assert_eq!(s.parse::<Engine>().unwrap(), default, "{s}");
+            }
+            let args = default.to_args();
+            assert_eq!(args, ["engine=static", "int_time=6.283185307179586"]);
+            let s = args.join(OsStr::new(" "));
+            assert_eq!(s.to_str().unwrap().parse::<Engine>().unwrap(), default);
         }
     }
 
@@ -362,6 +422,14 @@ mod tests {
     mod sample_algorithm {
         use super::*;
 
+        #[test]
+        fn bare() {
+            for x in [SampleAlgorithm::default(), SampleAlgorithm::FixedParam] {
+                assert_eq!(x.to_string().parse::<SampleAlgorithm>().unwrap(), x);
+            }
+            assert!("fixed".parse::<SampleAlgorithm>().is_err());
+        }
+
         #[test]
         fn from_str() {
             let s = "algorithm=hmc stepsize=0.5 metric=unit_e engine=nuts max_depth=5 engine=static int_time=3 engine=nuts max_depth=7 engine=static engine=nuts engine=static metric metric=dense_e stepsize_jitter stepsize stepsize_jitter=0.2 stepsize=0.51 engine=nuts";
@@ -412,6 +480,48 @@ mod tests {
         }
     }
 
+    /// Every variant, including those nested within `SampleAlgorithm`,
+    /// round-trips through `to_stmt` and `FromStr`.
+    #[test]
+    fn exhaustive_round_trip() {
+        use crate::translate::Translate;
+        let metrics = all_variants!(Metric {
+            UnitE => Metric::UnitE,
+            DiagE => Metric::DiagE,
+            DenseE => Metric::DenseE,
+        });
+        let engines = all_variants!(Engine {
+            Static => StaticBuilder::new().int_time(2.5).build(),
+            Nuts => NutsBuilder::new().max_depth(7).build(),
+        });
+        let algorithms = all_variants!(SampleAlgorithm {
+            Hmc => HmcBuilder::new().stepsize(0.5).build(),
+            FixedParam => SampleAlgorithm::FixedParam,
+        });
+        for x in &metrics {
+            let s = x.to_stmt();
+            assert_eq!(s.to_str().unwrap().parse::<Metric>().unwrap(), *x);
+        }
+        for x in &engines {
+            let s = x.to_stmt();
+            assert_eq!(s.to_str().unwrap().parse::<Engine>().unwrap(), *x);
+        }
+        for x in &algorithms {
+            let s = x.to_stmt();
+            assert_eq!(s.to_str().unwrap().parse::<SampleAlgorithm>().unwrap(), *x);
+        }
+        for metric in &metrics {
+            for engine in &engines {
+                let x = HmcBuilder::new()
+                    .metric(metric.clone())
+                    .engine(engine.clone())
+                    .build();
+                let s = x.to_stmt();
+                assert_eq!(s.to_str().unwrap().parse::<SampleAlgorithm>().unwrap(), x);
+            }
+        }
+    }
+
     mod method {
         use super::*;
 
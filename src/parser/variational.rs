@@ -3,7 +3,10 @@ use crate::parser::*;
 use crate::variational::*;
 
 impl_from_str! { VariationalAdapt, VariationalAdaptError, variational_adapt_as_type }
-impl_from_str! { VariationalAlgorithm, VariationalAlgorithmError, variational_algorithm_as_type }
+impl_from_str! {
+    VariationalAlgorithm, VariationalAlgorithmError, variational_algorithm_as_type,
+    "algorithm" => ["meanfield", "fullrank"]
+}
 
 macro_rules! unify_variational_adapt_terms {
     ($B:ident, $P:ident) => {
@@ -116,6 +119,22 @@ mod tests {
             let s = "algorithm=fullrank";
             let lhs = s.parse::<VariationalAlgorithm>().unwrap();
             assert_eq!(lhs, VariationalAlgorithm::FullRank);
+
+            let lhs = "meanfield".parse::<VariationalAlgorithm>().unwrap();
+            assert_eq!(lhs, VariationalAlgorithm::MeanField);
+            let lhs = "fullrank".parse::<VariationalAlgorithm>().unwrap();
+            assert_eq!(lhs, VariationalAlgorithm::FullRank);
+            assert!("full_rank".parse::<VariationalAlgorithm>().is_err());
+        }
+
+        #[test]
+        fn display_round_trip() {
+            for x in [
+                VariationalAlgorithm::MeanField,
+                VariationalAlgorithm::FullRank,
+            ] {
+                assert_eq!(x.to_string().parse::<VariationalAlgorithm>().unwrap(), x);
+            }
         }
     }
 
@@ -0,0 +1,24 @@
+//! The types required for typical usage, such that `use cmdstan::prelude::*;`
+//! suffices to locate CmdStan, compile a program, build an argument tree
+//! and call the model.
+//!
+//! The glob re-exports of the crate root are broader; in particular,
+//! they include [`argtree::Output`](crate::argtree::Output), which would
+//! shadow `std::process::Output`. Here, the sections of the tree are
+//! available only through their builders, e.g. [`OutputBuilder`],
+//! and the error types only through the `error` module, other than
+//! [`Error`] and [`ErrorKind`].
+
+pub use crate::argtree::{
+    ArgTree, ArgTreeBuilder, DataBuilder, Init, OutputBuilder, RandomBuilder, Seed,
+};
+pub use crate::base::{CmdStan, CmdStanModel, CmdStanOutput, StanProgram};
+pub use crate::compile::CompileOptionsBuilder;
+pub use crate::error::{Error, ErrorKind};
+pub use crate::method::{
+    BfgsBuilder, DiagnoseBuilder, GenerateQuantitiesBuilder, GradientBuilder, HmcBuilder,
+    LaplaceBuilder, LbfgsBuilder, LogProbBuilder, Method, Metric, NutsBuilder, OptimizeBuilder,
+    PathfinderBuilder, SampleAdaptBuilder, SampleBuilder, StaticBuilder, VariationalAdaptBuilder,
+    VariationalAlgorithm, VariationalBuilder,
+};
+pub use crate::translate::Translate;
@@ -0,0 +1,149 @@
+//! Progress reporting for calls to a compiled model.
+//!
+//! CmdStan writes a line of the form
+//! `Chain [1] Iteration:  100 / 2000 [  5%]  (Warmup)` to stdout
+//! every `refresh` iterations; the `Chain [k]` prefix is present only
+//! when multiple chains are run by a single process. These lines are
+//! the only source of progress information, hence, if
+//! `output refresh=0` (see [`OutputBuilder::quiet`](crate::OutputBuilder::quiet)),
+//! there is nothing to report.
+
+use std::time::Duration;
+
+/// Tuning of the reader which consumes CmdStan's console output in
+/// [`CmdStanModel::call_with_progress_config`](crate::CmdStanModel::call_with_progress_config).
+/// The default is that of [`CmdStanModel::call_with_progress`](crate::CmdStanModel::call_with_progress):
+/// the buffer of [`std::io::BufReader`], and every update is reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProgressConfig {
+    /// Capacity, in bytes, of the buffer from which lines are read.
+    /// Lines longer than this are still read in full, but with
+    /// additional reads.
+    pub buffer_capacity: usize,
+    /// Minimum time between invocations of the callback. Updates which
+    /// arrive sooner are coalesced: only the most recent is reported,
+    /// once the interval has elapsed or the process exits. The final
+    /// update is always reported.
+    pub poll_interval: Duration,
+}
+
+impl Default for ProgressConfig {
+    fn default() -> Self {
+        Self {
+            buffer_capacity: 8 * 1024,
+            poll_interval: Duration::ZERO,
+        }
+    }
+}
+
+/// Phase of the sampler at the time at which a progress line is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Phase {
+    Warmup,
+    Sampling,
+}
+
+/// A single progress update, as parsed from a line of CmdStan's console output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Progress {
+    /// Chain to which the update pertains. `None` if the process
+    /// is running a single chain.
+    pub chain: Option<u32>,
+    /// Current iteration, counting warmup iterations.
+    pub iteration: u32,
+    /// Total number of iterations, including warmup.
+    pub total: u32,
+    /// Percent complete, as reported by CmdStan.
+    pub percent: u32,
+    /// Phase of the sampler.
+    pub phase: Phase,
+}
+
+impl Progress {
+    /// Try to parse a line of console output. Lines which are not
+    /// progress updates result in `None`.
+    pub fn from_line(line: &str) -> Option<Self> {
+        let line = line.trim();
+        let (chain, rest) = match line.strip_prefix("Chain [") {
+            Some(rest) => {
+                let (chain, rest) = rest.split_once(']')?;
+                (Some(chain.trim().parse::<u32>().ok()?), rest.trim_start())
+            }
+            None => (None, line),
+        };
+        let rest = rest.strip_prefix("Iteration:")?;
+        let (iteration, rest) = rest.split_once('/')?;
+        let iteration = iteration.trim().parse::<u32>().ok()?;
+        let (total, rest) = rest.split_once('[')?;
+        let total = total.trim().parse::<u32>().ok()?;
+        let (percent, rest) = rest.split_once("%]")?;
+        let percent = percent.trim().parse::<u32>().ok()?;
+        let phase = match rest.trim() {
+            "(Warmup)" => Phase::Warmup,
+            "(Sampling)" => Phase::Sampling,
+            _ => return None,
+        };
+        Some(Self {
+            chain,
+            iteration,
+            total,
+            percent,
+            phase,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_line() {
+        assert_eq!(
+            Progress::from_line("Iteration:    1 / 2000 [  0%]  (Warmup)"),
+            Some(Progress {
+                chain: None,
+                iteration: 1,
+                total: 2000,
+                percent: 0,
+                phase: Phase::Warmup,
+            })
+        );
+        assert_eq!(
+            Progress::from_line("Chain [3] Iteration: 1001 / 2000 [ 50%]  (Sampling)\n"),
+            Some(Progress {
+                chain: Some(3),
+                iteration: 1001,
+                total: 2000,
+                percent: 50,
+                phase: Phase::Sampling,
+            })
+        );
+        assert_eq!(
+            Progress::from_line("Chain [12] Iteration: 2000 / 2000 [100%]  (Sampling)"),
+            Some(Progress {
+                chain: Some(12),
+                iteration: 2000,
+                total: 2000,
+                percent: 100,
+                phase: Phase::Sampling,
+            })
+        );
+
+        assert_eq!(Progress::from_line(""), None);
+        assert_eq!(
+            Progress::from_line("Gradient evaluation took 1e-05 seconds"),
+            None
+        );
+        assert_eq!(
+            Progress::from_line("Iteration: 1 / 2000 [  0%]  (Adapting)"),
+            None
+        );
+        assert_eq!(
+            Progress::from_line("Chain [x] Iteration: 1 / 2 [ 50%]  (Warmup)"),
+            None
+        );
+        assert_eq!(Progress::from_line("Iteration: 1 / 2000"), None);
+    }
+}
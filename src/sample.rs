@@ -1,6 +1,7 @@
 use crate::builder::Builder;
+use crate::error::Error;
 use crate::translate::Translate;
-use std::ffi::OsString;
+use std::{ffi::OsString, fmt, fs, io, path::Path};
 
 /// Warmup Adaptation for [`Method::Sample`][crate::method::Method::Sample]
 #[derive(Debug, PartialEq, Clone, Translate, Builder)]
@@ -51,6 +52,24 @@ pub struct SampleAdapt {
     pub window: u32,
 }
 
+impl SampleAdapt {
+    /// Return the number of warmup iterations spanned by the three
+    /// stages of metric adaptation, i.e. `init_buffer + window + term_buffer`.
+    pub fn stages_len(&self) -> u64 {
+        u64::from(self.init_buffer) + u64::from(self.window) + u64::from(self.term_buffer)
+    }
+
+    /// Return `true` if the three stages of metric adaptation fit within
+    /// `num_warmup` iterations. If they do not, CmdStan warns and
+    /// substitutes stages of 15%, 75% and 10% of `num_warmup`, hence, the
+    /// configured buffers are silently ignored. With `num_warmup < 20`,
+    /// CmdStan performs no metric adaptation at all, hence, the stages
+    /// are irrelevant and considered to fit.
+    pub fn stages_fit(&self, num_warmup: i32) -> bool {
+        num_warmup < 20 || self.stages_len() <= num_warmup as u64
+    }
+}
+
 /// Sampling algorithm. Defaults to [`SampleAlgorithm::Hmc`].
 #[derive(Debug, PartialEq, Clone, Translate, Builder)]
 #[non_exhaustive]
@@ -71,6 +90,12 @@ pub enum SampleAlgorithm {
         /// Input file with precomputed Euclidean metric.
         /// Valid values: Path to existing file.
         /// Defaults to `""`.
+        ///
+        /// When multiple chains are run, CmdStan first looks for a
+        /// file per chain, with the chain id inserted prior to the
+        /// suffix, e.g. `metric_1.json`, `metric_2.json`, ..., for
+        /// `metric.json`; if these do not exist, the given file is
+        /// used for all chains. See [`ArgTree::metric_files`](crate::ArgTree::metric_files).
         #[defaults_to = ""]
         metric_file: OsString,
         /// Step size for discrete evolution.
@@ -95,6 +120,68 @@ impl Default for SampleAlgorithm {
     }
 }
 
+/// Emits the spelling of the variant, as it appears in the argument
+/// tree, e.g. `hmc`, without the options of the algorithm.
+impl fmt::Display for SampleAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Hmc { .. } => "hmc",
+            Self::FixedParam => "fixed_param",
+        })
+    }
+}
+
+impl SampleAlgorithm {
+    /// Check that the values are within the ranges accepted by CmdStan,
+    /// including those of the engine (see [`Engine::validate`]).
+    pub fn validate(&self) -> Result<(), Error> {
+        match self {
+            Self::Hmc {
+                engine,
+                stepsize,
+                stepsize_jitter,
+                ..
+            } => {
+                // NaN fails every comparison, hence, is rejected.
+                Error::require(*stepsize > 0.0, "stepsize", "> 0", stepsize)?;
+                Error::require(
+                    (0.0..=1.0).contains(stepsize_jitter),
+                    "stepsize_jitter",
+                    "in [0, 1]",
+                    stepsize_jitter,
+                )?;
+                engine.validate()
+            }
+            Self::FixedParam => Ok(()),
+        }
+    }
+}
+
+impl HmcBuilder {
+    /// Build the `SampleAlgorithm::Hmc` instance, returning an error if
+    /// any value would be rejected by CmdStan (see [`SampleAlgorithm::validate`]).
+    pub fn try_build(self) -> Result<SampleAlgorithm, Error> {
+        let x = self.build();
+        x.validate()?;
+        Ok(x)
+    }
+    /// Configure `engine` as [`Engine::Nuts`], with options set by `f`.
+    pub fn nuts<F>(self, f: F) -> Self
+    where
+        F: FnOnce(NutsBuilder) -> NutsBuilder,
+    {
+        self.engine(f(NutsBuilder::new()))
+    }
+    /// Configure `engine` as [`Engine::Static`], with options set by `f`.
+    /// (`static` being a keyword, the name is qualified.)
+    pub fn static_engine<F>(self, f: F) -> Self
+    where
+        F: FnOnce(StaticBuilder) -> StaticBuilder,
+    {
+        self.engine(f(StaticBuilder::new()))
+    }
+}
+
 /// Engine for Hamiltonian Monte Carlo. Defaults to [`Engine::Nuts`].
 #[derive(Debug, PartialEq, Clone, Translate, Builder)]
 #[non_exhaustive]
@@ -127,6 +214,51 @@ impl Default for Engine {
     }
 }
 
+/// Emits the spelling of the variant, as it appears in the argument
+/// tree, e.g. `nuts`, without the options of the engine.
+impl fmt::Display for Engine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Static { .. } => "static",
+            Self::Nuts { .. } => "nuts",
+        })
+    }
+}
+
+impl Engine {
+    /// Check that the values are within the ranges accepted by CmdStan.
+    pub fn validate(&self) -> Result<(), Error> {
+        match self {
+            Self::Static { int_time } => {
+                Error::require(*int_time > 0.0, "int_time", "> 0", int_time)
+            }
+            Self::Nuts { max_depth } => {
+                Error::require(*max_depth > 0, "max_depth", "> 0", max_depth)
+            }
+        }
+    }
+}
+
+impl StaticBuilder {
+    /// Build the `Engine::Static` instance, returning an error if any value
+    /// would be rejected by CmdStan (see [`Engine::validate`]).
+    pub fn try_build(self) -> Result<Engine, Error> {
+        let x = self.build();
+        x.validate()?;
+        Ok(x)
+    }
+}
+
+impl NutsBuilder {
+    /// Build the `Engine::Nuts` instance, returning an error if any value
+    /// would be rejected by CmdStan (see [`Engine::validate`]).
+    pub fn try_build(self) -> Result<Engine, Error> {
+        let x = self.build();
+        x.validate()?;
+        Ok(x)
+    }
+}
+
 /// Geometry of base manifold. Defaults to [`Metric::DiagE`]
 #[derive(Debug, PartialEq, Default, Clone, Translate)]
 #[declare = "metric"]
@@ -143,6 +275,67 @@ pub enum Metric {
     DenseE,
 }
 
+/// Emits the spelling of the variant, as it appears in the argument
+/// tree, e.g. `diag_e`.
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::UnitE => "unit_e",
+            Self::DiagE => "diag_e",
+            Self::DenseE => "dense_e",
+        })
+    }
+}
+
+/// An inverse metric, in the form which CmdStan reads from a
+/// `metric_file`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum InvMetric {
+    /// The diagonal elements, as for [`Metric::DiagE`].
+    Diag(Vec<f64>),
+    /// The rows of the matrix, as for [`Metric::DenseE`].
+    Dense(Vec<Vec<f64>>),
+}
+
+impl InvMetric {
+    /// Render `self` as JSON, e.g. `{"inv_metric":[1,0.5]}`. Non-finite
+    /// elements have no JSON representation, and are rendered as-is,
+    /// hence, CmdStan will reject the file.
+    pub fn to_json(&self) -> String {
+        fn push_row(s: &mut String, row: &[f64]) {
+            s.push('[');
+            for (i, x) in row.iter().enumerate() {
+                if i > 0 {
+                    s.push(',');
+                }
+                s.push_str(&x.to_string());
+            }
+            s.push(']');
+        }
+        let mut s = String::from("{\"inv_metric\":");
+        match self {
+            Self::Diag(diag) => push_row(&mut s, diag),
+            Self::Dense(rows) => {
+                s.push('[');
+                for (i, row) in rows.iter().enumerate() {
+                    if i > 0 {
+                        s.push(',');
+                    }
+                    push_row(&mut s, row);
+                }
+                s.push(']');
+            }
+        }
+        s.push('}');
+        s
+    }
+
+    /// Write `self`, as JSON, to `path`.
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_json())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -169,6 +362,21 @@ mod tests {
             );
         }
 
+        #[test]
+        fn stages_fit() {
+            let x = SampleAdapt::default();
+            assert_eq!(x.stages_len(), 150);
+            assert!(x.stages_fit(150));
+            assert!(!x.stages_fit(149));
+            assert!(x.stages_fit(19));
+            let x = SampleAdapt::builder()
+                .init_buffer(u32::MAX)
+                .term_buffer(u32::MAX)
+                .build();
+            assert_eq!(x.stages_len(), 2 * u64::from(u32::MAX) + 25);
+            assert!(!x.stages_fit(i32::MAX));
+        }
+
         #[test]
         fn builder() {
             let x = SampleAdapt::builder()
@@ -240,6 +448,39 @@ mod tests {
     mod algorithm {
         use super::*;
 
+        #[test]
+        fn display() {
+            assert_eq!(SampleAlgorithm::default().to_string(), "hmc");
+            assert_eq!(SampleAlgorithm::FixedParam.to_string(), "fixed_param");
+        }
+
+        #[test]
+        fn try_build() {
+            assert!(HmcBuilder::new().try_build().is_ok());
+            assert!(HmcBuilder::new()
+                .stepsize_jitter(1.0)
+                .static_engine(|b| b)
+                .try_build()
+                .is_ok());
+            assert!(SampleAlgorithm::FixedParam.validate().is_ok());
+
+            let f = |b: HmcBuilder| format!("{:#}", b.try_build().unwrap_err());
+            assert_eq!(
+                f(HmcBuilder::new().stepsize(0.0)),
+                "argument validation: stepsize must be > 0, got 0"
+            );
+            assert!(f(HmcBuilder::new().stepsize(f64::NAN)).contains("stepsize"));
+            assert_eq!(
+                f(HmcBuilder::new().stepsize_jitter(1.5)),
+                "argument validation: stepsize_jitter must be in [0, 1], got 1.5"
+            );
+            assert!(f(HmcBuilder::new().stepsize_jitter(-0.1)).contains("stepsize_jitter"));
+            assert_eq!(
+                f(HmcBuilder::new().nuts(|b| b.max_depth(-1))),
+                "argument validation: max_depth must be > 0, got -1"
+            );
+        }
+
         #[test]
         fn builder() {
             let x = HmcBuilder::new()
@@ -274,6 +515,20 @@ mod tests {
             assert_eq!(engine, Engine::Nuts { max_depth: 100 });
         }
 
+        #[test]
+        fn engine() {
+            let x = HmcBuilder::new().nuts(|b| b.max_depth(3)).build();
+            let y = HmcBuilder::new()
+                .engine(NutsBuilder::new().max_depth(3))
+                .build();
+            assert_eq!(x, y);
+            let x = HmcBuilder::new().static_engine(|b| b.int_time(2.5)).build();
+            let y = HmcBuilder::new()
+                .engine(StaticBuilder::new().int_time(2.5))
+                .build();
+            assert_eq!(x, y);
+        }
+
         #[test]
         fn from() {
             let x = HmcBuilder::new();
@@ -345,6 +600,12 @@ mod tests {
     mod engine {
         use super::*;
 
+        #[test]
+        fn display() {
+            assert_eq!(StaticBuilder::new().build().to_string(), "static");
+            assert_eq!(NutsBuilder::new().max_depth(3).build().to_string(), "nuts");
+        }
+
         #[test]
         fn builder() {
             let x = StaticBuilder::new().int_time(2.5).build();
@@ -354,6 +615,22 @@ mod tests {
             assert_eq!(x, Engine::Nuts { max_depth: 100 });
         }
 
+        #[test]
+        fn try_build() {
+            assert!(NutsBuilder::new().try_build().is_ok());
+            assert!(StaticBuilder::new().try_build().is_ok());
+            let e = NutsBuilder::new().max_depth(0).try_build().unwrap_err();
+            assert_eq!(
+                format!("{e:#}"),
+                "argument validation: max_depth must be > 0, got 0"
+            );
+            let e = StaticBuilder::new().int_time(-1.0).try_build().unwrap_err();
+            assert_eq!(
+                format!("{e:#}"),
+                "argument validation: int_time must be > 0, got -1"
+            );
+        }
+
         #[test]
         fn default() {
             let x = StaticBuilder::new().build();
@@ -398,6 +675,13 @@ mod tests {
     mod metric {
         use super::*;
 
+        #[test]
+        fn display() {
+            assert_eq!(Metric::UnitE.to_string(), "unit_e");
+            assert_eq!(Metric::DiagE.to_string(), "diag_e");
+            assert_eq!(Metric::DenseE.to_string(), "dense_e");
+        }
+
         #[test]
         fn default() {
             let x = Metric::default();
@@ -413,5 +697,14 @@ mod tests {
             let x = Metric::DenseE;
             assert_eq!(x.to_args(), vec!["metric=dense_e"]);
         }
+
+        #[test]
+        fn inv_metric_to_json() {
+            let x = InvMetric::Diag(vec![1.0, 0.5, 2e-3]);
+            assert_eq!(x.to_json(), r#"{"inv_metric":[1,0.5,0.002]}"#);
+            let x = InvMetric::Dense(vec![vec![1.0, 0.25], vec![0.25, 3.0]]);
+            assert_eq!(x.to_json(), r#"{"inv_metric":[[1,0.25],[0.25,3]]}"#);
+            assert_eq!(InvMetric::Diag(vec![]).to_json(), r#"{"inv_metric":[]}"#);
+        }
     }
 }
@@ -1,8 +1,24 @@
-use std::ffi::OsString;
+use crate::draws::invalid_data;
+use crate::error::{Error, ErrorKind};
+use std::{
+    ffi::OsString,
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::Path,
+};
+
+/// The percentiles reported by `stansummary` when none are given.
+pub const DEFAULT_PERCENTILES: [f64; 3] = [5.0, 50.0, 95.0];
 
 /// Options for the `stansummary` tool. See
 /// <https://mc-stan.org/docs/cmdstan-guide/stansummary.html> for more
 /// information.
+///
+/// Every flag of `stansummary` (as of CmdStan 2.36) is represented,
+/// other than `--help`. There is no flag to include warmup draws:
+/// `stansummary` always excludes those saved by `save_warmup`. Nor is
+/// there a flag to select chains, as the chains are the files given;
+/// see [`CmdStan::stansummary_chains`](crate::CmdStan::stansummary_chains).
 #[derive(Debug, Clone, PartialEq)]
 #[non_exhaustive]
 pub struct StanSummaryOptions {
@@ -11,8 +27,13 @@ pub struct StanSummaryOptions {
     pub autocorr: Option<i32>,
     /// Write statistics to a csv file.
     pub csv_filename: Option<OsString>,
+    /// Restrict the summary to the named parameters, in the order given;
+    /// if empty, all parameters are summarized. Requires CmdStan 2.33
+    /// or later.
+    pub include_param: Vec<String>,
     /// Percentiles to report as ordered set of comma-separated
-    /// integers from (1,99), inclusive. Default is 5,50,95.
+    /// integers from (1,99), inclusive. Default is
+    /// [`DEFAULT_PERCENTILES`].
     pub percentiles: Vec<f64>,
     /// Significant figures reported. Default is 2. Must be an integer
     /// from (1, 18), inclusive.
@@ -34,6 +55,9 @@ impl StanSummaryOptions {
             s.push(file);
             v.push(s);
         }
+        for param in &self.include_param {
+            v.push(format!("--include_param={}", param).into());
+        }
         let mut s = OsString::with_capacity(14 + 3 * self.percentiles.len());
         s.push("--percentiles=");
         let mut values = self.percentiles.iter();
@@ -48,6 +72,163 @@ impl StanSummaryOptions {
         v.push(format!("--sig_figs={}", self.sig_figs).into());
         v
     }
+
+    /// Return the names of the percentile columns which `stansummary`
+    /// reports for `self.percentiles`, e.g. `["5%", "50%", "95%"]`.
+    pub fn percentile_columns(&self) -> Vec<String> {
+        self.percentiles.iter().map(|p| format!("{}%", p)).collect()
+    }
+
+    /// Check that the percentile columns of `header`, the column names
+    /// of the statistics written by `stansummary` (e.g. to
+    /// `csv_filename`), match [`StanSummaryOptions::percentile_columns`].
+    /// A column is a percentile column if its name ends with `%`.
+    /// A mismatch indicates that the output was produced with
+    /// different options, or that the layout differs across versions
+    /// of CmdStan.
+    pub fn check_percentile_columns<S: AsRef<str>>(&self, header: &[S]) -> Result<(), Error> {
+        let expected = self.percentile_columns();
+        let found: Vec<&str> = header
+            .iter()
+            .map(AsRef::as_ref)
+            .filter(|name| name.ends_with('%'))
+            .collect();
+        if found == expected {
+            Ok(())
+        } else {
+            Err(Error::new(
+                ErrorKind::StanSummary,
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "expected percentile columns {:?}, found {:?}",
+                        expected, found
+                    ),
+                )
+                .into(),
+            ))
+        }
+    }
+}
+
+/// The statistics written by `stansummary` to `csv_filename`: one row
+/// per variable (including the algorithm's, e.g. `lp__`), with one value
+/// per statistic (e.g. `Mean`, `5%`, `R_hat`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    statistics: Vec<String>,
+    names: Vec<String>,
+    rows: Vec<Vec<f64>>,
+}
+
+/// Split a line of CSV into fields, removing the quotes from quoted fields,
+/// which may contain commas (e.g. `"theta[1,2]"`).
+fn split_fields(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    for c in line.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+impl Summary {
+    /// Read the statistics in the format written by `stansummary` to
+    /// `csv_filename` from `reader`. Comment lines (beginning with `#`)
+    /// are skipped. The first line which remains is the header, the
+    /// first column of which holds the names of the variables.
+    ///
+    /// An error of kind `InvalidData` is returned if there is no header,
+    /// if any row has a number of fields which differs from the header,
+    /// or if any value cannot be parsed as a number.
+    pub fn from_reader<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut header: Option<Vec<String>> = None;
+        let mut names = Vec::new();
+        let mut rows = Vec::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = split_fields(line).into_iter();
+            let Some(statistics) = &header else {
+                header = Some(fields.skip(1).map(|s| s.trim().to_string()).collect());
+                continue;
+            };
+            let name = fields.next().unwrap_or_default();
+            let row = fields
+                .map(|field| {
+                    field.trim().parse::<f64>().map_err(|_| {
+                        invalid_data(format!(
+                            "line {}: cannot parse `{}` as a number",
+                            i + 1,
+                            field
+                        ))
+                    })
+                })
+                .collect::<io::Result<Vec<f64>>>()?;
+            if row.len() != statistics.len() {
+                return Err(invalid_data(format!(
+                    "line {}: expected {} values, found {}",
+                    i + 1,
+                    statistics.len(),
+                    row.len()
+                )));
+            }
+            names.push(name.trim().to_string());
+            rows.push(row);
+        }
+        match header {
+            Some(statistics) => Ok(Self {
+                statistics,
+                names,
+                rows,
+            }),
+            None => Err(invalid_data("no header")),
+        }
+    }
+
+    /// Read the file at `path`, written by `stansummary` given
+    /// [`StanSummaryOptions::csv_filename`]; see [`Summary::from_reader`].
+    pub fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        File::open(path)
+            .and_then(|file| Self::from_reader(BufReader::new(file)))
+            .map_err(|e| Error::new(ErrorKind::StanSummary, e.into()))
+    }
+
+    /// Return the names of the statistics, in the order of the header,
+    /// e.g. `["Mean", "MCSE", "StdDev", "5%", "50%", "95%", ...]`.
+    pub fn statistics(&self) -> &[String] {
+        &self.statistics
+    }
+
+    /// Return the names of the variables, in the order of the rows.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Return the statistics of the variable of the given `name`, in
+    /// the order of [`Summary::statistics`], if present.
+    pub fn row(&self, name: &str) -> Option<&[f64]> {
+        self.names
+            .iter()
+            .position(|x| x == name)
+            .map(|i| self.rows[i].as_slice())
+    }
+
+    /// Return the value of `statistic` for the variable of the given `name`,
+    /// if both are present.
+    pub fn get(&self, name: &str, statistic: &str) -> Option<f64> {
+        let j = self.statistics.iter().position(|x| x == statistic)?;
+        self.row(name).map(|row| row[j])
+    }
 }
 
 impl From<StanSummaryOptionsBuilder> for StanSummaryOptions {
@@ -55,17 +236,34 @@ impl From<StanSummaryOptionsBuilder> for StanSummaryOptions {
         x.build()
     }
 }
+/// Enables a builder to be passed directly to
+/// [`CmdStan::stansummary`](crate::CmdStan::stansummary).
+impl From<StanSummaryOptionsBuilder> for Option<StanSummaryOptions> {
+    fn from(x: StanSummaryOptionsBuilder) -> Self {
+        Some(x.build())
+    }
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct StanSummaryOptionsBuilder {
     autocorr: Option<i32>,
     csv_filename: Option<OsString>,
+    include_param: Option<Vec<String>>,
     percentiles: Option<Vec<f64>>,
     sig_figs: Option<u8>,
 }
 impl StanSummaryOptionsBuilder {
     insert_field!(autocorr, i32);
     insert_into_field!(csv_filename, OsString);
+    /// Configure the named option with the given parameter names.
+    pub fn include_param<I, S>(mut self, include_param: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.include_param = Some(include_param.into_iter().map(Into::into).collect());
+        self
+    }
     insert_into_field!(percentiles, Vec<f64>);
     insert_field!(sig_figs, u8);
 
@@ -73,16 +271,20 @@ impl StanSummaryOptionsBuilder {
         Self {
             autocorr: None,
             csv_filename: None,
+            include_param: None,
             percentiles: None,
             sig_figs: None,
         }
     }
     pub fn build(self) -> StanSummaryOptions {
-        let percentiles = self.percentiles.unwrap_or_else(|| vec![5.0, 50.0, 95.0]);
+        let percentiles = self
+            .percentiles
+            .unwrap_or_else(|| DEFAULT_PERCENTILES.to_vec());
         let sig_figs = self.sig_figs.unwrap_or(2);
         StanSummaryOptions {
             autocorr: self.autocorr,
             csv_filename: self.csv_filename,
+            include_param: self.include_param.unwrap_or_default(),
             percentiles,
             sig_figs,
         }
@@ -106,6 +308,7 @@ mod tests {
             let x = StanSummaryOptions {
                 autocorr: None,
                 csv_filename: Some("stansummary.csv".into()),
+                include_param: Vec::new(),
                 percentiles: vec![5.0, 25.0, 50.0, 75.0, 95.0],
                 sig_figs: 6,
             };
@@ -121,6 +324,7 @@ mod tests {
             let x = StanSummaryOptions {
                 autocorr: Some(1),
                 csv_filename: None,
+                include_param: Vec::new(),
                 percentiles: vec![50.0, 75.0],
                 sig_figs: 2,
             };
@@ -132,6 +336,7 @@ mod tests {
             let x = StanSummaryOptions {
                 autocorr: Some(1),
                 csv_filename: Some("hello.csv".into()),
+                include_param: Vec::new(),
                 percentiles: vec![50.0],
                 sig_figs: 4,
             };
@@ -148,6 +353,7 @@ mod tests {
             let x = StanSummaryOptions {
                 autocorr: None,
                 csv_filename: Some("hello.csv".into()),
+                include_param: Vec::new(),
                 percentiles: vec![50.0],
                 sig_figs: 3,
             };
@@ -160,6 +366,82 @@ mod tests {
                 ]
             );
         }
+        #[test]
+        fn include_param() {
+            let x = StanSummaryOptions::builder()
+                .include_param(["theta", "mu"])
+                .build();
+            assert_eq!(
+                x.command_fragment(),
+                vec![
+                    "--include_param=theta",
+                    "--include_param=mu",
+                    "--percentiles=5,50,95",
+                    "--sig_figs=2"
+                ]
+            );
+        }
+
+        #[test]
+        fn check_percentile_columns() {
+            let x = StanSummaryOptions::builder().build();
+            assert_eq!(x.percentile_columns(), vec!["5%", "50%", "95%"]);
+            let header = [
+                "name", "Mean", "MCSE", "StdDev", "5%", "50%", "95%", "R_hat",
+            ];
+            assert!(x.check_percentile_columns(&header).is_ok());
+
+            let x = StanSummaryOptions::builder()
+                .percentiles(vec![2.5, 97.5])
+                .build();
+            assert_eq!(x.percentile_columns(), vec!["2.5%", "97.5%"]);
+            let e = x.check_percentile_columns(&header).unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::StanSummary);
+            assert!(x
+                .check_percentile_columns(&["Mean", "2.5%", "97.5%"])
+                .is_ok());
+        }
+    }
+
+    mod summary {
+        use super::*;
+
+        static SUMMARY_CSV: &str = "# Inference for Stan model: bernoulli_model
+# 1 chains: each with iter=(1000); warmup=(0); thin=(1); 1000 iterations saved.
+name,Mean,MCSE,StdDev,5%,50%,95%,N_Eff,N_Eff/s,R_hat
+\"lp__\",-7.3,0.033,0.75,-8.8,-7.0,-6.8,5.1e+02,1.5e+04,1.0
+\"theta\",0.25,0.0054,0.12,0.077,0.24,0.47,5.2e+02,1.5e+04,nan
+\"z[1,2]\",1,0,0,1,1,1,inf,inf,1
+# Samples were drawn using hmc with nuts.
+";
+
+        #[test]
+        fn from_reader() {
+            let x = Summary::from_reader(SUMMARY_CSV.as_bytes()).unwrap();
+            assert_eq!(x.statistics().len(), 9);
+            assert_eq!(x.statistics()[0], "Mean");
+            assert_eq!(x.names(), ["lp__", "theta", "z[1,2]"]);
+            assert_eq!(x.get("theta", "Mean"), Some(0.25));
+            assert_eq!(x.get("lp__", "N_Eff"), Some(510.0));
+            assert!(x.get("theta", "R_hat").unwrap().is_nan());
+            assert_eq!(x.get("z[1,2]", "N_Eff/s"), Some(f64::INFINITY));
+            assert_eq!(x.get("mu", "Mean"), None);
+            assert_eq!(x.get("theta", "MAD"), None);
+            assert_eq!(x.row("theta").unwrap().len(), 9);
+            let opts = StanSummaryOptions::builder().build();
+            assert!(opts.check_percentile_columns(x.statistics()).is_ok());
+
+            assert!(Summary::from_reader("# nothing\n".as_bytes()).is_err());
+            let e = Summary::from_reader("name,Mean\ntheta,x\n".as_bytes()).unwrap_err();
+            assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+            assert!(Summary::from_reader("name,Mean\ntheta,1,2\n".as_bytes()).is_err());
+        }
+
+        #[test]
+        fn from_csv() {
+            let e = Summary::from_csv("/nonexistent/summary.csv").unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::StanSummary);
+        }
     }
 
     mod builder {
@@ -173,10 +455,18 @@ mod tests {
                 StanSummaryOptions {
                     autocorr: None,
                     csv_filename: None,
+                    include_param: Vec::new(),
                     percentiles: vec![5.0, 50.0, 95.0],
                     sig_figs: 2
                 }
             );
         }
+
+        #[test]
+        fn from() {
+            let b = StanSummaryOptions::builder().sig_figs(3);
+            let x: Option<StanSummaryOptions> = b.clone().into();
+            assert_eq!(x, Some(b.build()));
+        }
     }
 }
@@ -0,0 +1,192 @@
+//! Temporary files which are deleted when no longer referenced.
+//!
+//! Content which is given inline, e.g. the source of a Stan program
+//! given to [`StanProgram::from_source`](crate::StanProgram::from_source),
+//! must be written to a file for CmdStan to read it. Such files are
+//! written to a [`ManagedTempFiles`], a handle to a directory which is
+//! deleted, with its contents, when the last handle is dropped.
+
+use std::{
+    collections::hash_map::RandomState,
+    env, fs,
+    hash::{BuildHasher, Hasher},
+    io,
+    path::{Component, Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::SystemTime,
+};
+
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// The number of names tried before giving up on creation of a directory.
+const ATTEMPTS: usize = 16;
+
+/// Return a suffix which is unpredictable to other processes: the keys
+/// of `RandomState` are randomly seeded, and the time and counter ensure
+/// that successive suffixes differ.
+fn random_suffix() -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_usize(COUNTER.fetch_add(1, Ordering::Relaxed));
+    if let Ok(t) = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+        hasher.write_u128(t.as_nanos());
+    }
+    hasher.finish()
+}
+
+/// Create `dir`, which must not already exist, such that a directory
+/// (or link) placed there by another user is never adopted. On Unix,
+/// the directory is accessible only to the owner.
+fn create_new_dir(dir: &Path) -> io::Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder.create(dir)
+}
+
+/// Return `true` if `name` is a plain file name, i.e. a single component
+/// without separators, such that it cannot refer to a file outside of
+/// the directory onto which it is joined.
+pub(crate) fn is_file_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    matches!(
+        (components.next(), components.next()),
+        (Some(Component::Normal(x)), None) if x == name
+    )
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct TempDir(PathBuf);
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// A handle to a directory of temporary files. Clones share the directory,
+/// which is deleted when the last clone is dropped. Values which are
+/// constructed from inline content hold a handle, such that the files
+/// remain for as long as the value (or anything derived from it, e.g.
+/// a model compiled from a program) is alive.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ManagedTempFiles {
+    dir: Arc<TempDir>,
+}
+
+impl ManagedTempFiles {
+    /// Create a new, empty directory in the temporary directory of the system.
+    /// The name of the directory is random, and creation fails, rather than
+    /// reuse a directory which already exists.
+    pub fn new() -> io::Result<Self> {
+        let temp = env::temp_dir();
+        for _ in 0..ATTEMPTS {
+            let name = format!("cmdstan-rs-{}-{:016x}", process::id(), random_suffix());
+            let dir = temp.join(name);
+            match create_new_dir(&dir) {
+                Ok(()) => {
+                    // Owned from this point on, hence, removed on any error.
+                    let dir = match fs::canonicalize(&dir) {
+                        Ok(canonical) => TempDir(canonical),
+                        Err(e) => {
+                            drop(TempDir(dir));
+                            return Err(e);
+                        }
+                    };
+                    return Ok(Self { dir: Arc::new(dir) });
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "too many temporary directories already exist",
+        ))
+    }
+
+    /// Return the absolute path to the directory.
+    pub fn dir(&self) -> &Path {
+        &self.dir.0
+    }
+
+    /// Write `contents` to the file `name` in the directory, returning
+    /// the absolute path to the file. `name` must be a plain file name,
+    /// without path separators, else an error of kind `InvalidInput`
+    /// is returned.
+    pub fn write<C: AsRef<[u8]>>(&self, name: &str, contents: C) -> io::Result<PathBuf> {
+        if !is_file_name(name) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("not a file name: {name:?}"),
+            ));
+        }
+        let path = self.dir().join(name);
+        fs::write(&path, contents)?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lifetime() {
+        let files = ManagedTempFiles::new().unwrap();
+        let path = files.write("data.json", "{}").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{}");
+        assert!(path.starts_with(files.dir()));
+
+        let other = ManagedTempFiles::new().unwrap();
+        assert_ne!(files, other);
+
+        let clone = files.clone();
+        drop(files);
+        assert!(path.is_file());
+        drop(clone);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn names() {
+        let files = ManagedTempFiles::new().unwrap();
+        let name = files.dir().file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with(&format!("cmdstan-rs-{}-", process::id())));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(files.dir()).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+
+        // An existing directory is never adopted.
+        assert_eq!(
+            create_new_dir(files.dir()).unwrap_err().kind(),
+            io::ErrorKind::AlreadyExists
+        );
+
+        for name in ["a.stan", "data.json", ".hidden"] {
+            assert!(is_file_name(name), "{name}");
+        }
+        for name in [
+            "",
+            ".",
+            "..",
+            "../a.stan",
+            "a/b.stan",
+            "/tmp/a.stan",
+            "./a.stan",
+        ] {
+            assert!(!is_file_name(name), "{name}");
+            let e = files.write(name, "").unwrap_err();
+            assert_eq!(e.kind(), io::ErrorKind::InvalidInput);
+        }
+    }
+}
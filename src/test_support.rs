@@ -0,0 +1,172 @@
+//! Support for tests which exercise the process orchestration without
+//! a real CmdStan installation.
+//!
+//! [`StubCmdStan`] lays out a directory which looks, to this library,
+//! like a CmdStan installation: a `makefile`, whose targets copy shell
+//! scripts into place, and shell scripts which stand in for `stanc`,
+//! `stansummary`, `diagnose` and compiled models. Each script prints
+//! just enough for the checks performed by [`CmdStan::try_from`](crate::CmdStan)
+//! and [`CmdStanModel::try_from`](crate::CmdStanModel) to pass.
+
+use crate::temp::ManagedTempFiles;
+#[cfg(unix)]
+use std::{fs, os::unix::fs::PermissionsExt, path::PathBuf};
+use std::{ops::Deref, path::Path};
+
+/// An empty directory, unique to the calling test, which is deleted,
+/// with its contents, when dropped; it dereferences to its path.
+#[derive(Debug)]
+pub(crate) struct TestDir(ManagedTempFiles);
+
+impl Deref for TestDir {
+    type Target = Path;
+    fn deref(&self) -> &Path {
+        self.0.dir()
+    }
+}
+
+impl AsRef<Path> for TestDir {
+    fn as_ref(&self) -> &Path {
+        self.0.dir()
+    }
+}
+
+/// Create an empty directory, unique to the calling test.
+pub(crate) fn temp_dir() -> TestDir {
+    TestDir(ManagedTempFiles::new().unwrap())
+}
+
+// Bare `make` prints the help, which contains the needle. Targets
+// for the utilities copy the respective script into place, and any
+// file for which a `.stan` file exists is "compiled" by copying
+// the model script.
+#[cfg(unix)]
+const MAKEFILE: &str = "MAKEFLAGS += --no-builtin-rules
+.SUFFIXES:
+.PHONY: help clean-all
+
+help:
+\t@echo 'Build CmdStan utilities:'
+
+bin/%: stub/%
+\t@mkdir -p bin
+\tcp $< $@
+\tchmod +x $@
+
+%:: %.stan
+\tcp stub/model $@
+\tchmod +x $@
+
+clean-all:
+\trm -f bin/stanc bin/stansummary bin/diagnose
+";
+
+#[cfg(unix)]
+const STANC: &str = r#"#!/bin/sh
+if [ "$1" = "--help" ]; then
+    echo "Usage: stanc [option] <model_file>"
+    exit 0
+fi
+echo "$@"
+"#;
+
+#[cfg(unix)]
+const STANSUMMARY: &str = r#"#!/bin/sh
+if [ "$#" -eq 0 ] || [ "$1" = "--help" ]; then
+    echo "Usage: stansummary [OPTIONS] stan_csv_filename [stan_csv_filename]*"
+    exit 0
+fi
+for arg in "$@"; do
+    case "$arg" in
+        --csv_filename=*)
+            printf 'name,Mean,5%%,50%%,95%%\n"theta",0.25,0.1,0.2,0.4\n' > "${arg#--csv_filename=}" ;;
+    esac
+done
+echo "          Mean     MCSE   StdDev"
+echo "lp__       -7.0  0.02     0.70"
+echo "theta      0.25  0.003    0.12"
+"#;
+
+#[cfg(unix)]
+const DIAGNOSE: &str = r#"#!/bin/sh
+if [ "$#" -eq 0 ]; then
+    echo "Usage: diagnose <filename 1> [<filename 2> ... <filename N>]"
+    exit 0
+fi
+echo "Processing complete, no problems detected."
+"#;
+
+/// Writes two draws to the file given by `output file=...`, if
+/// present, else to `output.csv`, much like a compiled model.
+#[cfg(unix)]
+const MODEL: &str = r#"#!/bin/sh
+if [ "$1" = "help" ]; then
+    echo "Bayesian inference with Markov Chain Monte Carlo"
+    exit 0
+fi
+out=output.csv
+in_output=0
+for arg in "$@"; do
+    case "$arg" in
+        output) in_output=1 ;;
+        file=*) if [ "$in_output" -eq 1 ]; then out="${arg#file=}"; fi ;;
+    esac
+done
+echo "Iteration: 1 / 2 [ 50%]  (Warmup)"
+echo "Iteration: 2 / 2 [100%]  (Sampling)"
+echo "Adjust your expectations accordingly!"
+printf '# method = sample\nlp__,theta\n-7.1,0.2\n-6.9,0.3\n' > "$out"
+"#;
+
+/// A directory which stands in for a CmdStan installation.
+#[cfg(unix)]
+#[derive(Debug)]
+pub(crate) struct StubCmdStan {
+    root: TestDir,
+}
+
+#[cfg(unix)]
+impl StubCmdStan {
+    /// Lay out a stub installation in a directory unique to the calling test,
+    /// which is deleted when the stub is dropped. None of the utilities are built.
+    pub(crate) fn new() -> Self {
+        let root = temp_dir();
+        fs::write(root.join("makefile"), MAKEFILE).unwrap();
+        fs::create_dir(root.join("stub")).unwrap();
+        let bernoulli = root.join("examples").join("bernoulli");
+        fs::create_dir_all(&bernoulli).unwrap();
+        fs::write(bernoulli.join("bernoulli.stan"), "").unwrap();
+        fs::write(bernoulli.join("bernoulli.data.json"), "{}").unwrap();
+        let stub = Self { root };
+        for (name, body) in [
+            ("stanc", STANC),
+            ("stansummary", STANSUMMARY),
+            ("diagnose", DIAGNOSE),
+            ("model", MODEL),
+        ] {
+            stub.script(name, body);
+        }
+        stub
+    }
+
+    /// Replace the script which stands in for `name` (one of `stanc`,
+    /// `stansummary`, `diagnose` or `model`) by `body`. This must occur
+    /// prior to the target being built.
+    pub(crate) fn script(&self, name: &str, body: &str) -> &Self {
+        let path = self.root.join("stub").join(name);
+        fs::write(&path, body).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        self
+    }
+
+    /// Write a Stan program at `name` relative to the root.
+    pub(crate) fn program(&self, name: &str) -> PathBuf {
+        let path = self.root.join(name);
+        fs::write(&path, "parameters { real theta; }\n").unwrap();
+        path
+    }
+
+    pub(crate) fn root(&self) -> &Path {
+        &self.root
+    }
+}
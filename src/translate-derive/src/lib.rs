@@ -7,6 +7,8 @@ static UNNAMED_FIELDS: &str = "`Translate` not supported on struct with unnamed
 static ENUM_ZERO_VARIANT: &str = "`Translate` not supported on enum with zero variants";
 static UNION: &str = "`Translate` not supported union";
 static ENUM_REQ_DECLARE: &str = "enum requires `declare`";
+static VEC_NOT_LAST: &str = "`Vec` field must be the last field of a struct";
+static OMIT_IF_NUMBER: &str = "`omit_if` is supported only on number fields";
 
 /// Coarse type categorization, sufficient for this procedural macro.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -16,6 +18,8 @@ enum Type {
     Float,
     String,
     Bool,
+    /// `Vec<OsString>`, the elements of which are emitted verbatim.
+    Vec,
     NotPrimitive,
 }
 impl Type {
@@ -74,14 +78,40 @@ impl From<&Ident> for Type {
     }
 }
 
-#[proc_macro_derive(Translate, attributes(declare))]
+/// Categorize the type of the field `f`.
+fn field_type(f: &syn::Field) -> Type {
+    match &f.ty {
+        syn::Type::Path(path) => match path.path.get_ident() {
+            Some(ident) => Type::from(ident),
+            None if path.path.segments.last().unwrap().ident == "Vec" => Type::Vec,
+            None => unimplemented!("type is not an identifier or `Vec`"),
+        },
+        _ => unimplemented!("type is not `TypePath`"),
+    }
+}
+
+/// Return the fields of a struct which are translated, i.e. those not
+/// marked `#[internal]`.
+fn translated_fields(data: &syn::DataStruct) -> impl Iterator<Item = &syn::Field> {
+    data.fields.iter().filter(|f| !is_internal(&f.attrs[..]))
+}
+
+/// Field attributes:
+///
+/// - `#[internal]`: the field is not an argument, and is not translated.
+/// - `#[omit_if = "method"]`: the (primitive) field is omitted from the
+///   arguments if `self.method()` returns `true`; the tree and statement
+///   forms are unaffected.
+#[proc_macro_derive(Translate, attributes(declare, internal, omit_if))]
 pub fn derive_translate(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
 
     let name = input.ident;
     let decl = get_declare(&input.attrs[..]);
     let append_args = append_args_body(&input.data, decl.clone());
+    let arg_count = arg_count_body(&input.data, decl.is_some());
     let write_tree_offset = write_tree_offset_body(&input.data, decl.clone());
+    let field_paths = field_paths_body(&input.data, decl.clone());
     let write_stmt = write_stmt_body(&input.data, decl);
     let expanded = quote! {
         impl crate::translate::private::Sealed for #name {}
@@ -89,6 +119,9 @@ pub fn derive_translate(input: proc_macro::TokenStream) -> proc_macro::TokenStre
             fn append_args(&self, v: &mut Vec<OsString>) {
                 #append_args
             }
+            fn arg_count(&self) -> usize {
+                #arg_count
+            }
 
             fn write_tree_offset(&self, n: usize, s: &mut OsString) {
                 use std::fmt::Write;
@@ -98,6 +131,9 @@ pub fn derive_translate(input: proc_macro::TokenStream) -> proc_macro::TokenStre
                 use std::fmt::Write;
                 #write_stmt
             }
+            fn field_paths() -> Vec<String> {
+                #field_paths
+            }
         }
     };
     proc_macro::TokenStream::from(expanded)
@@ -113,18 +149,23 @@ fn struct_append_args(data: &syn::DataStruct, decl: Option<String>) -> TokenStre
             } else {
                 quote! {}
             };
-            let iter = data.fields.iter().map(move |f| {
+            let iter = translated_fields(data).map(move |f| {
                 let ident = f.ident.as_ref().unwrap();
-                let ty_ident = match &f.ty {
-                    syn::Type::Path(path) => path.path.get_ident(),
-                    _ => unimplemented!("type is not `TypePath`"),
-                }
-                .unwrap();
-
-                (ident, Type::from(ty_ident))
+                (ident, field_type(f), get_omit_if(&f.attrs[..]))
             });
-            for (ident, ty) in iter {
-                if ty.is_number() {
+            for (ident, ty, omit_if) in iter {
+                if let Some(omit_if) = omit_if {
+                    if !ty.is_number() {
+                        unimplemented!("{}", OMIT_IF_NUMBER);
+                    }
+                    let lhs = format!("{}={{}}", ident);
+                    q = quote! {
+                        #q
+                        if !self.#omit_if() {
+                            v.push(OsString::from(format!(#lhs, self.#ident)));
+                        }
+                    };
+                } else if ty.is_number() {
                     let lhs = format!("{}={{}}", ident);
                     q = quote! {
                         #q
@@ -148,6 +189,11 @@ fn struct_append_args(data: &syn::DataStruct, decl: Option<String>) -> TokenStre
                             s
                         });
                     };
+                } else if ty == Type::Vec {
+                    q = quote! {
+                        #q
+                        v.extend(self.#ident.iter().cloned());
+                    };
                 } else {
                     q = quote! {
                         #q
@@ -263,6 +309,85 @@ fn append_args_body(data: &Data, decl: Option<String>) -> TokenStream {
     }
 }
 
+/// The number of arguments is known for each primitive field,
+/// whereas the remainder must be summed at runtime.
+fn struct_arg_count(data: &syn::DataStruct, has_decl: bool) -> TokenStream {
+    match &data.fields {
+        Fields::Named(_) => {
+            let mut n = has_decl as usize;
+            let mut q = quote! {};
+            for f in translated_fields(data) {
+                let ident = f.ident.as_ref().unwrap();
+                if let Some(omit_if) = get_omit_if(&f.attrs[..]) {
+                    q = quote! {
+                        #q + !self.#omit_if() as usize
+                    };
+                    continue;
+                }
+                match field_type(f) {
+                    Type::Vec => {
+                        q = quote! {
+                            #q + self.#ident.len()
+                        };
+                    }
+                    Type::NotPrimitive => {
+                        q = quote! {
+                            #q + self.#ident.arg_count()
+                        };
+                    }
+                    _ => n += 1,
+                }
+            }
+            quote! {
+                #n #q
+            }
+        }
+        Fields::Unnamed(_) => unimplemented!("{}", UNNAMED_FIELDS),
+        Fields::Unit => unimplemented!("{}", UNIT_STRUCT),
+    }
+}
+
+fn enum_variant_arg_count_body(var: &syn::Variant) -> TokenStream {
+    let me = &var.ident;
+    match &var.fields {
+        Fields::Named(_) => {
+            // One argument for the declaration of the variant.
+            let mut n: usize = 1;
+            let mut idents = Vec::new();
+            for f in var.fields.iter() {
+                let ident = f.ident.as_ref().unwrap();
+                match field_type(f) {
+                    Type::NotPrimitive => idents.push(ident),
+                    _ => n += 1,
+                }
+            }
+            quote! {
+                Self::#me { #(#idents,)* .. } => #n #(+ #idents.arg_count())*
+            }
+        }
+        Fields::Unnamed(_) => unimplemented!("{}", UNNAMED_FIELDS),
+        Fields::Unit => quote! {
+            Self::#me => 1
+        },
+    }
+}
+
+fn arg_count_body(data: &Data, has_decl: bool) -> TokenStream {
+    match *data {
+        Data::Struct(ref data) => struct_arg_count(data, has_decl),
+        Data::Enum(ref data) if data.variants.len() != 0 => {
+            let recurse = data.variants.iter().map(enum_variant_arg_count_body);
+            quote! {
+                match self {
+                    #(#recurse),*
+                }
+            }
+        }
+        Data::Enum(_) => unimplemented!("{}", ENUM_ZERO_VARIANT),
+        Data::Union(_) => unimplemented!("{}", UNION),
+    }
+}
+
 fn write_stmt_body(data: &Data, decl: Option<String>) -> TokenStream {
     match *data {
         Data::Struct(ref data) => struct_write_stmt(data, decl),
@@ -460,21 +585,17 @@ fn struct_write_tree_offset(data: &syn::DataStruct, decl: Option<String>) -> Tok
             } else {
                 quote! {}
             };
-            let mut iter = data
-                .fields
-                .iter()
+            let mut iter = translated_fields(data)
                 .map(move |f| {
                     let ident = f.ident.as_ref().unwrap();
-                    let ty_ident = match &f.ty {
-                        syn::Type::Path(path) => path.path.get_ident(),
-                        _ => unimplemented!("type is not `TypePath`"),
-                    }
-                    .unwrap();
-                    (ident, Type::from(ty_ident))
+                    (ident, field_type(f))
                 })
                 .peekable();
             while let Some((ident, ty)) = iter.next() {
-                let is_not_last = iter.peek().is_some();
+                let is_not_last = iter.peek().is_some_and(|(_, ty)| *ty != Type::Vec);
+                if ty == Type::Vec && iter.peek().is_some() {
+                    unimplemented!("{}", VEC_NOT_LAST);
+                }
                 if ty.is_number() {
                     let lhs = format!("{} = {{}}", ident);
                     q = quote! {
@@ -503,6 +624,20 @@ fn struct_write_tree_offset(data: &syn::DataStruct, decl: Option<String>) -> Tok
                         write!(s, #lhs).unwrap();
                         s.push(&self.#ident);
                     }
+                } else if ty == Type::Vec {
+                    // Each element on a line of its own, preceded by
+                    // the newline which would otherwise follow the
+                    // previous field, as there may be no elements.
+                    q = quote! {
+                        #q
+                        for x in self.#ident.iter() {
+                            s.push("\n");
+                            for _ in 0..n {
+                                s.push(" ");
+                            }
+                            s.push(x);
+                        }
+                    };
                 } else {
                     q = quote! {
                         #q
@@ -534,21 +669,17 @@ fn struct_write_stmt(data: &syn::DataStruct, decl: Option<String>) -> TokenStrea
             } else {
                 quote! {}
             };
-            let mut iter = data
-                .fields
-                .iter()
+            let mut iter = translated_fields(data)
                 .map(move |f| {
                     let ident = f.ident.as_ref().unwrap();
-                    let ty_ident = match &f.ty {
-                        syn::Type::Path(path) => path.path.get_ident(),
-                        _ => unimplemented!("type is not `TypePath`"),
-                    }
-                    .unwrap();
-                    (ident, Type::from(ty_ident))
+                    (ident, field_type(f))
                 })
                 .peekable();
             while let Some((ident, ty)) = iter.next() {
-                let is_not_last = iter.peek().is_some();
+                let is_not_last = iter.peek().is_some_and(|(_, ty)| *ty != Type::Vec);
+                if ty == Type::Vec && iter.peek().is_some() {
+                    unimplemented!("{}", VEC_NOT_LAST);
+                }
                 if ty.is_number() {
                     let lhs = format!("{}={{}}", ident);
                     q = quote! {
@@ -568,6 +699,15 @@ fn struct_write_stmt(data: &syn::DataStruct, decl: Option<String>) -> TokenStrea
                         write!(s, #lhs).unwrap();
                         s.push(&self.#ident);
                     };
+                } else if ty == Type::Vec {
+                    // Likewise, the separator precedes each element.
+                    q = quote! {
+                        #q
+                        for x in self.#ident.iter() {
+                            s.push(" ");
+                            s.push(x);
+                        }
+                    };
                 } else {
                     q = quote! {
                         #q
@@ -686,6 +826,95 @@ fn enum_write_stmt(data: &syn::DataEnum, decl: Option<String>) -> TokenStream {
     }
 }
 
+/// Push the path of each field of `fields` onto `v`, qualified by
+/// `prefix`. The paths of a field which is not primitive are those
+/// of its type, as the type (rather than the field) declares the name.
+fn fields_field_paths(fields: &Fields, prefix: &str) -> TokenStream {
+    let mut q = quote! {};
+    let mut iter = fields
+        .iter()
+        .filter(|f| !is_internal(&f.attrs[..]))
+        .peekable();
+    while let Some(f) = iter.next() {
+        let ident = f.ident.as_ref().unwrap();
+        match field_type(f) {
+            Type::Vec => {
+                if iter.peek().is_some() {
+                    unimplemented!("{}", VEC_NOT_LAST);
+                }
+            }
+            Type::NotPrimitive => {
+                let ty = &f.ty;
+                q = quote! {
+                    #q
+                    v.extend(
+                        <#ty as Translate>::field_paths()
+                            .into_iter()
+                            .map(|x| format!("{}{}", #prefix, x)),
+                    );
+                };
+            }
+            _ => {
+                let path = format!("{}{}", prefix, ident);
+                q = quote! {
+                    #q
+                    v.push(String::from(#path));
+                };
+            }
+        }
+    }
+    q
+}
+
+fn field_paths_body(data: &Data, decl: Option<String>) -> TokenStream {
+    let q = match *data {
+        Data::Struct(ref data) => match &data.fields {
+            Fields::Named(_) => {
+                let prefix = decl.map(|x| format!("{}.", x)).unwrap_or_default();
+                fields_field_paths(&data.fields, &prefix)
+            }
+            Fields::Unnamed(_) => unimplemented!("{}", UNNAMED_FIELDS),
+            Fields::Unit => unimplemented!("{}", UNIT_STRUCT),
+        },
+        Data::Enum(ref data) if data.variants.len() != 0 => {
+            let Some(decl) = decl else {
+                unimplemented!("{}", ENUM_REQ_DECLARE)
+            };
+            let decl = decl.trim_matches('"');
+            // The declaration is itself a field, the value of which is
+            // the variant; the fields of a variant follow its name.
+            let mut q = quote! {
+                v.push(String::from(#decl));
+            };
+            for var in data.variants.iter() {
+                let name = if let Some(name) = get_declare(&var.attrs[..]) {
+                    name.trim_matches('"').to_string()
+                } else {
+                    var.ident.to_string().to_lowercase()
+                };
+                let prefix = format!("{}.{}.", decl, name);
+                let r = match &var.fields {
+                    Fields::Unnamed(_) => unimplemented!("{}", UNNAMED_FIELDS),
+                    fields => fields_field_paths(fields, &prefix),
+                };
+                q = quote! {
+                    #q
+                    #r
+                };
+            }
+            q
+        }
+        Data::Enum(_) => unimplemented!("{}", ENUM_ZERO_VARIANT),
+        Data::Union(_) => unimplemented!("{}", UNION),
+    };
+    quote! {
+        #[allow(unused_mut)]
+        let mut v: Vec<String> = Vec::new();
+        #q
+        v
+    }
+}
+
 fn is_outer(a: &Attribute) -> bool {
     match a.style {
         AttrStyle::Outer => true,
@@ -724,3 +953,35 @@ fn get_declare(input: &[Attribute]) -> Option<String> {
         None
     }
 }
+
+fn is_internal(input: &[Attribute]) -> bool {
+    input.iter().any(|a| {
+        if is_outer(a) && a.meta.path().is_ident("internal") {
+            match &a.meta {
+                Meta::Path(_) => true,
+                _ => unimplemented!("`internal` attribute takes no value."),
+            }
+        } else {
+            false
+        }
+    })
+}
+
+/// Return the method named by `#[omit_if = "..."]`, if any.
+fn get_omit_if(input: &[Attribute]) -> Option<Ident> {
+    let a = input
+        .iter()
+        .find(|a| is_outer(a) && a.meta.path().is_ident("omit_if"))?;
+    match &a.meta {
+        Meta::NameValue(x) => match &x.value {
+            syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(s),
+                ..
+            }) => Some(s.parse().unwrap_or_else(|_| {
+                unimplemented!("`omit_if` value must name a method")
+            })),
+            _ => unimplemented!("`omit_if` value must be a string literal"),
+        },
+        _ => unimplemented!("`omit_if` attribute must be name-value."),
+    }
+}
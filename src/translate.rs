@@ -1,8 +1,16 @@
-use std::ffi::OsString;
+use std::ffi::{OsStr, OsString};
+use std::fmt;
 
 pub use translate_derive::*;
 
 /// This trait is sealed and cannot be implemented for types outside this crate.
+///
+/// Numbers are written using `Display`; for floating point values, this is
+/// the shortest decimal representation which parses to the same value,
+/// without an exponent (e.g. `1e-12` is written as `0.000000000001`).
+/// Hence, every value round-trips through the parser exactly, including
+/// subnormals, although the spelling may differ from that which CmdStan
+/// echoes (e.g. `0.050000000000000003` for `0.05`).
 pub trait Translate: private::Sealed {
     /// Write `self` to `s` as a statement in command line language.
     /// If `s` has sufficient capacity to hold the result, this will
@@ -14,6 +22,10 @@ pub trait Translate: private::Sealed {
     fn write_tree_offset(&self, n: usize, s: &mut OsString);
     /// Translate `self` to command line arguments and append to `v`.
     fn append_args(&self, v: &mut Vec<OsString>);
+    /// Return the number of arguments which [`Translate::append_args`]
+    /// would append, without translating `self`, such that `v` may be
+    /// allocated with the requisite capacity.
+    fn arg_count(&self) -> usize;
 
     /// Write `self` to `s` as a tree.
     /// If `s` has sufficient capacity to hold the result, this will
@@ -35,10 +47,91 @@ pub trait Translate: private::Sealed {
     }
     /// Translate `self` to command line arguments.
     fn to_args(&self) -> Vec<OsString> {
-        let mut v = Vec::new();
+        let mut v = Vec::with_capacity(self.arg_count());
         self.append_args(&mut v);
         v
     }
+    /// Translate `self` to a statement in command line language, with
+    /// each argument quoted as necessary for a POSIX shell, e.g.
+    /// `output 'file=my output.csv'`. Unlike [`Translate::to_stmt`],
+    /// the result may be split by a shell into the original arguments,
+    /// hence, it is suitable for display of a command to be pasted into
+    /// a terminal. To call a program, use [`Translate::to_args`].
+    fn to_stmt_quoted(&self) -> OsString {
+        let mut s = OsString::new();
+        for (i, arg) in self.to_args().iter().enumerate() {
+            if i != 0 {
+                s.push(" ");
+            }
+            push_quoted(&mut s, arg);
+        }
+        s
+    }
+
+    /// Write `self` to `f` as a tree, with any content which is not
+    /// valid UTF-8 replaced as by [`OsStr::to_string_lossy`].
+    fn write_tree_str(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        f.write_str(&self.to_tree().to_string_lossy())
+    }
+    /// Return a wrapper whose `Display` implementation writes `self`
+    /// as a tree, e.g. `writeln!(log, "{}", tree.tree_display())`.
+    fn tree_display(&self) -> TreeDisplay<'_, Self>
+    where
+        Self: Sized,
+    {
+        TreeDisplay(self)
+    }
+
+    /// Return the fully-qualified path of each field which a value of
+    /// this type may write, e.g. `method.sample.num_samples`, in the
+    /// notation of [`ArgTree::to_key_value`](crate::ArgTree::to_key_value).
+    /// The paths of every variant of an enum are included, hence, any
+    /// particular value writes a subset. Elements which are emitted
+    /// verbatim, e.g. [`ArgTree::extra_args`](crate::ArgTree::extra_args),
+    /// have no path.
+    fn field_paths() -> Vec<String>
+    where
+        Self: Sized,
+    {
+        Vec::new()
+    }
+}
+
+/// Displays the tree of the wrapped value, as produced by
+/// [`Translate::tree_display`].
+#[derive(Debug, Clone, Copy)]
+pub struct TreeDisplay<'a, T>(&'a T);
+
+impl<T: Translate> fmt::Display for TreeDisplay<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.write_tree_str(f)
+    }
+}
+
+/// Push `arg` onto `s`, enclosed in single quotes if it contains any
+/// byte which is special to a POSIX shell (or is not ASCII).
+fn push_quoted(s: &mut OsString, arg: &OsStr) {
+    let bytes = arg.as_encoded_bytes();
+    let is_plain = |b: &u8| b.is_ascii_alphanumeric() || b"_-.,/=:+@%".contains(b);
+    if !bytes.is_empty() && bytes.iter().all(is_plain) {
+        s.push(arg);
+        return;
+    }
+    s.push("'");
+    for (i, part) in bytes.split(|b| *b == b'\'').enumerate() {
+        if i != 0 {
+            // Close the quotes, emit an escaped quote, then re-open.
+            s.push("'\\''");
+        }
+        // SAFETY:
+        // - each fragment only contains content that originated
+        //   from `OsStr::as_encoded_bytes`.
+        // - split with ASCII single quote, which is a non-empty UTF-8
+        //   substring.
+        // Thus, the invariants are maintained.
+        s.push(unsafe { OsStr::from_encoded_bytes_unchecked(part) });
+    }
+    s.push("'");
 }
 
 // public within the crate to allow `impl crate::translate::private::Sealed for ...`
@@ -98,6 +191,15 @@ mod tests {
             assert_eq!(x.to_stmt(), "a=1 level1 d=4 e=foo level2 g=5 h=6 b=2");
         }
 
+        #[test]
+        fn arg_count() {
+            let x = example();
+            assert_eq!(x.c.f.arg_count(), 3);
+            assert_eq!(x.c.arg_count(), 6);
+            assert_eq!(x.arg_count(), 8);
+            assert_eq!(x.arg_count(), x.to_args().len());
+        }
+
         #[test]
         fn to_tree() {
             let x = example();
@@ -128,6 +230,20 @@ level1
 b = 2";
             assert_eq!(x.to_tree(), rhs);
         }
+
+        #[test]
+        fn tree_display() {
+            let x = example();
+            assert_eq!(x.tree_display().to_string(), x.to_tree().to_str().unwrap());
+            assert_eq!(
+                format!("{}", x.c.f.tree_display()),
+                "level2\n  g = 5\n  h = 6"
+            );
+
+            let mut s = String::from("tree:\n");
+            x.c.f.write_tree_str(&mut s).unwrap();
+            assert_eq!(s, "tree:\nlevel2\n  g = 5\n  h = 6");
+        }
     }
 
     mod actual {
@@ -142,7 +258,7 @@ b = 2";
             if n != 0 {
                 let cap: usize = v.iter().map(|x| x.len()).sum();
                 let mut s = OsString::with_capacity(cap + n - 1);
-                let mut iter = v.into_iter();
+                let mut iter = v.iter();
                 s.push(iter.next().unwrap());
                 for x in iter {
                     s.push(" ");
@@ -154,7 +270,46 @@ b = 2";
             }
         }
         fn test_args_eq_stmt<T: Translate>(x: &T) {
-            assert_eq!(x.to_stmt(), join_with_ws(&x.to_args()));
+            let args = x.to_args();
+            assert_eq!(x.to_stmt(), join_with_ws(&args));
+            assert_eq!(x.arg_count(), args.len());
+        }
+
+        #[test]
+        fn to_stmt_quoted() {
+            let x = ArgTree::builder()
+                .data(Data::builder().file("my data.json"))
+                .output(Output::builder().file("it's.csv"))
+                .build();
+            let s = x.to_stmt_quoted();
+            let s = s.to_str().unwrap();
+            assert!(s.starts_with("method=sample num_samples=1000 "));
+            assert!(s.contains(" data 'file=my data.json' "));
+            assert!(s.contains(" output 'file=it'\\''s.csv' "));
+            assert!(s.contains(" metric_file= "));
+
+            let x = ArgTree::default();
+            assert_eq!(x.to_stmt_quoted(), x.to_stmt());
+        }
+
+        #[cfg(unix)]
+        #[test]
+        fn to_stmt_quoted_shell() {
+            let x = ArgTree::builder()
+                .data(Data::builder().file("a b\t$HOME `c` \"d\" 'e'"))
+                .init("*")
+                .build();
+            let output = std::process::Command::new("sh")
+                .arg("-c")
+                .arg(format!(
+                    "printf '%s\\n' {}",
+                    x.to_stmt_quoted().to_str().unwrap()
+                ))
+                .output()
+                .unwrap();
+            let stdout = String::from_utf8(output.stdout).unwrap();
+            let args: Vec<&str> = stdout.lines().collect();
+            assert_eq!(args, x.to_args());
         }
 
         #[test]
@@ -364,6 +519,7 @@ method = variational
                 id: 1,
                 init: "2".into(),
                 num_threads: 12,
+                extra_args: Vec::new(),
                 data: Data {
                     file: "bernoulli.data.json".into(),
                 },
@@ -374,6 +530,7 @@ method = variational
                     profile_file: "profile.csv".into(),
                     refresh: 100,
                     sig_figs: 18,
+                    omitted: Default::default(),
                 },
             };
 
@@ -1,6 +1,7 @@
 use crate::builder::Builder;
+use crate::error::Error;
 use crate::translate::Translate;
-use std::ffi::OsString;
+use std::{ffi::OsString, fmt};
 
 /// Variational inference algorithm. Defaults to
 /// [`VariationalAlgorithm::MeanField`].
@@ -35,6 +36,34 @@ pub struct VariationalAdapt {
     pub iter: i32,
 }
 
+impl VariationalAdapt {
+    /// Check that the values are within the ranges accepted by CmdStan.
+    pub fn validate(&self) -> Result<(), Error> {
+        Error::require(self.iter > 0, "(variational) adapt iter", "> 0", self.iter)
+    }
+}
+
+impl VariationalAdaptBuilder {
+    /// Build the `VariationalAdapt` instance, returning an error if any value
+    /// would be rejected by CmdStan (see [`VariationalAdapt::validate`]).
+    pub fn try_build(self) -> Result<VariationalAdapt, Error> {
+        let x = self.build();
+        x.validate()?;
+        Ok(x)
+    }
+}
+
+/// Emits the spelling of the variant, as it appears in the argument
+/// tree, e.g. `meanfield`.
+impl fmt::Display for VariationalAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::MeanField => "meanfield",
+            Self::FullRank => "fullrank",
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -49,6 +78,12 @@ mod tests {
             assert_eq!(x, VariationalAlgorithm::MeanField);
         }
 
+        #[test]
+        fn display() {
+            assert_eq!(VariationalAlgorithm::MeanField.to_string(), "meanfield");
+            assert_eq!(VariationalAlgorithm::FullRank.to_string(), "fullrank");
+        }
+
         #[test]
         fn to_args() {
             let x = VariationalAlgorithm::default();
@@ -94,5 +129,22 @@ mod tests {
                 .build();
             assert_eq!(x.to_args(), vec!["adapt", "engaged=0", "iter=200"]);
         }
+
+        #[test]
+        fn try_build() {
+            let x = VariationalAdaptBuilder::new().iter(1).try_build().unwrap();
+            assert_eq!(x.iter, 1);
+            for n in [0, -1, i32::MIN] {
+                let e = VariationalAdaptBuilder::new()
+                    .iter(n)
+                    .try_build()
+                    .unwrap_err();
+                assert_eq!(e.kind(), crate::error::ErrorKind::Validation);
+            }
+            assert_eq!(
+                VariationalAdaptBuilder::new().try_build().unwrap(),
+                VariationalAdapt::default()
+            );
+        }
     }
 }